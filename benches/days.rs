@@ -0,0 +1,8 @@
+//! `cargo bench [-- <bench options>]`, e.g. `cargo bench -- 16 --save-baseline before`.
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    // cargo passes `--bench` to every bench target
+    let args = std::env::args().skip(1).filter(|arg| arg != "--bench");
+    advent_of_code_2022::cli::run(std::iter::once("bench".to_string()).chain(args))
+}
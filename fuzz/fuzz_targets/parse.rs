@@ -0,0 +1,6 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// the first byte picks the day, the rest is its input
+fuzz_target!(|data: &[u8]| advent_of_code_2022::fuzz::parse_day(data));
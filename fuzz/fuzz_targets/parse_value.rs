@@ -0,0 +1,6 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// the first byte picks the parser, the rest is its text
+fuzz_target!(|data: &[u8]| advent_of_code_2022::fuzz::parse_value(data));
@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::solution::Answer;
+
+pub(crate) const DEFAULT_ANSWERS_PATH: &str = "answers.toml";
+
+/// Expected answers, stored as one `[dayNN]` table per day with `part1` and `part2` keys.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub(crate) struct Answers(BTreeMap<String, DayAnswers>);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DayAnswers {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    part1: Option<Answer>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    part2: Option<Answer>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Status {
+    Pass,
+    Fail { expected: Answer },
+    Missing,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Status::Pass => write!(f, "pass"),
+            Status::Fail { .. } => write!(f, "fail"),
+            Status::Missing => write!(f, "missing"),
+        }
+    }
+}
+
+impl DayAnswers {
+    fn part(&self, part: u8) -> &Option<Answer> {
+        match part {
+            1 => &self.part1,
+            _ => &self.part2,
+        }
+    }
+    fn part_mut(&mut self, part: u8) -> &mut Option<Answer> {
+        match part {
+            1 => &mut self.part1,
+            _ => &mut self.part2,
+        }
+    }
+}
+
+pub(crate) fn day_key(day: u8) -> String {
+    format!("day{:02}", day)
+}
+
+impl Answers {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read answers file {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("invalid answers file {}", path.display()))
+    }
+    /// Like [`Answers::load`], but starts empty when the file doesn't exist yet.
+    pub fn load_or_default(path: &Path) -> anyhow::Result<Self> {
+        if path.exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, toml::to_string(self)?)
+            .with_context(|| format!("cannot write answers file {}", path.display()))
+    }
+    pub fn expected(&self, day: u8, part: u8) -> Option<&Answer> {
+        self.0.get(&day_key(day))?.part(part).as_ref()
+    }
+    /// Parts without an answer, like day 25 part 2, are not recorded.
+    pub fn record(&mut self, day: u8, part: u8, answer: &Answer) {
+        if *answer == Answer::None {
+            return;
+        }
+        *self.0.entry(day_key(day)).or_default().part_mut(part) = Some(answer.clone());
+    }
+    pub fn check(&self, day: u8, part: u8, actual: &Answer) -> Status {
+        match self.expected(day, part) {
+            Some(expected) if expected == actual => Status::Pass,
+            Some(expected) => Status::Fail {
+                expected: expected.clone(),
+            },
+            None if *actual == Answer::None => Status::Pass,
+            None => Status::Missing,
+        }
+    }
+}
+
+/// Line-by-line diff, so multi-line answers like the day 10 CRT screen show where they differ.
+pub(crate) fn diff(expected: &Answer, actual: &Answer) -> String {
+    let expected = expected.to_string();
+    let actual = actual.to_string();
+    let expected: Vec<_> = expected.lines().collect();
+    let actual: Vec<_> = actual.lines().collect();
+    let mut result = String::new();
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) if e == a => result.push_str(&format!("  {}\n", e)),
+            (e, a) => {
+                if let Some(e) = e {
+                    result.push_str(&format!("- {}\n", e));
+                }
+                if let Some(a) = a {
+                    result.push_str(&format!("+ {}\n", a));
+                }
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let mut answers = Answers::default();
+        answers.record(5, 1, &Answer::Text("CMZ".to_string()));
+        answers.record(10, 1, &Answer::Number(13140));
+        answers.record(10, 2, &Answer::Text("##..\n..##".to_string()));
+        answers.record(25, 2, &Answer::None);
+
+        let contents = toml::to_string(&answers).unwrap();
+        assert!(contents.starts_with("[day05]\npart1 = \"CMZ\"\n"));
+        assert!(!contents.contains("day25"));
+        let answers: Answers = toml::from_str(&contents).unwrap();
+
+        assert_eq!(
+            Status::Pass,
+            answers.check(5, 1, &Answer::Text("CMZ".to_string()))
+        );
+        assert_eq!(
+            Status::Missing,
+            answers.check(5, 2, &Answer::Text("MCD".to_string()))
+        );
+        assert_eq!(Status::Pass, answers.check(25, 2, &Answer::None));
+        assert_eq!(
+            Status::Fail {
+                expected: Answer::Number(13140)
+            },
+            answers.check(10, 1, &Answer::Number(13141))
+        );
+        assert_eq!(
+            Status::Pass,
+            answers.check(10, 2, &Answer::Text("##..\n..##".to_string()))
+        );
+        assert_eq!(
+            "  ##..\n- ..##\n+ .###\n",
+            diff(
+                answers.expected(10, 2).unwrap(),
+                &Answer::Text("##..\n.###".to_string())
+            )
+        );
+    }
+}
@@ -0,0 +1,357 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::answers::day_key;
+use crate::cli::BenchOptions;
+use crate::params::Params;
+use crate::registry::Day;
+use crate::runner::format_duration;
+
+pub(crate) const BASELINES_DIR: &str = "target/bench";
+
+/// Fast phases are repeated within a sample until it takes about this long.
+const SAMPLE_TARGET: Duration = Duration::from_millis(10);
+
+/// Median changes smaller than this, or within the standard deviation, count as noise.
+const NOISE_THRESHOLD: f64 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Phase {
+    Parse,
+    Part1,
+    Part2,
+}
+
+impl Phase {
+    fn key(&self) -> &'static str {
+        match self {
+            Phase::Parse => "parse",
+            Phase::Part1 => "part1",
+            Phase::Part2 => "part2",
+        }
+    }
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Phase::Parse => write!(f, "parse"),
+            Phase::Part1 => write!(f, "part 1"),
+            Phase::Part2 => write!(f, "part 2"),
+        }
+    }
+}
+
+/// Summary of the per-iteration times of one phase, in nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Stats {
+    pub median_ns: u64,
+    pub mean_ns: u64,
+    pub stddev_ns: u64,
+    pub min_ns: u64,
+    pub max_ns: u64,
+    pub samples: usize,
+    pub iterations_per_sample: u64,
+}
+
+impl Stats {
+    fn new(samples: &[f64], iterations_per_sample: u64) -> Self {
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let n = sorted.len();
+        let median = if n.is_multiple_of(2) {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+        } else {
+            sorted[n / 2]
+        };
+        let mean = sorted.iter().sum::<f64>() / n as f64;
+        let variance = if n > 1 {
+            sorted.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (n - 1) as f64
+        } else {
+            0.0
+        };
+        Self {
+            median_ns: median.round() as u64,
+            mean_ns: mean.round() as u64,
+            stddev_ns: variance.sqrt().round() as u64,
+            min_ns: sorted[0].round() as u64,
+            max_ns: sorted[n - 1].round() as u64,
+            samples: n,
+            iterations_per_sample,
+        }
+    }
+    fn median(&self) -> Duration {
+        Duration::from_nanos(self.median_ns)
+    }
+}
+
+/// Unlike [`format_duration`], keeps sub-millisecond phases readable.
+fn format_nanos(ns: u64) -> String {
+    match ns {
+        0..=999 => format!("{} ns", ns),
+        1_000..=999_999 => format!("{:.2} µs", ns as f64 / 1e3),
+        _ => format_duration(Duration::from_nanos(ns)),
+    }
+}
+
+/// Runs `f` for at least `warmup` (and at least once), then takes `samples` timed samples.
+fn measure<T>(mut f: impl FnMut() -> T, warmup: Duration, samples: usize) -> Stats {
+    let start = Instant::now();
+    let mut iterations = 0u128;
+    while iterations == 0 || start.elapsed() < warmup {
+        black_box(f());
+        iterations += 1;
+    }
+    let per_iteration = (start.elapsed().as_nanos() / iterations).max(1);
+    let batch = (SAMPLE_TARGET.as_nanos() / per_iteration).clamp(1, 1_000_000) as u64;
+
+    let times: Vec<f64> = (0..samples.max(1))
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..batch {
+                black_box(f());
+            }
+            start.elapsed().as_nanos() as f64 / batch as f64
+        })
+        .collect();
+    Stats::new(&times, batch)
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Change {
+    Improved(f64),
+    Regressed(f64),
+    Unchanged(f64),
+}
+
+impl Change {
+    fn between(baseline: &Stats, current: &Stats) -> Self {
+        let difference = current.median_ns as f64 - baseline.median_ns as f64;
+        let ratio = difference / (baseline.median_ns.max(1) as f64);
+        let noise = baseline.stddev_ns.max(current.stddev_ns) as f64;
+        if ratio.abs() <= NOISE_THRESHOLD || difference.abs() <= noise {
+            Change::Unchanged(ratio)
+        } else if ratio < 0.0 {
+            Change::Improved(ratio)
+        } else {
+            Change::Regressed(ratio)
+        }
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (ratio, label) = match self {
+            Change::Improved(r) => (r, "improved"),
+            Change::Regressed(r) => (r, "regressed"),
+            Change::Unchanged(r) => (r, "no change"),
+        };
+        write!(f, "{:+.1}% ({})", ratio * 100.0, label)
+    }
+}
+
+/// Saved results, stored as one `[dayNN.<phase>]` table per day and phase.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub(crate) struct Baseline(BTreeMap<String, BTreeMap<String, Stats>>);
+
+impl Baseline {
+    pub fn path(name: &str) -> PathBuf {
+        Path::new(BASELINES_DIR).join(format!("{}.toml", name))
+    }
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read baseline {}", path.display()))?;
+        toml::from_str(&contents).with_context(|| format!("invalid baseline {}", path.display()))
+    }
+    /// Like [`Baseline::load`], but starts empty when the file doesn't exist yet.
+    pub fn load_or_default(path: &Path) -> anyhow::Result<Self> {
+        if path.exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("cannot create directory {}", dir.display()))?;
+        }
+        std::fs::write(path, toml::to_string(self)?)
+            .with_context(|| format!("cannot write baseline {}", path.display()))
+    }
+    pub fn get(&self, day: u8, phase: Phase) -> Option<&Stats> {
+        self.0.get(&day_key(day))?.get(phase.key())
+    }
+    pub fn insert(&mut self, day: u8, phase: Phase, stats: Stats) {
+        self.0
+            .entry(day_key(day))
+            .or_default()
+            .insert(phase.key().to_string(), stats);
+    }
+}
+
+struct BenchTable;
+
+impl BenchTable {
+    fn header() -> String {
+        format!(
+            "{:>3}  {:<24}  {:<6}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}  {:>9}  {}",
+            "day", "name", "phase", "median", "mean", "stddev", "min", "max", "samples", "change"
+        )
+    }
+    fn row(day: &Day, phase: Phase, stats: &Stats, change: Option<&Change>) -> String {
+        format!(
+            "{:>3}  {:<24}  {:<6}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}  {:>9}  {}",
+            day.number,
+            day.name(),
+            phase.to_string(),
+            format_nanos(stats.median_ns),
+            format_nanos(stats.mean_ns),
+            format_nanos(stats.stddev_ns),
+            format_nanos(stats.min_ns),
+            format_nanos(stats.max_ns),
+            format!("{}x{}", stats.samples, stats.iterations_per_sample),
+            change.map(|c| c.to_string()).unwrap_or_default()
+        )
+    }
+}
+
+/// Benchmarks the parse step and the requested parts of one day, then prints a row per phase.
+fn bench_day(
+    day: &'static Day,
+    input: &str,
+    options: &BenchOptions,
+    baseline: Option<&Baseline>,
+) -> anyhow::Result<Vec<(Phase, Stats)>> {
+    let parsed = day.solver.parse(input)?;
+    let params = Params::defaults(day.solver.parameters());
+    let mut phases = vec![(
+        Phase::Parse,
+        measure(|| day.solver.parse(input), options.warmup, options.samples),
+    )];
+    for &part in options.parts.iter() {
+        // a part without an answer has nothing to measure
+        match part {
+            1 => parsed.part1(&params),
+            _ => parsed.part2(&params),
+        }
+        .with_context(|| format!("part {}", part))?;
+        let (phase, stats) = match part {
+            1 => (
+                Phase::Part1,
+                measure(|| parsed.part1(&params), options.warmup, options.samples),
+            ),
+            _ => (
+                Phase::Part2,
+                measure(|| parsed.part2(&params), options.warmup, options.samples),
+            ),
+        };
+        phases.push((phase, stats));
+    }
+    for (phase, stats) in phases.iter() {
+        let change = baseline
+            .and_then(|b| b.get(day.number, *phase))
+            .map(|b| Change::between(b, stats));
+        println!("{}", BenchTable::row(day, *phase, stats, change.as_ref()));
+    }
+    Ok(phases)
+}
+
+pub(crate) fn run(options: &BenchOptions) -> anyhow::Result<()> {
+    let baseline = match &options.baseline {
+        Some(name) => Some(Baseline::load(&Baseline::path(name))?),
+        None => None,
+    };
+    let save_path = options.save_baseline.as_deref().map(Baseline::path);
+    let mut saved = match &save_path {
+        Some(path) => Some(Baseline::load_or_default(path)?),
+        None => None,
+    };
+
+    println!("{}", BenchTable::header());
+    let mut total = Duration::ZERO;
+    for &day in options.days.iter() {
+        match options
+            .source
+            .read(day)
+            .and_then(|input| bench_day(day, &input, options, baseline.as_ref()))
+        {
+            Ok(phases) => {
+                for (phase, stats) in phases {
+                    total += stats.median();
+                    if let Some(saved) = saved.as_mut() {
+                        saved.insert(day.number, phase, stats);
+                    }
+                }
+            }
+            Err(e) => println!("{:>3}  {:<24}  error: {:#}", day.number, day.name(), e),
+        }
+    }
+    println!(
+        "\nsum of medians: {}",
+        format_nanos(total.as_nanos() as u64)
+    );
+
+    if let (Some(saved), Some(path)) = (saved, save_path) {
+        saved.save(&path)?;
+        println!("saved baseline to {}", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let stats = Stats::new(&[4.0, 1.0, 3.0, 2.0, 100.0], 1);
+        assert_eq!(3, stats.median_ns);
+        assert_eq!(22, stats.mean_ns);
+        assert_eq!(44, stats.stddev_ns);
+        assert_eq!((1, 100), (stats.min_ns, stats.max_ns));
+        assert_eq!(2, Stats::new(&[1.0, 3.0], 1).median_ns);
+
+        let stats = |median_ns, stddev_ns| Stats {
+            median_ns,
+            stddev_ns,
+            ..Stats::new(&[0.0], 1)
+        };
+        assert!(matches!(
+            Change::between(&stats(1000, 10), &stats(1030, 10)),
+            Change::Unchanged(_)
+        ));
+        assert!(matches!(
+            Change::between(&stats(1000, 10), &stats(1500, 10)),
+            Change::Regressed(_)
+        ));
+        assert!(matches!(
+            Change::between(&stats(1000, 300), &stats(1200, 10)),
+            Change::Unchanged(_)
+        ));
+        assert_eq!(
+            "-50.0% (improved)",
+            Change::between(&stats(1000, 10), &stats(500, 10)).to_string()
+        );
+
+        let mut baseline = Baseline::default();
+        baseline.insert(7, Phase::Part2, stats(1000, 10));
+        let contents = toml::to_string(&baseline).unwrap();
+        assert!(contents.starts_with("[day07.part2]\nmedian_ns = 1000\n"));
+        let baseline: Baseline = toml::from_str(&contents).unwrap();
+        assert_eq!(Some(&stats(1000, 10)), baseline.get(7, Phase::Part2));
+        assert_eq!(None, baseline.get(7, Phase::Parse));
+
+        let stats = measure(|| (0..100).sum::<u64>(), Duration::ZERO, 3);
+        assert_eq!(3, stats.samples);
+        assert_eq!("12.35 µs", format_nanos(12_345));
+        assert!(stats.min_ns <= stats.median_ns && stats.median_ns <= stats.max_ns);
+    }
+}
@@ -0,0 +1,119 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Asks a long search to stop, either explicitly or once its deadline has passed. Clones share
+/// the same flag, so another thread can cancel a search it handed a clone to.
+#[derive(Clone, Debug, Default)]
+pub struct Cancellation {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl Cancellation {
+    /// Only cancelled by [`Cancellation::cancel`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_deadline(deadline: Instant) -> Self {
+        Self {
+            cancelled: Default::default(),
+            deadline: Some(deadline),
+        }
+    }
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self::with_deadline(Instant::now() + timeout)
+    }
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+    /// Checked by the searches as they go; once the deadline has passed, it stays cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return true;
+        }
+        let expired = self.deadline.is_some_and(|d| Instant::now() >= d);
+        if expired {
+            self.cancel();
+        }
+        expired
+    }
+    /// Like [`Cancellation::is_cancelled`], without checking the deadline, for the searches that
+    /// only check it every so often but stop as soon as it has been seen.
+    pub fn was_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+    /// `value` as the result of a search that checked this: timed out if the search saw it
+    /// cancelled, even if it had nothing left to do by then.
+    pub fn outcome<T>(&self, value: T) -> Outcome<T> {
+        match self.cancelled.load(Ordering::Relaxed) {
+            true => Outcome::TimedOut(value),
+            false => Outcome::Finished(value),
+        }
+    }
+}
+
+/// The answer of a search that may have stopped early.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome<T> {
+    Finished(T),
+    /// Cancelled before the search was over, with the best answer found until then.
+    TimedOut(T),
+}
+
+impl<T> Outcome<T> {
+    pub fn value(self) -> T {
+        match self {
+            Outcome::Finished(value) | Outcome::TimedOut(value) => value,
+        }
+    }
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Outcome::Finished(_))
+    }
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Outcome<U> {
+        match self {
+            Outcome::Finished(value) => Outcome::Finished(f(value)),
+            Outcome::TimedOut(value) => Outcome::TimedOut(f(value)),
+        }
+    }
+}
+
+impl<T, E> Outcome<Result<T, E>> {
+    pub fn transpose(self) -> Result<Outcome<T>, E> {
+        match self {
+            Outcome::Finished(value) => Ok(Outcome::Finished(value?)),
+            Outcome::TimedOut(value) => Ok(Outcome::TimedOut(value?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let cancellation = Cancellation::new();
+        assert!(!cancellation.is_cancelled());
+        assert_eq!(Outcome::Finished(3), cancellation.outcome(3));
+        cancellation.clone().cancel();
+        assert!(cancellation.is_cancelled());
+        assert_eq!(Outcome::TimedOut(3), cancellation.outcome(3));
+
+        let expired = Cancellation::with_timeout(Duration::ZERO);
+        // not timed out until a search notices
+        assert_eq!(Outcome::Finished(1), expired.outcome(1));
+        assert!(!expired.was_cancelled());
+        assert!(expired.is_cancelled());
+        assert!(expired.was_cancelled());
+        assert_eq!(Outcome::TimedOut(2), expired.outcome(1).map(|n| n + 1));
+        assert!(!Cancellation::with_timeout(Duration::from_secs(60)).is_cancelled());
+        assert_eq!(4, Outcome::TimedOut(4).value());
+        assert!(!Outcome::TimedOut(4).is_finished());
+        assert_eq!(
+            Ok(Outcome::TimedOut(5)),
+            Outcome::TimedOut(Ok::<_, ()>(5)).transpose()
+        );
+        assert_eq!(Err(()), Outcome::Finished(Err::<u8, _>(())).transpose());
+    }
+}
@@ -0,0 +1,855 @@
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+
+use crate::answers::DEFAULT_ANSWERS_PATH;
+use crate::bench::{self, BASELINES_DIR};
+use crate::export::{self, ExportSettings, Palette};
+use crate::generate::{self, DEFAULT_SIZE};
+use crate::params::{Overrides, Params, Value};
+use crate::progress;
+use crate::registry::{self, Day};
+use crate::repl;
+use crate::runner;
+use crate::visualize;
+
+pub(crate) const INPUTS_DIR_ENV: &str = "AOC_INPUTS_DIR";
+
+pub(crate) const USAGE: &str = "\
+usage: advent-of-code-2022 [day] [options]
+       advent-of-code-2022 list
+       advent-of-code-2022 bench [day] [options]
+       advent-of-code-2022 generate [day] [options]
+       advent-of-code-2022 repl [day] [options]
+
+  day                    day number (1-25) or puzzle name, e.g. `monkey-math`;
+                         defaults to the latest day
+  --all                  run every day
+  -p, --part <1|2>       only run the given part
+  -i, --input <path>     read the puzzle input from a file
+  --stdin                read the puzzle input from stdin
+  --inputs-dir <dir>     directory holding `input<day>.txt` files
+                         (default: $AOC_INPUTS_DIR, then `input`)
+  --verify               check the answers against the answers file
+  --record               write the answers to the answers file
+  --answers <path>       answers file (default: `answers.toml`)
+  --format <text|json>   output format (default: text)
+  -j, --jobs <n>         number of threads running days and parts in parallel
+                         (default: one per CPU)
+  --param <name=value>   change a puzzle constant of a single day, e.g.
+                         `15 --param row=10`; `list` shows every parameter
+  --params <path>        read parameters from a file with `[day15]` tables
+  --timeout <secs>       stop the long searches (days 16 and 19) of each part
+                         after <secs> seconds, with the best answer found so far
+  --progress <bar|log>   report the progress of the long searches as a bar on
+                         stderr, or as `progress` log records (RUST_LOG=progress)
+  --visualize            watch the simulation of a single day in the terminal
+                         (space play/pause, arrows step and change speed, q quit)
+  --export <dir>         write the simulation of a single day to images in <dir>
+  -h, --help             print this help
+
+--export renders every character of a frame as a square of pixels:
+  --image-format <f>     gif (one animation), png or ppm (one file per frame)
+                         (default: gif)
+  --cell-size <n>        pixels per character (default: 4)
+  --palette <colours>    colour overrides, e.g. `o=ffcc00,#=808080`
+  --every <n>            only export every nth frame, and the last one
+  --frame-delay <ms>     time each GIF frame is shown for (default: 100)
+
+bench measures parsing and each part of every day (or the given day):
+  --samples <n>          timed samples per phase (default: 10)
+  --warmup <ms>          warmup time per phase (default: 500)
+  --save-baseline <name> save the results to `target/bench/<name>.toml`
+  --baseline <name>      compare the results against a saved baseline
+
+generate prints a random puzzle input for the day (or writes every day with --all):
+  --seed <n>             seed of the random generator (default: printed on stderr)
+  --size <n>             roughly how many lines, valves, monkeys and so on, or
+                         the width of a map (default: 10)
+  --output <dir>         write `input<day>.txt` files to <dir> instead
+
+repl parses the day's input and reads commands from stdin, such as `part1` or
+`step 10` for day 23; `help` lists the commands of the day";
+
+pub(crate) enum Command {
+    Help,
+    List,
+    Run(RunOptions),
+    Bench(BenchOptions),
+    Visualize(VisualizeOptions),
+    Export(ExportOptions),
+    Generate(GenerateOptions),
+    Repl(ReplOptions),
+}
+
+pub(crate) struct RunOptions {
+    pub days: Vec<&'static Day>,
+    pub parts: Vec<u8>,
+    pub source: InputSource,
+    pub answers_mode: Option<AnswersMode>,
+    pub answers_path: PathBuf,
+    pub format: OutputFormat,
+    /// Thread pool size; `None` uses one thread per CPU.
+    pub jobs: Option<usize>,
+    pub params: Overrides,
+    /// Time each part has before its search gives up.
+    pub timeout: Option<Duration>,
+    pub progress: Option<ProgressOutput>,
+}
+
+pub(crate) struct BenchOptions {
+    pub days: Vec<&'static Day>,
+    pub parts: Vec<u8>,
+    pub source: InputSource,
+    pub samples: usize,
+    pub warmup: Duration,
+    pub save_baseline: Option<String>,
+    pub baseline: Option<String>,
+}
+
+pub(crate) struct VisualizeOptions {
+    pub day: &'static Day,
+    /// Without `-p`, every part that has a visualisation.
+    pub part: Option<u8>,
+    pub source: InputSource,
+}
+
+pub(crate) struct ExportOptions {
+    pub day: &'static Day,
+    /// Without `-p`, every part that has a visualisation.
+    pub part: Option<u8>,
+    pub source: InputSource,
+    pub dir: PathBuf,
+    pub settings: ExportSettings,
+}
+
+pub(crate) struct GenerateOptions {
+    pub days: Vec<&'static Day>,
+    pub seed: u64,
+    pub size: usize,
+    /// Directory for the `input<day>.txt` files; `None` prints the input.
+    pub output: Option<PathBuf>,
+}
+
+pub(crate) struct ReplOptions {
+    pub day: &'static Day,
+    pub source: InputSource,
+    /// Used by the `part1` and `part2` commands.
+    pub params: Params,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum ProgressOutput {
+    Bar,
+    Log,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum AnswersMode {
+    Verify,
+    Record,
+}
+
+pub(crate) enum InputSource {
+    Stdin,
+    File(PathBuf),
+    Directory(PathBuf),
+}
+
+impl InputSource {
+    pub fn read(&self, day: &Day) -> Result<String> {
+        match self {
+            InputSource::Stdin => {
+                let mut input = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut input)
+                    .context("cannot read input from stdin")?;
+                Ok(input)
+            }
+            InputSource::File(path) => std::fs::read_to_string(path)
+                .with_context(|| format!("cannot read input file {}", path.display())),
+            InputSource::Directory(dir) => {
+                let path = dir.join(day.input_file_name());
+                std::fs::read_to_string(&path)
+                    .with_context(|| format!("cannot read input file {}", path.display()))
+            }
+        }
+    }
+}
+
+impl Command {
+    pub fn from_args(args: impl Iterator<Item = String>) -> Result<Self> {
+        Self::parse(args, std::env::var(INPUTS_DIR_ENV).ok())
+    }
+    fn parse(
+        mut args: impl Iterator<Item = String>,
+        inputs_dir_env: Option<String>,
+    ) -> Result<Self> {
+        let mut bench = false;
+        let mut generate = false;
+        let mut repl = false;
+        let mut seed = None;
+        let mut size = None;
+        let mut output = None;
+        let mut samples = None;
+        let mut warmup = None;
+        let mut save_baseline = None;
+        let mut baseline = None;
+        let mut day = None;
+        let mut all = false;
+        let mut part = None;
+        let mut input = None;
+        let mut stdin = false;
+        let mut inputs_dir = None;
+        let mut answers_mode = None;
+        let mut answers_path = None;
+        let mut format = OutputFormat::Text;
+        let mut jobs = None;
+        let mut visualize = false;
+        let mut export_dir = None;
+        let mut export_settings = ExportSettings::default();
+        let mut export_only = false;
+        let mut params_file = None;
+        let mut params = vec![];
+        let mut timeout = None;
+        let mut progress = None;
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .with_context(|| format!("missing value for {}", name))
+            };
+            export_only |= matches!(
+                arg.as_str(),
+                "--image-format" | "--cell-size" | "--palette" | "--every" | "--frame-delay"
+            );
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(Self::Help),
+                "list" if day.is_none() && !bench && !generate && !repl => return Ok(Self::List),
+                "bench" if day.is_none() && !bench && !generate && !repl => bench = true,
+                "generate" if day.is_none() && !bench && !generate && !repl => generate = true,
+                "repl" if day.is_none() && !bench && !generate && !repl => repl = true,
+                "--all" => all = true,
+                "--stdin" => stdin = true,
+                "--visualize" => visualize = true,
+                "-p" | "--part" => {
+                    part = Some(match value(&arg)?.as_str() {
+                        "1" => 1,
+                        "2" => 2,
+                        other => bail!("invalid part '{}', expected 1 or 2", other),
+                    })
+                }
+                "-i" | "--input" => input = Some(PathBuf::from(value(&arg)?)),
+                "--inputs-dir" => inputs_dir = Some(PathBuf::from(value(&arg)?)),
+                "--verify" | "--record" => {
+                    let mode = match arg.as_str() {
+                        "--verify" => AnswersMode::Verify,
+                        _ => AnswersMode::Record,
+                    };
+                    if answers_mode.as_ref().is_some_and(|m| *m != mode) {
+                        bail!("cannot combine --verify with --record");
+                    }
+                    answers_mode = Some(mode);
+                }
+                "--answers" => answers_path = Some(PathBuf::from(value(&arg)?)),
+                "--format" => {
+                    format = match value(&arg)?.as_str() {
+                        "text" => OutputFormat::Text,
+                        "json" => OutputFormat::Json,
+                        other => bail!("invalid format '{}', expected text or json", other),
+                    }
+                }
+                "-j" | "--jobs" => {
+                    jobs = Some(match value(&arg)?.parse::<usize>() {
+                        Ok(n) if n > 0 => n,
+                        _ => bail!("--jobs expects a positive number"),
+                    })
+                }
+                "--samples" => {
+                    samples = Some(match value(&arg)?.parse::<usize>() {
+                        Ok(n) if n > 0 => n,
+                        _ => bail!("--samples expects a positive number"),
+                    })
+                }
+                "--warmup" => {
+                    let ms = value(&arg)?;
+                    let ms = ms.parse().with_context(|| {
+                        format!("invalid warmup '{}', expected milliseconds", ms)
+                    })?;
+                    warmup = Some(Duration::from_millis(ms));
+                }
+                "--param" => {
+                    let param = value(&arg)?;
+                    let Some((name, value)) = param.split_once('=') else {
+                        bail!("invalid parameter '{}', expected <name>=<value>", param);
+                    };
+                    params.push((name.trim().to_string(), value.parse::<Value>()?));
+                }
+                "--params" => params_file = Some(PathBuf::from(value(&arg)?)),
+                "--progress" => {
+                    progress = Some(match value(&arg)?.as_str() {
+                        "bar" => ProgressOutput::Bar,
+                        "log" => ProgressOutput::Log,
+                        other => bail!("invalid progress '{}', expected bar or log", other),
+                    })
+                }
+                "--timeout" => {
+                    let secs = value(&arg)?;
+                    timeout = Some(
+                        secs.parse()
+                            .ok()
+                            .and_then(|s| Duration::try_from_secs_f64(s).ok())
+                            .with_context(|| {
+                                format!("invalid timeout '{}', expected seconds", secs)
+                            })?,
+                    );
+                }
+                "--export" => export_dir = Some(PathBuf::from(value(&arg)?)),
+                "--image-format" => export_settings.format = value(&arg)?.parse()?,
+                "--cell-size" => {
+                    export_settings.cell_size = match value(&arg)?.parse::<usize>() {
+                        Ok(n) if n > 0 => n,
+                        _ => bail!("--cell-size expects a positive number"),
+                    }
+                }
+                "--palette" => {
+                    export_settings.palette = Palette::default().with_overrides(&value(&arg)?)?
+                }
+                "--every" => {
+                    export_settings.every = match value(&arg)?.parse::<usize>() {
+                        Ok(n) if n > 0 => n,
+                        _ => bail!("--every expects a positive number"),
+                    }
+                }
+                "--frame-delay" => {
+                    let ms = value(&arg)?;
+                    let ms = ms.parse().with_context(|| {
+                        format!("invalid frame delay '{}', expected milliseconds", ms)
+                    })?;
+                    export_settings.delay = Duration::from_millis(ms);
+                }
+                "--seed" => {
+                    let n = value(&arg)?;
+                    seed = Some(
+                        n.parse()
+                            .with_context(|| format!("invalid seed '{}', expected a number", n))?,
+                    );
+                }
+                "--size" => {
+                    size = Some(match value(&arg)?.parse::<usize>() {
+                        Ok(n) if n > 0 => n,
+                        _ => bail!("--size expects a positive number"),
+                    })
+                }
+                "--output" => output = Some(PathBuf::from(value(&arg)?)),
+                "--save-baseline" => save_baseline = Some(value(&arg)?),
+                "--baseline" => baseline = Some(value(&arg)?),
+                other if other.starts_with('-') => bail!("unknown option '{}'", other),
+                other => {
+                    if day.is_some() {
+                        bail!("unexpected argument '{}'", other);
+                    }
+                    day = Some(
+                        registry::find(other)
+                            .with_context(|| format!("day {} not found", other))?,
+                    );
+                }
+            }
+        }
+
+        let days = match (all, day) {
+            (true, Some(_)) => bail!("cannot combine a day with --all"),
+            (true, None) => registry::all().iter().collect(),
+            (false, Some(day)) => vec![day],
+            (false, None) if bench => registry::all().iter().collect(),
+            (false, None) => vec![registry::latest()],
+        };
+        if generate && (part.is_some() || input.is_some() || stdin || inputs_dir.is_some()) {
+            bail!("generate cannot be combined with --part, --input, --stdin or --inputs-dir");
+        }
+        let source = match (stdin, input) {
+            (true, Some(_)) => bail!("cannot combine --stdin with --input"),
+            (true, None) => InputSource::Stdin,
+            (false, Some(path)) => InputSource::File(path),
+            (false, None) => InputSource::Directory(
+                inputs_dir
+                    .or_else(|| inputs_dir_env.map(PathBuf::from))
+                    .unwrap_or_else(|| PathBuf::from("input")),
+            ),
+        };
+        if days.len() > 1 && !matches!(source, InputSource::Directory(_)) {
+            bail!("--all and bench read every day from the inputs directory");
+        }
+        let parts = part.map(|p| vec![p]).unwrap_or_else(|| vec![1, 2]);
+        let mut overrides = match params_file {
+            Some(path) => Overrides::load(&path)?,
+            None => Overrides::default(),
+        };
+        if !params.is_empty() && days.len() > 1 {
+            bail!("--param changes a single day, use --params <path> for several");
+        }
+        for (name, value) in params {
+            overrides.set(days[0], name, value);
+        }
+        for day in days.iter() {
+            overrides.for_day(day)?;
+        }
+        if !overrides.is_empty() {
+            if bench || visualize || export_dir.is_some() || generate {
+                bail!("--param and --params only apply to running days");
+            }
+            if answers_mode.is_some() {
+                bail!("the answers file holds the answers for the default parameters, so --verify and --record cannot be combined with --param or --params");
+            }
+        }
+
+        if (timeout.is_some() || progress.is_some())
+            && (bench || visualize || export_dir.is_some() || generate || repl)
+        {
+            bail!("--timeout and --progress only apply to running days");
+        }
+
+        if export_dir.is_some() {
+            if bench || visualize || days.len() > 1 {
+                bail!("--export writes a single day and cannot be combined with --visualize");
+            }
+            if answers_mode.is_some() || format != OutputFormat::Text || jobs.is_some() {
+                bail!("--export cannot be combined with --verify, --record, --format or --jobs");
+            }
+        } else if export_only {
+            bail!("--image-format, --cell-size, --palette, --every and --frame-delay only apply to --export");
+        }
+        if visualize {
+            if bench || days.len() > 1 {
+                bail!("--visualize shows a single day");
+            }
+            if matches!(source, InputSource::Stdin) {
+                bail!(
+                    "--visualize reads keys from the terminal, so cannot read the input from stdin"
+                );
+            }
+            if answers_mode.is_some() || format != OutputFormat::Text || jobs.is_some() {
+                bail!("--visualize cannot be combined with --verify, --record, --format or --jobs");
+            }
+        }
+        if repl {
+            if bench || generate || days.len() > 1 {
+                bail!("repl explores a single day");
+            }
+            if matches!(source, InputSource::Stdin) {
+                bail!("repl reads commands from stdin, so cannot read the input from it");
+            }
+            if part.is_some() || visualize || export_dir.is_some() {
+                bail!("repl cannot be combined with --part, --visualize or --export");
+            }
+            if answers_mode.is_some() || format != OutputFormat::Text || jobs.is_some() {
+                bail!("repl cannot be combined with --verify, --record, --format or --jobs");
+            }
+            if samples.is_some()
+                || warmup.is_some()
+                || save_baseline.is_some()
+                || baseline.is_some()
+            {
+                bail!("--samples, --warmup, --save-baseline and --baseline only apply to bench");
+            }
+            if seed.is_some() || size.is_some() || output.is_some() {
+                bail!("--seed, --size and --output only apply to generate");
+            }
+            return Ok(Self::Repl(ReplOptions {
+                day: days[0],
+                source,
+                params: overrides.for_day(days[0])?,
+            }));
+        }
+        if bench {
+            if answers_mode.is_some() || format != OutputFormat::Text || jobs.is_some() {
+                bail!("bench cannot be combined with --verify, --record, --format or --jobs");
+            }
+            return Ok(Self::Bench(BenchOptions {
+                days,
+                parts,
+                source,
+                samples: samples.unwrap_or(10),
+                warmup: warmup.unwrap_or(Duration::from_millis(500)),
+                save_baseline,
+                baseline,
+            }));
+        }
+        if samples.is_some() || warmup.is_some() || save_baseline.is_some() || baseline.is_some() {
+            bail!("--samples, --warmup, --save-baseline and --baseline only apply to bench");
+        }
+        if generate {
+            if answers_mode.is_some() || format != OutputFormat::Text || jobs.is_some() {
+                bail!("generate cannot be combined with --verify, --record, --format or --jobs");
+            }
+            if visualize || export_dir.is_some() {
+                bail!("generate cannot be combined with --visualize or --export");
+            }
+            return Ok(Self::Generate(GenerateOptions {
+                days,
+                seed: seed.unwrap_or_else(|| {
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |d| d.as_nanos() as u64)
+                }),
+                size: size.unwrap_or(DEFAULT_SIZE),
+                output,
+            }));
+        }
+        if seed.is_some() || size.is_some() || output.is_some() {
+            bail!("--seed, --size and --output only apply to generate");
+        }
+        if let Some(dir) = export_dir {
+            return Ok(Self::Export(ExportOptions {
+                day: days[0],
+                part,
+                source,
+                dir,
+                settings: export_settings,
+            }));
+        }
+        if visualize {
+            return Ok(Self::Visualize(VisualizeOptions {
+                day: days[0],
+                part,
+                source,
+            }));
+        }
+
+        Ok(Self::Run(RunOptions {
+            days,
+            parts,
+            source,
+            answers_mode,
+            answers_path: answers_path.unwrap_or_else(|| PathBuf::from(DEFAULT_ANSWERS_PATH)),
+            format,
+            jobs,
+            params: overrides,
+            timeout,
+            progress,
+        }))
+    }
+}
+
+/// Entry point of the `advent-of-code-2022` binary.
+pub fn main() -> anyhow::Result<()> {
+    run(std::env::args().skip(1))
+}
+
+/// Runs the command described by `args`, which exclude the program name.
+pub fn run(args: impl Iterator<Item = String>) -> anyhow::Result<()> {
+    let options = match Command::from_args(args)? {
+        Command::Help => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Command::List => {
+            for day in registry::all() {
+                println!("{:>2} {}", day.number, day.name());
+                for parameter in day.solver.parameters() {
+                    println!(
+                        "     {:<16} {:<24} {}",
+                        parameter.name,
+                        parameter.default_value().to_string(),
+                        parameter.description
+                    );
+                }
+            }
+            return Ok(());
+        }
+        Command::Run(options) => {
+            progress::set_sink(match options.progress {
+                Some(ProgressOutput::Bar) => Some(Arc::new(progress::Bar)),
+                Some(ProgressOutput::Log) => Some(Arc::new(progress::Log)),
+                None => None,
+            });
+            options
+        }
+        Command::Bench(options) => {
+            println!(
+                "benchmarking {} day(s), {} samples per phase (baselines are kept in {})\n",
+                options.days.len(),
+                options.samples,
+                BASELINES_DIR
+            );
+            return bench::run(&options);
+        }
+        Command::Visualize(options) => return visualize::run(&options),
+        Command::Export(options) => return export::run(&options),
+        Command::Generate(options) => return generate::run(&options),
+        Command::Repl(options) => return repl::run(&options),
+    };
+    match options.format {
+        OutputFormat::Text => {
+            let start = Instant::now();
+            let result = runner::run(&options);
+            println!("main took {} ms.", start.elapsed().as_millis());
+            result
+        }
+        OutputFormat::Json => runner::run_json(&options),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::export::ImageFormat;
+
+    fn parse(args: &str, env: Option<&str>) -> Result<Command> {
+        Command::parse(
+            args.split_whitespace().map(|s| s.to_string()),
+            env.map(|s| s.to_string()),
+        )
+    }
+
+    fn run_options(args: &str, env: Option<&str>) -> RunOptions {
+        match parse(args, env).unwrap() {
+            Command::Run(options) => options,
+            _ => panic!("expected a run command for '{}'", args),
+        }
+    }
+
+    #[test]
+    fn test1() {
+        let options = run_options("", None);
+        assert_eq!(25, options.days[0].number);
+        assert_eq!(vec![1, 2], options.parts);
+        assert!(matches!(options.source, InputSource::Directory(dir) if dir == Path::new("input")));
+
+        let options = run_options("7 --part 2 --input my.txt", None);
+        assert_eq!(7, options.days[0].number);
+        assert_eq!(vec![2], options.parts);
+        assert!(matches!(options.source, InputSource::File(path) if path == Path::new("my.txt")));
+
+        let options = run_options("monkey-math --stdin -p 1", None);
+        assert_eq!(21, options.days[0].number);
+        assert!(matches!(options.source, InputSource::Stdin));
+
+        let options = run_options("--all", Some("env_dir"));
+        assert_eq!(25, options.days.len());
+        assert!(
+            matches!(options.source, InputSource::Directory(dir) if dir == Path::new("env_dir"))
+        );
+
+        let options = run_options("--all --inputs-dir flag_dir", Some("env_dir"));
+        assert!(
+            matches!(options.source, InputSource::Directory(dir) if dir == Path::new("flag_dir"))
+        );
+
+        let options = run_options("5 --verify", None);
+        assert_eq!(Some(AnswersMode::Verify), options.answers_mode);
+        assert_eq!(Path::new("answers.toml"), options.answers_path);
+        let options = run_options("--all --record --answers a.toml", None);
+        assert_eq!(Some(AnswersMode::Record), options.answers_mode);
+        assert_eq!(Path::new("a.toml"), options.answers_path);
+        assert_eq!(OutputFormat::Text, options.format);
+        let options = run_options("--all --format json --verify", None);
+        assert_eq!(OutputFormat::Json, options.format);
+        assert_eq!(None, options.jobs);
+        assert_eq!(Some(4), run_options("--all -j 4", None).jobs);
+
+        assert!(matches!(parse("list", None).unwrap(), Command::List));
+        assert!(matches!(parse("3 --help", None).unwrap(), Command::Help));
+
+        assert!(parse("--part 3", None).is_err());
+        assert!(parse("--input", None).is_err());
+        assert!(parse("26", None).is_err());
+        assert!(parse("1 2", None).is_err());
+        assert!(parse("--all 4", None).is_err());
+        assert!(parse("--all --stdin", None).is_err());
+        assert!(parse("--stdin --input a.txt", None).is_err());
+        assert!(parse("--verbose", None).is_err());
+        assert!(parse("--verify --record", None).is_err());
+        assert!(parse("--format yaml", None).is_err());
+    }
+
+    #[test]
+    fn test2() {
+        let Command::Bench(options) = parse("bench", None).unwrap() else {
+            panic!("expected a bench command");
+        };
+        assert_eq!(25, options.days.len());
+        assert_eq!(10, options.samples);
+        assert_eq!(None, options.baseline);
+        let Command::Bench(options) = parse(
+            "bench 16 -p 1 --samples 3 --warmup 0 --baseline old --save-baseline new",
+            None,
+        )
+        .unwrap() else {
+            panic!("expected a bench command");
+        };
+        assert_eq!(16, options.days[0].number);
+        assert_eq!(vec![1], options.parts);
+        assert_eq!(3, options.samples);
+        assert_eq!(Duration::ZERO, options.warmup);
+        assert_eq!(Some("old"), options.baseline.as_deref());
+        assert_eq!(Some("new"), options.save_baseline.as_deref());
+        assert!(parse("bench --samples 0", None).is_err());
+        assert!(parse("bench --verify", None).is_err());
+        assert!(parse("bench --stdin", None).is_err());
+        assert!(parse("5 --baseline old", None).is_err());
+        assert!(parse("--all --jobs 0", None).is_err());
+        assert!(parse("bench --jobs 2", None).is_err());
+    }
+
+    #[test]
+    fn test3() {
+        let Command::Visualize(options) = parse("14 --visualize -p 2", None).unwrap() else {
+            panic!("expected a visualize command");
+        };
+        assert_eq!(14, options.day.number);
+        assert_eq!(Some(2), options.part);
+        assert!(parse("--all --visualize", None).is_err());
+        assert!(parse("bench 14 --visualize", None).is_err());
+        assert!(parse("14 --visualize --stdin", None).is_err());
+        assert!(parse("14 --visualize --verify", None).is_err());
+    }
+
+    #[test]
+    fn test4() {
+        let Command::Export(options) = parse(
+            "17 --export out --image-format png --cell-size 2 --every 10 --palette #=ffffff",
+            None,
+        )
+        .unwrap() else {
+            panic!("expected an export command");
+        };
+        assert_eq!(17, options.day.number);
+        assert_eq!(Path::new("out"), options.dir);
+        assert_eq!(ImageFormat::Png, options.settings.format);
+        assert_eq!(
+            (2, 10),
+            (options.settings.cell_size, options.settings.every)
+        );
+        assert_eq!(Duration::from_millis(100), options.settings.delay);
+        assert!(parse("--all --export out", None).is_err());
+        assert!(parse("14 --export out --visualize", None).is_err());
+        assert!(parse("14 --cell-size 2", None).is_err());
+        assert!(parse("14 --export out --image-format bmp", None).is_err());
+        assert!(parse("14 --export out --palette o=red", None).is_err());
+
+        // the CRT only draws part 2, which is all that day 10 exports without -p
+        let dir = std::env::temp_dir().join(format!("aoc-cli-export-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("input10.txt");
+        std::fs::write(
+            &input,
+            generate::generate(registry::find("10").unwrap(), 1, 5),
+        )
+        .unwrap();
+        let args = format!("10 --export {} -i {}", dir.display(), input.display());
+        let Command::Export(options) = parse(&args, None).unwrap() else {
+            panic!("expected an export command");
+        };
+        assert_eq!(None, options.part);
+        export::run(&options).unwrap();
+        assert!(dir.join("day10-part2.gif").exists());
+        assert!(!dir.join("day10-part1.gif").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test5() {
+        let options = run_options("15 --param row=10 --param bound=20", None);
+        let params = options.params.for_day(options.days[0]).unwrap();
+        assert_eq!((10, 20), (params.integer("row"), params.integer("bound")));
+        let options = run_options("10 --param cycles=1,2", None);
+        assert_eq!(
+            &[1, 2],
+            options
+                .params
+                .for_day(options.days[0])
+                .unwrap()
+                .list("cycles")
+        );
+        assert!(run_options("--all", None).params.is_empty());
+        assert!(parse("15 --param rows=10", None).is_err());
+        assert!(parse("15 --param row", None).is_err());
+        assert!(parse("15 --param bound=-1", None).is_err());
+        assert!(parse("1 --param row=10", None).is_err());
+        assert!(parse("--all --param row=10", None).is_err());
+        assert!(parse("15 --param row=10 --verify", None).is_err());
+        assert!(parse("bench 15 --param row=10", None).is_err());
+        assert!(parse("--all --params missing.toml", None).is_err());
+    }
+
+    #[test]
+    fn test6() {
+        let Command::Generate(options) = parse("generate 16 --seed 3 --size 20", None).unwrap()
+        else {
+            panic!("expected a generate command");
+        };
+        assert_eq!(
+            (16, 3, 20, None),
+            (
+                options.days[0].number,
+                options.seed,
+                options.size,
+                options.output
+            )
+        );
+        let Command::Generate(options) = parse("generate --all --output out", None).unwrap() else {
+            panic!("expected a generate command");
+        };
+        assert_eq!(25, options.days.len());
+        assert_eq!(DEFAULT_SIZE, options.size);
+        assert_eq!(Some(Path::new("out")), options.output.as_deref());
+        assert!(parse("generate 16 --size 0", None).is_err());
+        assert!(parse("generate 16 -p 1", None).is_err());
+        assert!(parse("generate 16 --verify", None).is_err());
+        assert!(parse("16 --seed 3", None).is_err());
+        assert!(parse("bench generate", None).is_err());
+    }
+
+    #[test]
+    fn test7() {
+        assert_eq!(None, run_options("16", None).timeout);
+        assert_eq!(
+            Some(Duration::from_millis(2500)),
+            run_options("--all --timeout 2.5", None).timeout
+        );
+        assert!(parse("16 --timeout -1", None).is_err());
+        assert!(parse("16 --timeout soon", None).is_err());
+        assert!(parse("bench 16 --timeout 1", None).is_err());
+        assert_eq!(None, run_options("16", None).progress);
+        assert_eq!(
+            Some(ProgressOutput::Log),
+            run_options("19 --progress log", None).progress
+        );
+        assert!(parse("19 --progress dots", None).is_err());
+        assert!(parse("generate 19 --progress bar", None).is_err());
+    }
+
+    #[test]
+    fn test8() {
+        let Command::Repl(options) = parse("repl 15 -i ex.txt --param row=10", None).unwrap()
+        else {
+            panic!("expected a repl command");
+        };
+        assert_eq!(15, options.day.number);
+        assert_eq!(10, options.params.integer("row"));
+        assert!(matches!(options.source, InputSource::File(_)));
+        let Command::Repl(options) = parse("repl", None).unwrap() else {
+            panic!("expected a repl command");
+        };
+        assert_eq!(registry::latest().number, options.day.number);
+        assert!(parse("repl --all", None).is_err());
+        assert!(parse("repl 23 --stdin", None).is_err());
+        assert!(parse("repl 23 -p 1", None).is_err());
+        assert!(parse("repl 23 --format json", None).is_err());
+        assert!(parse("repl 19 --timeout 1", None).is_err());
+        assert!(parse("repl generate", None).is_err());
+        assert!(parse("bench repl", None).is_err());
+    }
+}
@@ -1,73 +1,345 @@
-use std::str::FromStr;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::fmt::{Display, Formatter};
+use std::io::BufRead;
 
 use log::info;
+use rand::rngs::StdRng;
+use rand::Rng;
+use thiserror::Error;
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input1.txt").unwrap();
+use crate::parse_error::{parse_number, ParseError};
+use crate::repl::Explore;
+use crate::solution::Solution;
 
-    println!("most calories: {}", solve(&input));
-    println!("sum top 3 calories: {}", solve_2(&input));
+const DAY: u8 = 1;
+
+/// Calories per bucket of the histogram of the parsed input.
+const BUCKET_WIDTH: u64 = 10_000;
+
+pub struct CalorieCounting;
+
+impl Solution for CalorieCounting {
+    type Input = Summary;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        let summary = summarize(input.as_bytes(), 3, BUCKET_WIDTH)?;
+        info!("elves:\n{}", summary);
+        Ok(summary)
+    }
+    fn part1(summary: &Self::Input) -> u64 {
+        summary.max().unwrap_or_default()
+    }
+    fn part2(summary: &Self::Input) -> u64 {
+        summary.top().iter().sum()
+    }
+    fn explore(summary: &Self::Input) -> Option<Box<dyn Explore + '_>> {
+        Some(Box::new(SummaryExplorer(summary)))
+    }
+    /// At least three elves, so that part 2 has a top three.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        (0..size.max(3))
+            .map(|_| {
+                (0..rng.gen_range(1..=10))
+                    .map(|_| rng.gen_range(1000..=60_000).to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ReadError {
+    #[error("cannot read the inventory: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+/// The calories each elf carries, read a line at a time, so that memory stays the same however
+/// long the inventory is.
+pub struct ElfTotals<R> {
+    reader: R,
+    line: String,
+    line_number: usize,
+}
+
+impl<R: BufRead> ElfTotals<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            line_number: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for ElfTotals<R> {
+    type Item = Result<u64, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut total = None;
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return total.map(Ok),
+                Ok(_) => self.line_number += 1,
+                Err(error) => return Some(Err(error.into())),
+            }
+            let line = self.line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                match total {
+                    Some(total) => return Some(Ok(total)),
+                    None => continue,
+                }
+            }
+            match add_food(total.unwrap_or(0), line) {
+                Ok(calories) => total = Some(calories),
+                Err(mut error) => {
+                    error.line = self.line_number;
+                    return Some(Err(error.into()));
+                }
+            }
+        }
+    }
 }
 
-fn get_elves(input: &str) -> Vec<Elf> {
-    input
-        .split("\n\n")
-        .filter(|s| !s.is_empty())
-        .map(|s| s.parse().unwrap())
-        .collect()
+/// Adds the single food item on `line` to `calories`.
+fn add_food(calories: u64, line: &str) -> Result<u64, ParseError> {
+    let food = line.trim();
+    if let Some((_, rest)) = food.split_once(char::is_whitespace) {
+        let extra = rest.trim_start();
+        return Err(ParseError::new(
+            DAY,
+            line,
+            extra,
+            "expected one item per line",
+        ));
+    }
+    let value = parse_number::<u64>(DAY, line, food)?;
+    calories
+        .checked_add(value)
+        .ok_or_else(|| ParseError::new(DAY, line, food, "too many calories"))
 }
 
-fn solve(input: &str) -> u64 {
-    let elves = get_elves(input);
-    info!("elves: {:?}", elves);
-    elves
-        .iter()
-        .map(|elf| elf.food.iter().map(|f| f.0).sum())
-        .max()
-        .unwrap()
+/// The `k` largest items pushed so far, keeping only those.
+#[derive(Debug, Clone)]
+pub struct TopK<T> {
+    k: usize,
+    /// A min-heap, so that the smallest of the top is the one to replace.
+    heap: BinaryHeap<Reverse<T>>,
 }
 
-fn solve_2(input: &str) -> u64 {
-    let elves = get_elves(input);
-    let mut sorted: Vec<_> = elves
-        .iter()
-        .map(|elf| elf.food.iter().map(|f| f.0).sum())
-        .collect();
-    sorted.sort();
-    sorted.iter().rev().take(3).sum()
+impl<T: Ord> TopK<T> {
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            heap: BinaryHeap::with_capacity(k + 1),
+        }
+    }
+    pub fn push(&mut self, item: T) {
+        if self.heap.len() < self.k {
+            self.heap.push(Reverse(item));
+        } else if self.heap.peek().is_some_and(|Reverse(min)| item > *min) {
+            self.heap.pop();
+            self.heap.push(Reverse(item));
+        }
+    }
+    /// Largest first.
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(item)| item)
+            .collect()
+    }
 }
 
-#[derive(Debug)]
-struct Elf {
-    food: Vec<Food>,
+impl<T: Ord> Extend<T> for TopK<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, items: I) {
+        for item in items {
+            self.push(item);
+        }
+    }
 }
 
-impl FromStr for Elf {
-    type Err = ();
+/// How many values fall in each range of `width`. A bucket only exists once a value lands in it,
+/// so memory grows with the spread of the values rather than with their number.
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    width: u64,
+    buckets: BTreeMap<u64, u64>,
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self {
-            food: s
-                .split_whitespace()
-                .filter(|s| !s.is_empty())
-                .map(|val| Food(val.parse().expect("parse error")))
-                .collect(),
+impl Histogram {
+    pub fn new(width: u64) -> Self {
+        Self {
+            width: width.max(1),
+            buckets: BTreeMap::new(),
+        }
+    }
+    pub fn add(&mut self, value: u64) {
+        *self.buckets.entry(value / self.width).or_default() += 1;
+    }
+    pub fn width(&self) -> u64 {
+        self.width
+    }
+    /// The lowest value of every bucket holding some, in order, with how many it holds.
+    pub fn buckets(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.buckets
+            .iter()
+            .map(|(&bucket, &count)| (bucket * self.width, count))
+    }
+    /// The middle of the bucket holding the value of the given rank, counted from 0.
+    fn value_at(&self, rank: u64) -> Option<u64> {
+        let mut seen = 0;
+        self.buckets().find_map(|(start, count)| {
+            seen += count;
+            (rank < seen).then(|| start.saturating_add((self.width - 1) / 2))
         })
     }
 }
 
-#[derive(Debug)]
-struct Food(u64);
+impl Display for Histogram {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        const BAR: u64 = 40;
+        let most = self.buckets.values().copied().max().unwrap_or(1);
+        for (start, count) in self.buckets() {
+            writeln!(
+                f,
+                "{:>8}-{:<8} {:>6} {}",
+                start,
+                start.saturating_add(self.width - 1),
+                count,
+                "#".repeat((count * BAR).div_ceil(most) as usize)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Statistics of the calories each elf carries, updated one elf at a time.
+#[derive(Debug, Clone)]
+pub struct Summary {
+    count: u64,
+    sum: u128,
+    min: Option<u64>,
+    max: Option<u64>,
+    top: TopK<u64>,
+    histogram: Histogram,
+}
+
+impl Summary {
+    /// Keeps the `k` largest totals and buckets of `bucket_width` calories.
+    pub fn new(k: usize, bucket_width: u64) -> Self {
+        Self {
+            count: 0,
+            sum: 0,
+            min: None,
+            max: None,
+            top: TopK::new(k),
+            histogram: Histogram::new(bucket_width),
+        }
+    }
+    pub fn add(&mut self, total: u64) {
+        self.count += 1;
+        self.sum += total as u128;
+        self.min = Some(self.min.map_or(total, |min| min.min(total)));
+        self.max = Some(self.max.map_or(total, |max| max.max(total)));
+        self.top.push(total);
+        self.histogram.add(total);
+    }
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+    pub fn sum(&self) -> u128 {
+        self.sum
+    }
+    pub fn min(&self) -> Option<u64> {
+        self.min
+    }
+    pub fn max(&self) -> Option<u64> {
+        self.max
+    }
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum as f64 / self.count as f64)
+    }
+    /// Exact with buckets 1 calorie wide; otherwise the middle of the bucket the median falls in.
+    pub fn median(&self) -> Option<f64> {
+        let (min, max) = (self.min?, self.max?);
+        let at = |rank| self.histogram.value_at(rank).unwrap().clamp(min, max) as f64;
+        Some((at((self.count - 1) / 2) + at(self.count / 2)) / 2.0)
+    }
+    pub fn is_median_exact(&self) -> bool {
+        self.histogram.width() == 1
+    }
+    /// The largest totals, largest first.
+    pub fn top(&self) -> Vec<u64> {
+        self.top.clone().into_sorted_vec()
+    }
+    pub fn histogram(&self) -> &Histogram {
+        &self.histogram
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (Some(min), Some(max), Some(mean), Some(median)) =
+            (self.min, self.max, self.mean(), self.median())
+        else {
+            return writeln!(f, "no elves");
+        };
+        let approximately = if self.is_median_exact() { "" } else { "≈ " };
+        writeln!(
+            f,
+            "{} elves carry {} calories: min {}, mean {:.1}, median {}{:.1}, max {}",
+            self.count, self.sum, min, mean, approximately, median, max
+        )?;
+        let top: Vec<String> = self.top().iter().map(|t| t.to_string()).collect();
+        writeln!(f, "top {}: {}", top.len(), top.join(", "))?;
+        write!(f, "{}", self.histogram)
+    }
+}
+
+/// Reads an inventory of any size, keeping the `k` largest totals.
+pub fn summarize(reader: impl BufRead, k: usize, bucket_width: u64) -> Result<Summary, ReadError> {
+    let mut summary = Summary::new(k, bucket_width);
+    for total in ElfTotals::new(reader) {
+        summary.add(total?);
+    }
+    Ok(summary)
+}
+
+struct SummaryExplorer<'a>(&'a Summary);
+
+impl Explore for SummaryExplorer<'_> {
+    fn commands(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "stats",
+            "show the statistics and histogram of the elves' calories",
+        )]
+    }
+    fn run(&mut self, command: &str, _args: &[&str]) -> Option<anyhow::Result<String>> {
+        match command {
+            "stats" => Some(Ok(self.0.to_string().trim_end().to_string())),
+            _ => None,
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
+    use std::io::BufReader;
+
     use super::*;
 
     #[test]
     fn test1() {
-        assert_eq!(
-            solve(
-                "1000
+        let input = "1000
 2000
 3000
 
@@ -80,28 +352,63 @@ mod tests {
 8000
 9000
 
-10000"
-            ),
-            24_000
-        );
+10000";
+        let summary = CalorieCounting::parse(input).unwrap();
+        assert_eq!(24_000, CalorieCounting::part1(&summary));
+        assert_eq!(45_000, CalorieCounting::part2(&summary));
         assert_eq!(
-            solve_2(
-                "1000
-2000
-3000
+            vec![6000, 4000, 11_000, 24_000, 10_000],
+            ElfTotals::new(input.replace('\n', "\r\n").as_bytes())
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        );
 
-4000
+        let exact = summarize(BufReader::with_capacity(4, input.as_bytes()), 2, 1).unwrap();
+        assert_eq!((5, 55_000), (exact.count(), exact.sum()));
+        assert_eq!((Some(4000), Some(24_000)), (exact.min(), exact.max()));
+        assert_eq!(Some(11_000.0), exact.mean());
+        assert_eq!(Some(10_000.0), exact.median());
+        assert_eq!(vec![24_000, 11_000], exact.top());
+        let even = summarize("1\n\n2\n\n\n\n10\n\n4\n".as_bytes(), 0, 1).unwrap();
+        assert_eq!(Some(3.0), even.median());
+        assert!(even.top().is_empty());
+        // the middle of the bucket of 10000 calories
+        assert_eq!(Some(14_999.0), summary.median());
+        assert_eq!(
+            vec![(0, 2), (10_000, 2), (20_000, 1)],
+            summary.histogram().buckets().collect::<Vec<_>>()
+        );
+        assert!(summary.to_string().contains("top 3: 24000, 11000, 10000"));
+        assert!(summary.to_string().contains("median ≈ 14999.0, max"));
+        assert!(exact.to_string().contains("median 10000.0, max"));
+        assert_eq!(None, Summary::new(3, 10).median());
 
-5000
-6000
+        let mut top = TopK::new(3);
+        top.extend([5, 1, 9, 3, 9, 7]);
+        assert_eq!(vec![9, 9, 7], top.into_sorted_vec());
 
-7000
-8000
-9000
+        // more calories than a u64 holds, over every elf
+        let max = u64::MAX.to_string();
+        let huge = summarize(format!("{max}\n\n{max}").as_bytes(), 3, BUCKET_WIDTH).unwrap();
+        assert_eq!(2 * u64::MAX as u128, huge.sum());
+        assert_eq!(Some(u64::MAX as f64), huge.median());
 
-10000"
-            ),
-            45_000
+        let error = CalorieCounting::parse("1000\n2000\n\n3000\n4x00").unwrap_err();
+        let Some(ReadError::Parse(error)) = error.downcast_ref::<ReadError>() else {
+            panic!("expected a parse error");
+        };
+        assert_eq!((1, 5, 1), (error.day, error.line, error.column));
+        assert_eq!("4x00", error.text);
+        let error = summarize("1\n18446744073709551615".as_bytes(), 3, 1).unwrap_err();
+        assert_eq!(
+            "day 1, line 2, column 1: too many calories: '18446744073709551615'",
+            error.to_string()
+        );
+        // several items on one line are not added up
+        let error = summarize("1000\n 1000 2000\n".as_bytes(), 3, 1).unwrap_err();
+        assert_eq!(
+            "day 1, line 2, column 7: expected one item per line: '2000'",
+            error.to_string()
         );
     }
 }
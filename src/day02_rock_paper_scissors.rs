@@ -1,153 +1,751 @@
-use std::str::FromStr;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input2.txt").unwrap();
+use anyhow::{bail, Context};
+use lazy_static::lazy_static;
+use permutator::KPermutationIterator;
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::Deserialize;
+use thiserror::Error;
 
-    println!("my total score: {}", play(&input));
-    println!("my total score v2: {}", play_v2(&input));
-}
+use crate::params::{Parameter, Params};
+use crate::parse_error::ParseError;
+use crate::repl::{self, Explore};
+use crate::solution::Solution;
+
+const DAY: u8 = 2;
+
+/// The game of the puzzle, as a ruleset.
+const STANDARD_RULES: &str = r#"
+shapes = ["rock", "paper", "scissors"]
+beats = [["rock", "scissors"], ["paper", "rock"], ["scissors", "paper"]]
+
+[shape_scores]
+rock = 1
+paper = 2
+scissors = 3
+
+[outcome_scores]
+win = 6
+draw = 3
+lose = 0
+
+[opponent_codes]
+A = "rock"
+B = "paper"
+C = "scissors"
+
+[player_codes]
+X = "rock"
+Y = "paper"
+Z = "scissors"
+
+[outcome_codes]
+X = "lose"
+Y = "draw"
+Z = "win"
+"#;
 
-fn play(input: &str) -> u64 {
-    input
-        .lines()
-        .map(|line| line.parse::<Round>().unwrap().get_my_score())
-        .sum()
+lazy_static! {
+    static ref STANDARD: Rules = Rules::from_toml(STANDARD_RULES).unwrap();
 }
 
-fn play_v2(input: &str) -> u64 {
-    input
-        .lines()
-        .map(|line| {
-            let mut round: Round = line.parse().unwrap();
-            round.fix_encryption();
-            round.get_my_score()
+pub struct RockPaperScissors;
+
+impl Solution for RockPaperScissors {
+    type Input = Strategy;
+    type Answer1 = Result<u64, GameError>;
+    type Answer2 = Result<u64, GameError>;
+    const PARAMETERS: &'static [Parameter] = &[Parameter::text(
+        "rules",
+        "",
+        "TOML file of the game (see Rules::load), rock paper scissors if empty",
+    )];
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(Strategy {
+            text: input.to_string(),
+            standard: parse_guide(input),
         })
-        .sum()
+    }
+    fn part1(strategy: &Self::Input) -> Self::Answer1 {
+        Self::part1_with(strategy, &Params::defaults(Self::PARAMETERS))
+    }
+    fn part2(strategy: &Self::Input) -> Self::Answer2 {
+        Self::part2_with(strategy, &Params::defaults(Self::PARAMETERS))
+    }
+    fn part1_with(strategy: &Self::Input, params: &Params) -> Self::Answer1 {
+        strategy.score(params, |guide| guide.play())
+    }
+    fn part2_with(strategy: &Self::Input, params: &Params) -> Self::Answer2 {
+        strategy.score(params, |guide| guide.play_for_outcomes())
+    }
+    /// Only for guides that the standard rules can read.
+    fn explore(strategy: &Self::Input) -> Option<Box<dyn Explore + '_>> {
+        let guide = strategy.standard.as_ref().ok()?;
+        Some(Box::new(GuideExplorer(guide)))
+    }
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        (0..size)
+            .map(|_| format!("{} {}", rng.gen_range('A'..='C'), rng.gen_range('X'..='Z')))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
-#[derive(Copy, Clone)]
-enum Shape {
-    Rock,
-    Paper,
-    Scissors,
+fn parse_guide(input: &str) -> Result<Guide<'static>, ParseError> {
+    STANDARD.parse_guide(input)
 }
 
-struct Round {
-    player: Shape,
-    opponent: Shape,
+/// A strategy guide, whose letters only mean something under the rules of the `rules` parameter.
+pub struct Strategy {
+    text: String,
+    /// The guide read with the standard rules, kept so that the default parameters don't read it
+    /// again.
+    standard: Result<Guide<'static>, ParseError>,
+}
+
+impl Strategy {
+    fn score(
+        &self,
+        params: &Params,
+        play: impl Fn(&Guide) -> anyhow::Result<u64>,
+    ) -> Result<u64, GameError> {
+        let path = params.text("rules");
+        if path.is_empty() {
+            let guide = self
+                .standard
+                .as_ref()
+                .map_err(|e| GameError(e.clone().into()))?;
+            return play(guide).map_err(GameError);
+        }
+        let rules = Rules::load(Path::new(path)).map_err(GameError)?;
+        let guide = rules
+            .parse_guide(&self.text)
+            .map_err(|e| GameError(e.into()))?;
+        play(&guide).map_err(GameError)
+    }
 }
 
-enum GameResult {
+/// Why a guide cannot be scored: its rules file, a letter the rules don't know, or a round
+/// whose letter has no meaning in this part.
+#[derive(Debug, Error)]
+#[error("{0:#}")]
+pub struct GameError(anyhow::Error);
+
+/// One of the shapes of the [`Rules`] it comes from. Shapes never leave this module, and
+/// [`Guide`] keeps its rounds with their rules, so a shape is never looked up in another ruleset.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct Shape(usize);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
     Win,
     Lose,
-    Tie,
+    Draw,
 }
 
-impl Shape {
-    pub fn result_against(&self, other: &Self) -> GameResult {
-        match self {
-            Shape::Rock => match other {
-                Shape::Rock => GameResult::Tie,
-                Shape::Paper => GameResult::Lose,
-                Shape::Scissors => GameResult::Win,
-            },
-            Shape::Paper => match other {
-                Shape::Rock => GameResult::Win,
-                Shape::Paper => GameResult::Tie,
-                Shape::Scissors => GameResult::Lose,
-            },
-            Shape::Scissors => match other {
-                Shape::Rock => GameResult::Lose,
-                Shape::Paper => GameResult::Win,
-                Shape::Scissors => GameResult::Tie,
-            },
-        }
+/// A line of the strategy guide. Part 1 reads the second column as the player's shape, and
+/// part 2 as the outcome to aim for; rulesets may give a letter only one of these meanings.
+#[derive(Copy, Clone, Debug)]
+struct Round {
+    opponent: Shape,
+    /// Index of the second column in the letters of the rules.
+    letter: usize,
+    player: Option<Shape>,
+    outcome: Option<Outcome>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OutcomeScores {
+    win: u64,
+    draw: u64,
+    lose: u64,
+}
+
+/// A ruleset as written in its config file, with shapes referred to by name.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesConfig {
+    shapes: Vec<String>,
+    /// `[winner, loser]` pairs; shapes that beat neither way draw.
+    beats: Vec<(String, String)>,
+    shape_scores: HashMap<String, u64>,
+    outcome_scores: OutcomeScores,
+    opponent_codes: HashMap<String, String>,
+    player_codes: HashMap<String, String>,
+    outcome_codes: HashMap<String, Outcome>,
+}
+
+/// A game of any number of shapes, such as Rock-Paper-Scissors-Lizard-Spock, with its scores and
+/// the letters of the strategy guide.
+#[derive(Clone, Debug)]
+pub struct Rules {
+    names: Vec<String>,
+    scores: Vec<u64>,
+    /// `beats[a][b]` if shape `a` beats shape `b`.
+    beats: Vec<Vec<bool>>,
+    outcome_scores: OutcomeScores,
+    opponent_codes: HashMap<String, Shape>,
+    player_codes: HashMap<String, Shape>,
+    outcome_codes: HashMap<String, Outcome>,
+    /// The player's and outcome codes, sorted.
+    letters: Vec<String>,
+}
+
+impl Default for Rules {
+    /// Rock, paper and scissors, as in the puzzle.
+    fn default() -> Self {
+        STANDARD.clone()
     }
 }
 
-impl Round {
-    pub fn get_my_score(&self) -> u64 {
-        return match self.player {
-            Shape::Rock => 1,
-            Shape::Paper => 2,
-            Shape::Scissors => 3,
-        } + match self.player.result_against(&self.opponent) {
-            GameResult::Win => 6,
-            GameResult::Lose => 0,
-            GameResult::Tie => 3,
-        };
+impl Rules {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read rules file {}", path.display()))?;
+        Self::from_toml(&contents).with_context(|| format!("invalid rules file {}", path.display()))
     }
-    pub fn fix_encryption(&mut self) {
-        self.player = match self.player {
-            Shape::Rock => {
-                // X: should lose
-                match self.opponent {
-                    Shape::Rock => Shape::Scissors,
-                    Shape::Paper => Shape::Rock,
-                    Shape::Scissors => Shape::Paper,
-                }
+    pub fn from_toml(contents: &str) -> anyhow::Result<Self> {
+        let config: RulesConfig = toml::from_str(contents)?;
+        if config.shapes.is_empty() {
+            bail!("there are no shapes");
+        }
+        let mut shapes = HashMap::new();
+        for (i, name) in config.shapes.iter().enumerate() {
+            if shapes.insert(name.as_str(), Shape(i)).is_some() {
+                bail!("shape '{}' is listed twice", name);
             }
-            Shape::Paper => {
-                // Y: should tie
-                self.opponent
+        }
+        let shape = |name: &str| {
+            shapes
+                .get(name)
+                .copied()
+                .with_context(|| format!("unknown shape '{}'", name))
+        };
+
+        let mut beats = vec![vec![false; shapes.len()]; shapes.len()];
+        for (winner, loser) in config.beats.iter() {
+            let (winner, loser) = (shape(winner)?, shape(loser)?);
+            if winner == loser || beats[loser.0][winner.0] {
+                bail!(
+                    "'{}' and '{}' cannot beat each other",
+                    config.shapes[winner.0],
+                    config.shapes[loser.0]
+                );
             }
-            Shape::Scissors => {
-                // Z: should win
-                match self.opponent {
-                    Shape::Rock => Shape::Paper,
-                    Shape::Paper => Shape::Scissors,
-                    Shape::Scissors => Shape::Rock,
-                }
+            beats[winner.0][loser.0] = true;
+        }
+        for name in config.shape_scores.keys() {
+            shape(name)?;
+        }
+        let scores = config
+            .shapes
+            .iter()
+            .map(|name| {
+                config
+                    .shape_scores
+                    .get(name)
+                    .copied()
+                    .with_context(|| format!("shape '{}' has no score", name))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        let codes = |codes: HashMap<String, String>| -> anyhow::Result<HashMap<String, Shape>> {
+            codes
+                .into_iter()
+                .map(|(code, name)| Ok((code, shape(&name)?)))
+                .collect()
+        };
+        let mut letters: Vec<String> = config
+            .player_codes
+            .keys()
+            .chain(config.outcome_codes.keys())
+            .cloned()
+            .collect();
+        letters.sort();
+        letters.dedup();
+        let rules = Self {
+            letters,
+            scores,
+            beats,
+            outcome_scores: config.outcome_scores,
+            opponent_codes: codes(config.opponent_codes)?,
+            player_codes: codes(config.player_codes)?,
+            outcome_codes: config.outcome_codes,
+            names: config.shapes,
+        };
+        let mut codes = rules
+            .opponent_codes
+            .keys()
+            .chain(rules.player_codes.keys())
+            .chain(rules.outcome_codes.keys());
+        if let Some(code) = codes.find(|code| code.is_empty() || code.contains(char::is_whitespace))
+        {
+            bail!("code '{}' is empty or holds a space", code);
+        }
+        Ok(rules)
+    }
+    /// In the order of the config file.
+    pub fn shape_names(&self) -> &[String] {
+        &self.names
+    }
+    fn shapes(&self) -> impl Iterator<Item = Shape> {
+        (0..self.names.len()).map(Shape)
+    }
+    fn outcome(&self, player: Shape, opponent: Shape) -> Outcome {
+        if self.beats[player.0][opponent.0] {
+            Outcome::Win
+        } else if self.beats[opponent.0][player.0] {
+            Outcome::Lose
+        } else {
+            Outcome::Draw
+        }
+    }
+    fn score(&self, player: Shape, opponent: Shape) -> u64 {
+        self.scores[player.0]
+            + match self.outcome(player, opponent) {
+                Outcome::Win => self.outcome_scores.win,
+                Outcome::Lose => self.outcome_scores.lose,
+                Outcome::Draw => self.outcome_scores.draw,
             }
+    }
+    /// The best scoring shape with that outcome against `opponent`, if any has it.
+    fn response(&self, opponent: Shape, outcome: Outcome) -> Option<Shape> {
+        self.shapes()
+            .filter(|&player| self.outcome(player, opponent) == outcome)
+            .max_by_key(|&player| (self.scores[player.0], std::cmp::Reverse(player.0)))
+    }
+    pub fn parse_guide(&self, input: &str) -> Result<Guide<'_>, ParseError> {
+        let rounds = input
+            .lines()
+            .map(|line| {
+                self.parse_round(line)
+                    .map_err(|e: ParseError| e.within(input, line))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Guide {
+            rules: self,
+            rounds,
+        })
+    }
+    fn parse_round(&self, s: &str) -> Result<Round, ParseError> {
+        let mut parts = s.split_whitespace();
+        let too_short = || ParseError::new(DAY, s, s, "line too short");
+        let opponent = parts.next().ok_or_else(too_short)?;
+        let opponent = *self
+            .opponent_codes
+            .get(opponent)
+            .ok_or_else(|| ParseError::new(DAY, s, opponent, "invalid opponent option"))?;
+        let player = parts.next().ok_or_else(too_short)?;
+        let Ok(letter) = self.letters.binary_search_by(|l| l.as_str().cmp(player)) else {
+            return Err(ParseError::new(DAY, s, player, "invalid player option"));
         };
+        Ok(Round {
+            opponent,
+            letter,
+            player: self.player_codes.get(player).copied(),
+            outcome: self.outcome_codes.get(player).copied(),
+        })
+    }
+    fn meaning(&self, choice: Choice) -> Meaning {
+        match choice {
+            Choice::Shape(shape) => Meaning::Shape(self.names[shape.0].clone()),
+            Choice::Outcome(outcome) => Meaning::Outcome(outcome),
+        }
     }
 }
 
-impl FromStr for Round {
-    type Err = ();
+/// The rounds of a strategy guide, with the rules they were read with.
+#[derive(Clone, Debug)]
+pub struct Guide<'a> {
+    rules: &'a Rules,
+    rounds: Vec<Round>,
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.split_whitespace();
-        let opponent: Shape = match parts.next().expect("line too short") {
-            "A" => Shape::Rock,
-            "B" => Shape::Paper,
-            "C" => Shape::Scissors,
-            other => panic!("invalid opponent option '{}'", other),
-        };
-        let player: Shape = match parts.next().expect("line too short") {
-            "X" => Shape::Rock,
-            "Y" => Shape::Paper,
-            "Z" => Shape::Scissors,
-            other => panic!("invalid player option '{}'", other),
+impl<'a> Guide<'a> {
+    pub fn rules(&self) -> &'a Rules {
+        self.rules
+    }
+    /// The player's score with the second column as their shape, as in part 1.
+    pub fn play(&self) -> anyhow::Result<u64> {
+        self.rounds
+            .iter()
+            .enumerate()
+            .map(|(i, round)| {
+                let player = round
+                    .player
+                    .with_context(|| format!("round {} gives an outcome, not a shape", i + 1))?;
+                Ok(self.rules.score(player, round.opponent))
+            })
+            .sum()
+    }
+    /// The player's score with the second column as the outcome to aim for, as in part 2.
+    pub fn play_for_outcomes(&self) -> anyhow::Result<u64> {
+        let rules = self.rules;
+        self.rounds
+            .iter()
+            .enumerate()
+            .map(|(i, round)| {
+                let outcome = round
+                    .outcome
+                    .with_context(|| format!("round {} gives a shape, not an outcome", i + 1))?;
+                let player = rules.response(round.opponent, outcome).with_context(|| {
+                    format!(
+                        "round {}: no shape can {:?} against {}",
+                        i + 1,
+                        outcome,
+                        rules.names[round.opponent.0]
+                    )
+                })?;
+                Ok(rules.score(player, round.opponent))
+            })
+            .sum()
+    }
+    /// The score of playing the best shape against each of the opponent's.
+    pub fn best_response_score(&self) -> u64 {
+        let rules = self.rules;
+        self.rounds
+            .iter()
+            .map(|round| {
+                let scores = rules
+                    .shapes()
+                    .map(|player| rules.score(player, round.opponent));
+                scores.max().unwrap()
+            })
+            .sum()
+    }
+    /// Every way of giving a different shape, or a different outcome, to each letter of the
+    /// second column, best scoring first.
+    pub fn rank_mappings(&self) -> Vec<Mapping> {
+        let rules = self.rules;
+        let mut used: Vec<usize> = self.rounds.iter().map(|round| round.letter).collect();
+        used.sort();
+        used.dedup();
+        // rounds of each letter against each shape, so that a mapping is scored without going
+        // through the guide again
+        let mut counts = vec![vec![0; rules.names.len()]; used.len()];
+        for round in self.rounds.iter() {
+            counts[used.binary_search(&round.letter).unwrap()][round.opponent.0] += 1;
+        }
+        let score = |choices: &[Choice]| -> Option<u64> {
+            counts
+                .iter()
+                .zip(choices)
+                .flat_map(|(counts, &choice)| {
+                    rules
+                        .shapes()
+                        .zip(counts)
+                        .filter(|(_, &count)| count > 0)
+                        .map(move |(opponent, &count)| {
+                            let player = match choice {
+                                Choice::Shape(shape) => shape,
+                                Choice::Outcome(outcome) => rules.response(opponent, outcome)?,
+                            };
+                            Some(count * rules.score(player, opponent))
+                        })
+                })
+                .sum()
         };
-        Ok(Self { player, opponent })
+
+        let shapes: Vec<Choice> = rules.shapes().map(Choice::Shape).collect();
+        let outcomes = [Outcome::Win, Outcome::Draw, Outcome::Lose].map(Choice::Outcome);
+        let mut mappings: Vec<Mapping> = [shapes.as_slice(), &outcomes]
+            .into_iter()
+            // the iterator wants 0 < k <= its items; with more letters than choices there is no
+            // mapping
+            .filter(|choices| (1..=choices.len()).contains(&used.len()))
+            .flat_map(|choices| KPermutationIterator::new(choices, used.len()))
+            .filter_map(|choices| {
+                let choices: Vec<Choice> = choices.into_iter().copied().collect();
+                Some(Mapping {
+                    score: score(&choices)?,
+                    letters: used
+                        .iter()
+                        .map(|&letter| rules.letters[letter].clone())
+                        .zip(choices.into_iter().map(|choice| rules.meaning(choice)))
+                        .collect(),
+                })
+            })
+            .collect();
+        mappings.sort_by_key(|mapping| Reverse(mapping.score));
+        mappings
+    }
+    pub fn analyse(&self) -> Analysis {
+        let opponents: Vec<Shape> = self.rounds.iter().map(|round| round.opponent).collect();
+        Analysis {
+            best_response: self.best_response_score(),
+            period: period(&opponents),
+            mappings: self.rank_mappings(),
+        }
+    }
+}
+
+/// A meaning of a letter while ranking mappings, with the shape still an index of the rules.
+#[derive(Copy, Clone, Debug)]
+enum Choice {
+    Shape(Shape),
+    Outcome(Outcome),
+}
+
+/// What a letter of the second column stands for.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Meaning {
+    /// The name of a shape.
+    Shape(String),
+    Outcome(Outcome),
+}
+
+impl Display for Meaning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Meaning::Shape(name) => write!(f, "{}", name),
+            Meaning::Outcome(outcome) => write!(f, "{}", format!("{:?}", outcome).to_lowercase()),
+        }
+    }
+}
+
+/// A meaning for each letter the guide uses, and the score it gives.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mapping {
+    pub letters: Vec<(String, Meaning)>,
+    pub score: u64,
+}
+
+/// Such as `X=rock Y=paper Z=scissors: 15`.
+impl Display for Mapping {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let letters: Vec<String> = self
+            .letters
+            .iter()
+            .map(|(letter, meaning)| format!("{}={}", letter, meaning))
+            .collect();
+        write!(f, "{}: {}", letters.join(" "), self.score)
+    }
+}
+
+/// What the opponent's shapes tell about them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Analysis {
+    /// The score of always playing the best shape against them.
+    pub best_response: u64,
+    /// See [`period`].
+    pub period: Option<usize>,
+    /// See [`Guide::rank_mappings`].
+    pub mappings: Vec<Mapping>,
+}
+
+/// The length of the shortest block that `sequence` repeats, if it repeats it at least twice; the
+/// last repetition may be cut short.
+pub fn period<T: PartialEq>(sequence: &[T]) -> Option<usize> {
+    // border[i]: the length of the longest proper prefix of sequence[..=i] that is also a suffix
+    let mut border = vec![0; sequence.len()];
+    for i in 1..sequence.len() {
+        let mut k = border[i - 1];
+        while k > 0 && sequence[i] != sequence[k] {
+            k = border[k - 1];
+        }
+        if sequence[i] == sequence[k] {
+            k += 1;
+        }
+        border[i] = k;
+    }
+    let period = sequence.len() - border.last()?;
+    (2 * period <= sequence.len()).then_some(period)
+}
+
+struct GuideExplorer<'a>(&'a Guide<'a>);
+
+impl Explore for GuideExplorer<'_> {
+    fn commands(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "analyse [mappings]",
+            "show the best response, the opponent's pattern and the best mappings (default 5)",
+        )]
+    }
+    fn run(&mut self, command: &str, args: &[&str]) -> Option<anyhow::Result<String>> {
+        match command {
+            "analyse" => Some(
+                repl::optional_argument(args, 0, "mappings", 5).map(|count| {
+                    let analysis = self.0.analyse();
+                    let mut lines = vec![format!("best response: {}", analysis.best_response)];
+                    lines.push(match analysis.period {
+                        Some(period) => format!("the opponent repeats every {} rounds", period),
+                        None => "the opponent does not repeat".to_string(),
+                    });
+                    lines.extend(
+                        analysis
+                            .mappings
+                            .iter()
+                            .take(count)
+                            .map(|mapping| mapping.to_string()),
+                    );
+                    lines.join("\n")
+                }),
+            ),
+            _ => None,
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::params::{Overrides, Value};
+    use crate::registry;
 
     #[test]
     fn test1() {
         assert_eq!(
-            play(
-                "A Y
+            RockPaperScissors::part1(
+                &RockPaperScissors::parse(
+                    "A Y
 B X
 C Z"
-            ),
+                )
+                .unwrap()
+            )
+            .unwrap(),
             15
-        )
+        );
+        let error = parse_guide("A Y\nB W").unwrap_err();
+        assert_eq!((2, 3, "W"), (error.line, error.column, error.text.as_str()));
     }
     #[test]
     fn test2() {
         assert_eq!(
-            play_v2(
-                "A Y
+            RockPaperScissors::part2(
+                &RockPaperScissors::parse(
+                    "A Y
 B X
 C Z"
-            ),
+                )
+                .unwrap()
+            )
+            .unwrap(),
             12
+        );
+
+        let guide = parse_guide("A Y\nB X\nC Z").unwrap();
+        let analysis = guide.analyse();
+        assert_eq!(8 + 9 + 7, analysis.best_response);
+        assert_eq!(None, analysis.period);
+        assert_eq!(12, analysis.mappings.len());
+        assert_eq!(
+            "X=scissors Y=paper Z=rock: 24",
+            analysis.mappings[0].to_string()
+        );
+        let described: Vec<String> = analysis.mappings.iter().map(|m| m.to_string()).collect();
+        assert!(described.contains(&"X=rock Y=paper Z=scissors: 15".to_string()));
+        assert!(described.contains(&"X=lose Y=draw Z=win: 12".to_string()));
+        let repeating = parse_guide("A X\nB X\nB Y\nA X\nB X\nB Z\nA X").unwrap();
+        assert_eq!(Some(3), repeating.analyse().period);
+        assert_eq!(Some(1), period(&[1, 1]));
+        assert_eq!(None, period(&[1, 2, 1]));
+        assert_eq!(None, period::<u8>(&[]));
+        assert!(parse_guide("").unwrap().rank_mappings().is_empty());
+
+        let dir = std::env::temp_dir().join(format!("aoc-day02-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("spock.toml");
+        std::fs::write(
+            &path,
+            r#"
+shapes = ["rock", "paper", "scissors", "lizard", "spock"]
+beats = [
+    ["scissors", "paper"], ["paper", "rock"], ["rock", "lizard"], ["lizard", "spock"],
+    ["spock", "scissors"], ["scissors", "lizard"], ["lizard", "paper"], ["paper", "spock"],
+    ["spock", "rock"], ["rock", "scissors"],
+]
+shape_scores = { rock = 1, paper = 2, scissors = 3, lizard = 4, spock = 5 }
+outcome_scores = { win = 6, draw = 3, lose = 0 }
+opponent_codes = { A = "rock", B = "paper", C = "scissors", D = "lizard", E = "spock" }
+player_codes = { V = "rock", W = "paper", X = "scissors", Y = "lizard", Z = "spock" }
+outcome_codes = { X = "lose", Y = "draw", Z = "win" }
+"#,
         )
+        .unwrap();
+        let spock = Rules::load(&path).unwrap();
+        let mut overrides = Overrides::default();
+        let day = registry::find("2").unwrap();
+        let rules = Value::Text(path.display().to_string());
+        overrides.set(day, "rules".to_string(), rules);
+        let params = overrides.for_day(day).unwrap();
+        let strategy = RockPaperScissors::parse("E Y\nA W\nD X").unwrap();
+        assert!(RockPaperScissors::part1(&strategy).is_err());
+        // lizard poisons spock, paper covers rock, scissors decapitate lizard
+        assert_eq!(
+            4 + 6 + 2 + 6 + 3 + 6,
+            RockPaperScissors::part1_with(&strategy, &params).unwrap()
+        );
+        // W is only a shape
+        assert_eq!(
+            "round 2 gives a shape, not an outcome",
+            RockPaperScissors::part2_with(&strategy, &params)
+                .unwrap_err()
+                .to_string()
+        );
+        let missing = Rules::load(&dir.join("missing.toml")).unwrap_err();
+        assert!(missing.to_string().starts_with("cannot read rules file"));
+        std::fs::write(&path, "shapes = []").unwrap();
+        assert!(Rules::load(&path)
+            .unwrap_err()
+            .to_string()
+            .starts_with("invalid rules file"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let guide = spock.parse_guide("E Y\nA W\nD X").unwrap();
+        // lizard poisons spock, paper covers rock, scissors decapitate lizard
+        assert_eq!(4 + 6 + 2 + 6 + 3 + 6, guide.play().unwrap());
+        // spock against spock, then the best scoring winners: spock over rock, not paper, and
+        // scissors over lizard, not rock
+        assert_eq!(5 + 3 + 5 + 6 + 3 + 6, {
+            let guide = spock.parse_guide("E Y\nA Z\nD Z").unwrap();
+            guide.play_for_outcomes().unwrap()
+        });
+        let mappings = guide.rank_mappings();
+        // 5 * 4 * 3 ways to give shapes to W, X and Y, and 3 * 2 to give them outcomes
+        assert_eq!(66, mappings.len());
+        assert_eq!("W=spock X=scissors Y=lizard: 30", mappings[0].to_string());
+        // five letters can't all have different outcomes
+        let every_letter = spock.parse_guide("A V\nA W\nA X\nA Y\nA Z").unwrap();
+        assert_eq!(5 * 4 * 3 * 2, every_letter.rank_mappings().len());
+        // lizard against spock, spock against rock and scissors against lizard
+        assert_eq!(4 + 6 + 5 + 6 + 3 + 6, guide.best_response_score());
+        let only_shapes = spock.parse_guide("A V").unwrap();
+        assert!(only_shapes.play_for_outcomes().is_err());
+        assert_eq!(
+            vec!["rock", "paper", "scissors", "lizard", "spock"],
+            spock.shape_names()
+        );
+        assert!(STANDARD.parse_guide("E Y").is_err());
+
+        assert_eq!(
+            15,
+            Rules::default()
+                .parse_guide("A Y\nB X\nC Z")
+                .unwrap()
+                .play()
+                .unwrap()
+        );
+        let invalid = |from: &str, to: &str| {
+            Rules::from_toml(&STANDARD_RULES.replace(from, to))
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            "'scissors' and 'rock' cannot beat each other",
+            invalid(r#"["paper", "rock"]"#, r#"["scissors", "rock"]"#)
+        );
+        assert_eq!(
+            "unknown shape 'rocks'",
+            invalid(r#"A = "rock""#, r#"A = "rocks""#)
+        );
+        assert_eq!("shape 'paper' has no score", invalid("paper = 2", ""));
+        assert!(invalid("lose = 0", "lose = -1").contains("invalid value"));
     }
 }
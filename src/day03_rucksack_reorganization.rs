@@ -1,41 +1,118 @@
-use std::collections::HashSet;
-use std::fmt::Debug;
-use std::iter::Chain;
-use std::slice::Iter;
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::BitAnd;
 use std::str::FromStr;
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input3.txt").unwrap();
-    let rucksacks: Vec<Rucksack> = input.lines().map(|l| l.parse().unwrap()).collect();
-    println!(
-        "sum of priorities in rucksacks: {}",
-        rucksacks
-            .iter()
-            .map(|r| r.get_letter_in_both().0 as u64)
-            .sum::<u64>()
-    );
-
-    let groups = rucksacks_to_groups(&rucksacks);
-    println!(
-        "sum of priorities in group: {}",
-        groups
-            .iter()
-            .map(|g| g.get_letter_in_all().0 as u64)
-            .sum::<u64>()
-    );
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use thiserror::Error;
+
+use crate::params::{Parameter, Params};
+use crate::parse_error::{invalid_char, parse_lines, ParseError};
+use crate::solution::Solution;
+
+const DAY: u8 = 3;
+
+pub struct RucksackReorganization;
+
+impl Solution for RucksackReorganization {
+    type Input = Vec<Rucksack>;
+    type Answer1 = Result<u64, RucksackError>;
+    type Answer2 = Result<u64, RucksackError>;
+    const PARAMETERS: &'static [Parameter] = &[
+        Parameter::integer(
+            "compartments",
+            2,
+            "equal parts of each rucksack that part 1 looks for a common item in",
+        )
+        .range(1, i64::MAX),
+        Parameter::integer("group_size", 3, "rucksacks of each group in part 2").range(1, i64::MAX),
+    ];
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(parse_lines(input)?)
+    }
+    fn part1(rucksacks: &Self::Input) -> Self::Answer1 {
+        Self::part1_with(rucksacks, &Params::defaults(Self::PARAMETERS))
+    }
+    fn part2(rucksacks: &Self::Input) -> Self::Answer2 {
+        Self::part2_with(rucksacks, &Params::defaults(Self::PARAMETERS))
+    }
+    fn part1_with(rucksacks: &Self::Input, params: &Params) -> Self::Answer1 {
+        sum_common_priorities(rucksacks, params.integer("compartments") as usize)
+    }
+    fn part2_with(rucksacks: &Self::Input, params: &Params) -> Self::Answer2 {
+        sum_badge_priorities(rucksacks, params.integer("group_size") as usize)
+    }
+    /// `size` groups of three rucksacks. Each rucksack draws from its own 17 letters, one of which
+    /// goes in both compartments, and the group's badge goes in one of them.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        let mut rucksacks = vec![];
+        for _ in 0..size.max(1) {
+            let mut letters: Vec<char> = ('a'..='z').chain('A'..='Z').collect();
+            letters.shuffle(rng);
+            let badge = letters.pop().unwrap();
+            for pool in letters.chunks(17) {
+                let (shared, pool) = pool.split_first().unwrap();
+                let (left, right) = pool.split_at(pool.len() / 2);
+                let mut compartments = [vec![*shared], vec![*shared]];
+                compartments[rng.gen_range(0..2)].push(badge);
+                let length = rng.gen_range(2..=16);
+                for (compartment, pool) in compartments.iter_mut().zip([left, right]) {
+                    while compartment.len() < length {
+                        compartment.push(*pool.choose(rng).unwrap());
+                    }
+                    compartment.shuffle(rng);
+                }
+                rucksacks.push(compartments.concat().into_iter().collect::<String>());
+            }
+        }
+        rucksacks.join("\n")
+    }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum RucksackError {
+    #[error("rucksack {rucksack} has {items} items, which do not split into {compartments} compartments")]
+    UnevenCompartments {
+        rucksack: usize,
+        items: usize,
+        compartments: usize,
+    },
+    #[error("rucksack {rucksack} has {} in every compartment, instead of one", describe(*.common))]
+    NotOneCommonItem { rucksack: usize, common: Letters },
+    #[error("group {group} has {} in every rucksack, instead of one badge", describe(*.common))]
+    NotOneBadge { group: usize, common: Letters },
+    #[error("the last group has {rucksacks} rucksacks instead of {group_size}")]
+    IncompleteGroup { rucksacks: usize, group_size: usize },
+}
+
+fn describe(letters: Letters) -> String {
+    match letters.is_empty() {
+        true => "no item".to_string(),
+        false => format!("items '{}'", letters),
+    }
+}
+
+/// An item, numbered by its priority.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
-struct Letter(u8);
+pub struct Letter(u8);
 
-impl From<char> for Letter {
-    fn from(value: char) -> Self {
-        let value = value as u8;
-        Self(if value <= 90 {
-            27 + value - 65
-        } else {
-            1 + value - 97
-        })
+impl Letter {
+    pub fn priority(self) -> u8 {
+        self.0
+    }
+}
+
+impl TryFrom<char> for Letter {
+    type Error = ParseError;
+
+    fn try_from(value: char) -> Result<Self, ParseError> {
+        Ok(Self(match value {
+            'a'..='z' => 1 + value as u8 - b'a',
+            'A'..='Z' => 27 + value as u8 - b'A',
+            _ => return Err(invalid_char(DAY, value, "invalid item")),
+        }))
     }
 }
 
@@ -49,74 +126,161 @@ impl From<Letter> for char {
     }
 }
 
+/// A set of letters, as one bit per priority.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Letters(u64);
+
+impl Letters {
+    pub const ALL: Letters = Letters(((1 << 52) - 1) << 1);
+
+    pub fn insert(&mut self, letter: Letter) {
+        self.0 |= 1 << letter.0;
+    }
+    pub fn contains(self, letter: Letter) -> bool {
+        self.0 & (1 << letter.0) != 0
+    }
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+    /// The letter, if there is exactly one.
+    pub fn only(self) -> Option<Letter> {
+        (self.len() == 1).then(|| Letter(self.0.trailing_zeros() as u8))
+    }
+    /// In order of priority.
+    pub fn iter(self) -> impl Iterator<Item = Letter> {
+        (1..=52).map(Letter).filter(move |&l| self.contains(l))
+    }
+}
+
+impl BitAnd for Letters {
+    type Output = Letters;
+
+    fn bitand(self, other: Self) -> Self::Output {
+        Letters(self.0 & other.0)
+    }
+}
+
+impl<'a> FromIterator<&'a Letter> for Letters {
+    fn from_iter<I: IntoIterator<Item = &'a Letter>>(letters: I) -> Self {
+        let mut set = Letters::default();
+        for &letter in letters {
+            set.insert(letter);
+        }
+        set
+    }
+}
+
+impl Display for Letters {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.iter().try_for_each(|l| write!(f, "{}", char::from(l)))
+    }
+}
+
 #[derive(Clone, Debug)]
-struct Rucksack {
-    compartments: [Vec<Letter>; 2],
+pub struct Rucksack {
+    items: Vec<Letter>,
 }
 
 impl Rucksack {
-    fn get_letter_in_both(&self) -> Letter {
-        let set: HashSet<&Letter> = self.compartments[0].iter().collect();
-        self.compartments[1]
-            .iter()
-            .filter(|l| set.contains(l))
-            .copied()
-            .next()
-            .expect("no letter found in both compartments")
+    pub fn items(&self) -> Letters {
+        self.items.iter().collect()
+    }
+    /// The items of each of `count` equal compartments, or `None` if they cannot be equal.
+    pub fn compartments(&self, count: usize) -> Option<Vec<Letters>> {
+        if count == 0 || !self.items.len().is_multiple_of(count) {
+            return None;
+        }
+        let n = self.items.len() / count;
+        Some(
+            (0..count)
+                .map(|i| self.items[i * n..(i + 1) * n].iter().collect())
+                .collect(),
+        )
     }
-    fn get_letters(&self) -> Chain<Iter<'_, Letter>, Iter<'_, Letter>> {
-        self.compartments[0]
+}
+
+/// Rucksacks carried by the same group of elves.
+pub struct Group<'a>(&'a [Rucksack]);
+
+impl Group<'_> {
+    /// The items in every rucksack of the group.
+    pub fn common_items(&self) -> Letters {
+        self.0
             .iter()
-            .chain(self.compartments[1].iter())
-    }
-}
-
-struct Group([Rucksack; 3]);
-
-impl Group {
-    fn get_letter_in_all(&self) -> Letter {
-        let set1: HashSet<&Letter> = self.0[0].get_letters().collect();
-        let set2: HashSet<&Letter> = self.0[1]
-            .get_letters()
-            .filter(|l| set1.contains(l))
-            .collect();
-        self.0[2]
-            .get_letters()
-            .filter(|l| set2.contains(l))
-            .copied()
-            .next()
-            .expect("no letter found in all 3 rucksacks")
-    }
-}
-
-fn rucksacks_to_groups(sacks: &Vec<Rucksack>) -> Vec<Group> {
-    sacks
-        .chunks(3)
-        .map(|sacks| {
-            Group(
-                sacks
-                    .iter()
-                    .cloned()
-                    .collect::<Vec<_>>()
-                    .try_into()
-                    .unwrap(),
-            )
+            .fold(Letters::ALL, |common, rucksack| common & rucksack.items())
+    }
+}
+
+/// Panics if `size` is 0.
+pub fn groups(rucksacks: &[Rucksack], size: usize) -> Result<Vec<Group<'_>>, RucksackError> {
+    let chunks = rucksacks.chunks_exact(size);
+    if !chunks.remainder().is_empty() {
+        return Err(RucksackError::IncompleteGroup {
+            rucksacks: chunks.remainder().len(),
+            group_size: size,
+        });
+    }
+    Ok(chunks.map(Group).collect())
+}
+
+/// The priorities of the item in every compartment of each rucksack, summed.
+pub fn sum_common_priorities(
+    rucksacks: &[Rucksack],
+    compartments: usize,
+) -> Result<u64, RucksackError> {
+    rucksacks
+        .iter()
+        .enumerate()
+        .map(|(i, rucksack)| {
+            let uneven = RucksackError::UnevenCompartments {
+                rucksack: i + 1,
+                items: rucksack.items.len(),
+                compartments,
+            };
+            let compartments = rucksack.compartments(compartments).ok_or(uneven)?;
+            let common = compartments
+                .into_iter()
+                .fold(Letters::ALL, |common, items| common & items);
+            let item = common.only().ok_or(RucksackError::NotOneCommonItem {
+                rucksack: i + 1,
+                common,
+            })?;
+            Ok(item.priority() as u64)
         })
-        .collect()
+        .sum()
+}
+
+/// The priorities of the badge of each group of `group_size` rucksacks, summed.
+pub fn sum_badge_priorities(
+    rucksacks: &[Rucksack],
+    group_size: usize,
+) -> Result<u64, RucksackError> {
+    groups(rucksacks, group_size)?
+        .iter()
+        .enumerate()
+        .map(|(i, group)| {
+            let common = group.common_items();
+            let badge = common.only().ok_or(RucksackError::NotOneBadge {
+                group: i + 1,
+                common,
+            })?;
+            Ok(badge.priority() as u64)
+        })
+        .sum()
 }
 
 impl FromStr for Rucksack {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let letters: Vec<Letter> = s.chars().map(|c| Letter::from(c)).collect();
-        assert_eq!(letters.len() % 2, 0, "invalid rucksack");
-        let n = letters.len() / 2;
         Ok(Self {
-            compartments: [
-                letters.iter().copied().take(n).collect(),
-                letters.iter().copied().skip(n).collect(),
-            ],
+            items: s
+                .char_indices()
+                .map(|(i, c)| Letter::try_from(c).map_err(|e| e.within(s, &s[i..i + c.len_utf8()])))
+                .collect::<Result<_, _>>()?,
         })
     }
 }
@@ -128,12 +292,25 @@ mod tests {
     #[test]
     fn test0() {
         fn test(c: char) {
-            assert_eq!(c, char::from(Letter::from(c)));
+            assert_eq!(c, char::from(Letter::try_from(c).unwrap()));
         }
-        test('a');
-        test('w');
-        test('A');
-        test('W');
+        ('a'..='z').chain('A'..='Z').for_each(test);
+        assert_eq!(1, Letter::try_from('a').unwrap().0);
+        assert_eq!(52, Letter::try_from('Z').unwrap().0);
+        assert!(Letter::try_from('!').is_err());
+        let letters: Letters = [
+            Letter::try_from('Z').unwrap(),
+            Letter::try_from('a').unwrap(),
+        ]
+        .iter()
+        .collect();
+        assert_eq!("aZ", letters.to_string());
+        assert_eq!((2, None), (letters.len(), letters.only()));
+        assert_eq!(52, Letters::ALL.len());
+        assert_eq!(
+            "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ",
+            Letters::ALL.to_string()
+        );
     }
 
     #[test]
@@ -144,21 +321,47 @@ PmmdzqPrVvPwwTWBwg
 wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
 ttgJtRGJQctTZtZT
 CrZsJsPPZsGzwwsLwLmpwMDw";
-        let rucksacks: Vec<Rucksack> = input.lines().map(|l| l.parse().unwrap()).collect();
+        let rucksacks: Vec<Rucksack> = parse_lines(input).unwrap();
+        assert_eq!(Ok(157), sum_common_priorities(&rucksacks, 2));
+        assert_eq!(Ok(70), sum_badge_priorities(&rucksacks, 3));
+        assert_eq!(
+            "r",
+            groups(&rucksacks, 3).unwrap()[0].common_items().to_string()
+        );
+
+        let thirds: Vec<Rucksack> = parse_lines("abcadeafg\nZbZxZy").unwrap();
+        assert_eq!(Ok(1 + 52), sum_common_priorities(&thirds, 3));
+        assert_eq!(
+            Err(RucksackError::UnevenCompartments {
+                rucksack: 1,
+                items: 9,
+                compartments: 2
+            }),
+            sum_common_priorities(&thirds, 2)
+        );
+        assert_eq!(Ok(2), sum_badge_priorities(&thirds, 2));
+        let error = sum_badge_priorities(&rucksacks[..4], 3).unwrap_err();
+        assert_eq!(
+            "the last group has 1 rucksacks instead of 3",
+            error.to_string()
+        );
+        let error = sum_badge_priorities(&rucksacks, 1).unwrap_err();
+        assert!(matches!(
+            error,
+            RucksackError::NotOneBadge { group: 1, common } if common == rucksacks[0].items()
+        ));
+        let none: Vec<Rucksack> = parse_lines("abcd\naa").unwrap();
         assert_eq!(
-            157,
-            rucksacks
-                .iter()
-                .map(|r| r.get_letter_in_both().0 as u64)
-                .sum::<u64>()
+            "rucksack 1 has no item in every compartment, instead of one",
+            sum_common_priorities(&none, 2).unwrap_err().to_string()
         );
-        let groups = rucksacks_to_groups(&rucksacks);
+        let several: Vec<Rucksack> = parse_lines("aBcaBc").unwrap();
         assert_eq!(
-            70,
-            groups
-                .iter()
-                .map(|g| g.get_letter_in_all().0 as u64)
-                .sum::<u64>()
+            "rucksack 1 has items 'acB' in every compartment, instead of one",
+            sum_common_priorities(&several, 2).unwrap_err().to_string()
         );
+        assert_eq!(Ok(0), sum_common_priorities(&[], 2));
+        let error = parse_lines::<Rucksack>("abAB\nab1c").unwrap_err();
+        assert_eq!((2, 3, "1"), (error.line, error.column, error.text.as_str()));
     }
 }
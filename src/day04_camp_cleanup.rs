@@ -1,63 +1,119 @@
-use std::str::FromStr;
+use std::cmp::Reverse;
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input4.txt").unwrap();
-    let pairs = parse_assignment_pairs(&input);
-    println!(
-        "pairs where range fully contains the other: {}",
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::interval::{Interval, IntervalSet};
+use crate::parse_error::{parse_number, ParseError};
+use crate::repl::Explore;
+use crate::solution::Solution;
+
+const DAY: u8 = 4;
+
+pub struct CampCleanup;
+
+impl Solution for CampCleanup {
+    type Input = Vec<[Interval<u64>; 2]>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(parse_assignment_pairs(input)?)
+    }
+    fn part1(pairs: &Self::Input) -> usize {
         pairs
             .iter()
-            .filter(|pair| pair[0].fully_contains(&pair[1]) || pair[1].fully_contains(&pair[0]))
+            .filter(|pair| {
+                pair[0].contains_interval(&pair[1]) || pair[1].contains_interval(&pair[0])
+            })
             .count()
-    );
-    println!(
-        "pairs that overlap: {}",
+    }
+    fn part2(pairs: &Self::Input) -> usize {
         pairs
             .iter()
             .filter(|pair| pair[0].overlaps(&pair[1]))
             .count()
-    );
+    }
+    fn explore(pairs: &Self::Input) -> Option<Box<dyn Explore + '_>> {
+        Some(Box::new(PairsExplorer(pairs)))
+    }
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        let mut range = || {
+            let from = rng.gen_range(1..=99);
+            format!("{}-{}", from, rng.gen_range(from..=99))
+        };
+        (0..size)
+            .map(|_| format!("{},{}", range(), range()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
-fn parse_assignment_pairs(s: &str) -> Vec<[Range; 2]> {
+fn parse_assignment_pairs(s: &str) -> Result<Vec<[Interval<u64>; 2]>, ParseError> {
     s.lines()
         .map(|line| {
-            let mut parts = line.split(',');
-            [
-                parts.next().unwrap().parse().unwrap(),
-                parts.next().unwrap().parse().unwrap(),
-            ]
+            let (first, second) = line
+                .split_once(',')
+                .ok_or_else(|| ParseError::new(DAY, s, line, "expected a pair of ranges"))?;
+            let parse = |range: &str| parse_range(range).map_err(|e| e.within(s, range));
+            Ok([parse(first)?, parse(second)?])
         })
         .collect()
 }
 
-struct Range {
-    from: u64,
-    to: u64,
-}
-
-impl Range {
-    pub fn fully_contains(&self, other: &Self) -> bool {
-        self.from <= other.from && self.to >= other.to
-    }
-    pub fn overlaps(&self, other: &Self) -> bool {
-        self.from <= other.to && self.to >= other.from
+fn parse_range(s: &str) -> Result<Interval<u64>, ParseError> {
+    let (from, to) = s
+        .trim()
+        .split_once('-')
+        .ok_or_else(|| ParseError::new(DAY, s, s, "cannot parse range"))?;
+    let (from, to) = (parse_number(DAY, s, from)?, parse_number(DAY, s, to)?);
+    if from > to {
+        return Err(ParseError::new(DAY, s, s, "range ends before it starts"));
     }
+    Ok(Interval::new(from, to))
 }
 
-impl FromStr for Range {
-    type Err = ();
+struct PairsExplorer<'a>(&'a [[Interval<u64>; 2]]);
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.trim().split('-');
-        let result = Self {
-            from: parts.next().unwrap().parse().unwrap(),
-            to: parts.next().unwrap().parse().unwrap(),
-        };
-        if parts.next().is_some() {
-            panic!("cannot parse range '{}'", s)
+impl Explore for PairsExplorer<'_> {
+    fn commands(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("unassigned", "list the sections no elf cleans"),
+            ("overlap", "show the pair whose assignments overlap most"),
+        ]
+    }
+    fn run(&mut self, command: &str, _args: &[&str]) -> Option<anyhow::Result<String>> {
+        match command {
+            "unassigned" => {
+                let assigned: IntervalSet<u64> = self.0.iter().flatten().copied().collect();
+                let gaps: Vec<String> = assigned.gaps().map(|gap| gap.to_string()).collect();
+                Some(Ok(match (assigned.min(), assigned.max()) {
+                    (Some(min), Some(max)) if gaps.is_empty() => {
+                        format!("every section from {} to {} is assigned", min, max)
+                    }
+                    (Some(_), Some(_)) => gaps.join(", "),
+                    _ => "no section is assigned".to_string(),
+                }))
+            }
+            "overlap" => Some(Ok(
+                match self
+                    .0
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, pair)| Some((i + 1, pair[0].intersection(&pair[1])?)))
+                    .min_by_key(|(i, overlap)| (Reverse(overlap.len()), *i))
+                {
+                    Some((pair, overlap)) => format!(
+                        "pair {} overlaps most, on {} ({} sections)",
+                        pair,
+                        overlap,
+                        overlap.len()
+                    ),
+                    None => "no pair overlaps".to_string(),
+                },
+            )),
+            _ => None,
         }
-        Ok(result)
     }
 }
 
@@ -73,12 +129,13 @@ mod tests {
 2-8,3-7
 6-6,4-6
 2-6,4-8";
-        let pairs = parse_assignment_pairs(input);
+        let pairs = parse_assignment_pairs(input).unwrap();
         assert_eq!(
             2,
             pairs
                 .iter()
-                .filter(|pair| pair[0].fully_contains(&pair[1]) || pair[1].fully_contains(&pair[0]))
+                .filter(|pair| pair[0].contains_interval(&pair[1])
+                    || pair[1].contains_interval(&pair[0]))
                 .count()
         );
         assert_eq!(
@@ -88,5 +145,28 @@ mod tests {
                 .filter(|pair| pair[0].overlaps(&pair[1]))
                 .count()
         );
+        let error = parse_assignment_pairs("2-4,6-8\n2-3,4-5-6").unwrap_err();
+        assert_eq!(
+            (2, 7, "5-6"),
+            (error.line, error.column, error.text.as_str())
+        );
+        let error = parse_assignment_pairs("2-4,6-8\n3-2,4-5").unwrap_err();
+        assert_eq!(
+            (2, 1, "3-2"),
+            (error.line, error.column, error.text.as_str())
+        );
+
+        let mut explorer = PairsExplorer(&pairs);
+        let mut run = |command| explorer.run(command, &[]).unwrap().unwrap();
+        assert_eq!("every section from 2 to 9 is assigned", run("unassigned"));
+        assert_eq!("pair 4 overlaps most, on 3-7 (5 sections)", run("overlap"));
+        let pairs = parse_assignment_pairs("1-2,8-9\n4-4,6-6").unwrap();
+        assert_eq!(
+            "3-3, 5-5, 7-7",
+            PairsExplorer(&pairs)
+                .run("unassigned", &[])
+                .unwrap()
+                .unwrap()
+        );
     }
 }
@@ -1,34 +1,108 @@
 use std::str::FromStr;
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input5.txt").unwrap();
-    let (mut stacks, instructions) = parse_input(&input);
-    for instruction in instructions.iter() {
-        stacks.move_crate(instruction);
-    }
+use rand::rngs::StdRng;
+use rand::Rng;
 
-    println!("top of stacks: {}", stacks.top_of_stacks());
+use crate::parse_error::{parse_number, ParseError};
+use crate::solution::Solution;
 
-    let (mut stacks, instructions) = parse_input(&input);
-    stacks.move_at_once = true;
-    for instruction in instructions.iter() {
-        stacks.move_crate(instruction);
-    }
+const DAY: u8 = 5;
+
+pub struct SupplyStacks;
+
+impl Solution for SupplyStacks {
+    type Input = (Stacks, Vec<Instruction>);
+    type Answer1 = String;
+    type Answer2 = String;
 
-    println!("top of stacks v2: {}", stacks.top_of_stacks());
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(parse_input(input)?)
+    }
+    fn part1((stacks, instructions): &Self::Input) -> String {
+        let mut stacks = stacks.clone();
+        for instruction in instructions.iter() {
+            stacks.move_crate(instruction);
+        }
+        stacks.top_of_stacks()
+    }
+    fn part2((stacks, instructions): &Self::Input) -> String {
+        let mut stacks = stacks.clone();
+        stacks.move_at_once = true;
+        for instruction in instructions.iter() {
+            stacks.move_crate(instruction);
+        }
+        stacks.top_of_stacks()
+    }
+    /// `size` moves between up to 9 stacks; no move takes the last crate of a stack, so every
+    /// stack has a top crate at the end.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        let mut heights: Vec<usize> = (0..rng.gen_range(3..=9))
+            .map(|_| rng.gen_range(1..=8))
+            .collect();
+        heights[0] = heights[0].max(2);
+        let tallest = *heights.iter().max().unwrap();
+        let mut lines: Vec<String> = (0..tallest)
+            .rev()
+            .map(|level| {
+                heights
+                    .iter()
+                    .map(|&height| match level < height {
+                        true => format!("[{}]", rng.gen_range('A'..='Z')),
+                        false => "   ".to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+        lines.push(
+            (1..=heights.len())
+                .map(|i| format!(" {} ", i))
+                .collect::<Vec<_>>()
+                .join(" "),
+        );
+        lines.push(String::new());
+        for _ in 0..size {
+            let from = loop {
+                let from = rng.gen_range(0..heights.len());
+                if heights[from] > 1 {
+                    break from;
+                }
+            };
+            let to = (from + rng.gen_range(1..heights.len())) % heights.len();
+            let amount = rng.gen_range(1..heights[from]);
+            heights[from] -= amount;
+            heights[to] += amount;
+            lines.push(format!("move {} from {} to {}", amount, from + 1, to + 1));
+        }
+        lines.join("\n")
+    }
 }
 
-fn parse_input(input: &str) -> (Stacks, Vec<Instruction>) {
-    let mut parts = input.split("\n\n");
-    (
-        parts.next().unwrap().parse().unwrap(),
-        parts
-            .next()
-            .unwrap()
-            .lines()
-            .map(|line| line.parse().unwrap())
-            .collect(),
-    )
+fn parse_input(input: &str) -> Result<(Stacks, Vec<Instruction>), ParseError> {
+    let (stacks, instructions) = input.split_once("\n\n").ok_or_else(|| {
+        ParseError::new(
+            DAY,
+            input,
+            input.lines().next().unwrap_or_default(),
+            "expected stacks and instructions separated by a blank line",
+        )
+    })?;
+    let stacks: Stacks = stacks
+        .parse()
+        .map_err(|e: ParseError| e.within(input, stacks))?;
+    let instructions = instructions
+        .lines()
+        .map(|line| {
+            let instruction: Instruction = line
+                .parse()
+                .map_err(|e: ParseError| e.within(input, line))?;
+            if instruction.from.max(instruction.to) >= stacks.stacks.len() {
+                return Err(ParseError::new(DAY, input, line, "no such stack"));
+            }
+            Ok(instruction)
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((stacks, instructions))
 }
 
 #[derive(Debug, Clone)]
@@ -53,8 +127,8 @@ impl Stack {
     }
 }
 
-#[derive(Debug)]
-struct Stacks {
+#[derive(Debug, Clone)]
+pub struct Stacks {
     stacks: Vec<Stack>,
     move_at_once: bool,
 }
@@ -73,36 +147,46 @@ impl Stacks {
 }
 
 #[derive(Debug)]
-struct Instruction {
+pub struct Instruction {
     amount: usize,
     from: usize,
     to: usize,
 }
 
 impl FromStr for Instruction {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.split_whitespace();
-        parts.next();
-        let amount = parts.next().unwrap().parse::<usize>().unwrap();
-        parts.next();
-        let from = parts.next().unwrap().parse::<usize>().unwrap() - 1;
-        parts.next();
-        let to = parts.next().unwrap().parse::<usize>().unwrap() - 1;
-        Ok(Self { amount, from, to })
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        let [_, amount, _, from, _, to] = parts[..] else {
+            return Err(ParseError::new(
+                DAY,
+                s,
+                s,
+                "expected 'move <amount> from <stack> to <stack>'",
+            ));
+        };
+        let stack = |text: &str| match parse_number::<usize>(DAY, s, text)? {
+            0 => Err(ParseError::new(DAY, s, text, "stacks are numbered from 1")),
+            n => Ok(n - 1),
+        };
+        Ok(Self {
+            amount: parse_number(DAY, s, amount)?,
+            from: stack(from)?,
+            to: stack(to)?,
+        })
     }
 }
 
 impl FromStr for Stacks {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut lines = s.lines();
-        let mut line = lines.next().unwrap();
+        let mut line = lines.next().unwrap_or_default();
         let n = line.len() + 1;
         if n % 4 != 0 {
-            panic!("invalid length of Stacks")
+            return Err(ParseError::new(DAY, s, line, "invalid length of Stacks"));
         }
         let mut stacks: Vec<Stack> = vec![Stack::new(); n / 4];
 
@@ -110,8 +194,9 @@ impl FromStr for Stacks {
             let parts: Vec<char> = line.chars().collect();
             for i in 0..stacks.len() {
                 let j = 4 * i + 1;
-                if !parts[j].is_whitespace() {
-                    stacks[i].0.push(parts[j]);
+                match parts.get(j) {
+                    Some(c) if !c.is_whitespace() => stacks[i].0.push(*c),
+                    _ => {}
                 }
             }
 
@@ -157,7 +242,7 @@ move 1 from 1 to 2",
             " ".repeat(4)
         );
 
-        let (mut stacks, instructions) = parse_input(&input);
+        let (mut stacks, instructions) = parse_input(&input).unwrap();
         for instruction in instructions.iter() {
             stacks.move_crate(instruction);
         }
@@ -166,7 +251,7 @@ move 1 from 1 to 2",
         println!("instructions: {:?}", instructions);
         assert_eq!("CMZ", stacks.top_of_stacks());
 
-        let (mut stacks, instructions) = parse_input(&input);
+        let (mut stacks, instructions) = parse_input(&input).unwrap();
         stacks.move_at_once = true;
         for instruction in instructions.iter() {
             stacks.move_crate(instruction);
@@ -175,5 +260,11 @@ move 1 from 1 to 2",
         println!("stacks: {:?}", stacks);
         println!("instructions: {:?}", instructions);
         assert_eq!("MCD", stacks.top_of_stacks());
+
+        let error = parse_input(&input.replace("from 1 to 2", "from 1 to 4")).unwrap_err();
+        assert_eq!(
+            (9, 1, "move 1 from 1 to 4"),
+            (error.line, error.column, error.text.as_str())
+        );
     }
 }
@@ -1,15 +1,37 @@
 use std::collections::HashSet;
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input6.txt").unwrap();
-    println!(
-        "characters before start-of-packet mark is detected: {}",
-        get_start_of_packet_position(&input, 4)
-    );
-    println!(
-        "characters before start-of-message mark is detected: {}",
-        get_start_of_packet_position(&input, 14)
-    );
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::solution::Solution;
+
+pub struct TuningTrouble;
+
+impl Solution for TuningTrouble {
+    type Input = String;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(input.trim().to_string())
+    }
+    fn part1(signal: &Self::Input) -> usize {
+        get_start_of_packet_position(signal, 4)
+    }
+    fn part2(signal: &Self::Input) -> usize {
+        get_start_of_packet_position(signal, 14)
+    }
+    /// `size` letters from a to h, which can hold a start-of-packet marker but not a
+    /// start-of-message one, followed by 14 different letters.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        let mut marker: Vec<char> = ('a'..='z').collect();
+        marker.shuffle(rng);
+        (0..size)
+            .map(|_| rng.gen_range('a'..='h'))
+            .chain(marker.into_iter().take(14))
+            .collect()
+    }
 }
 
 fn get_start_of_packet_position(s: &str, marker_size: usize) -> usize {
@@ -1,34 +1,119 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input7.txt").unwrap();
+use log::info;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
 
-    let dir = build_directories(&input);
-    println!(
-        "sum of total sizes of small directories: {}",
-        dir.get_directories_recursive()
-            .into_iter()
-            .filter(|d| d.cached_size <= 100_000)
-            .map(|d| d.cached_size)
-            .sum::<u64>()
-    );
-
-    let used = dir.cached_size;
-    println!(
-        "initial specs: total {}, used {}, free {}",
-        Directory::TOTAL_SPACE,
-        used,
-        Directory::TOTAL_SPACE - used,
-    );
-
-    println!(
-        "smallest directory that can be deleted: {}",
-        dir.get_smallest_directory_big_enough().cached_size
-    );
+use crate::params::{Parameter, Params};
+use crate::parse_error::{parse_number, ParseError};
+use crate::repl::{self, Explore};
+use crate::solution::Solution;
+
+const DAY: u8 = 7;
+
+pub struct NoSpaceLeftOnDevice;
+
+impl Solution for NoSpaceLeftOnDevice {
+    type Input = Directory;
+    type Answer1 = u64;
+    type Answer2 = u64;
+    const PARAMETERS: &'static [Parameter] = &[
+        Parameter::integer(
+            "small_size",
+            100_000,
+            "largest size of the directories part 1 sums",
+        )
+        .range(0, i64::MAX),
+        Parameter::integer("total_space", 70_000_000, "size of the disk").range(0, i64::MAX),
+        Parameter::integer("necessary_space", 30_000_000, "free space the update needs")
+            .range(0, i64::MAX),
+    ];
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(build_directories(input)?)
+    }
+    fn part1(dir: &Self::Input) -> u64 {
+        Self::part1_with(dir, &Params::defaults(Self::PARAMETERS))
+    }
+    fn part2(dir: &Self::Input) -> u64 {
+        Self::part2_with(dir, &Params::defaults(Self::PARAMETERS))
+    }
+    fn part1_with(dir: &Self::Input, params: &Params) -> u64 {
+        sum_small_directories(dir, params.integer("small_size") as u64)
+    }
+    fn part2_with(dir: &Self::Input, params: &Params) -> u64 {
+        let total = params.integer("total_space") as u64;
+        let used = dir.cached_size;
+        info!(
+            "initial specs: total {}, used {}, free {}",
+            total,
+            used,
+            total.saturating_sub(used),
+        );
+        dir.get_smallest_directory_big_enough(total, params.integer("necessary_space") as u64)
+            .cached_size
+    }
+    fn explore(dir: &Self::Input) -> Option<Box<dyn Explore + '_>> {
+        Some(Box::new(DirectoryExplorer(dir)))
+    }
+    /// A tree of `size` directories below `/`, each with up to 4 files, explored depth first.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        let mut children: Vec<Vec<usize>> = vec![vec![]];
+        for directory in 1..=size {
+            children[rng.gen_range(0..directory)].push(directory);
+            children.push(vec![]);
+        }
+        let mut lines = vec!["$ cd /".to_string()];
+        explore(0, &children, rng, &mut lines);
+        lines.join("\n")
+    }
+}
+
+/// Lists `directory`, then changes into each of its children in turn.
+fn explore(directory: usize, children: &[Vec<usize>], rng: &mut StdRng, lines: &mut Vec<String>) {
+    let mut names = HashSet::new();
+    let mut unique_name = |rng: &mut StdRng| loop {
+        let name: String = (0..rng.gen_range(1..=8))
+            .map(|_| rng.gen_range('a'..='z'))
+            .collect();
+        if names.insert(name.clone()) {
+            return name;
+        }
+    };
+    let directories: Vec<String> = children[directory]
+        .iter()
+        .map(|_| unique_name(rng))
+        .collect();
+    let mut entries: Vec<String> = directories
+        .iter()
+        .map(|name| format!("dir {}", name))
+        .collect();
+    for _ in 0..rng.gen_range(0..=4) {
+        let name = unique_name(rng);
+        entries.push(format!("{} {}", rng.gen_range(1000..=300_000), name));
+    }
+    entries.shuffle(rng);
+    lines.push("$ ls".to_string());
+    lines.extend(entries);
+    for (&child, name) in children[directory].iter().zip(directories) {
+        lines.push(format!("$ cd {}", name));
+        explore(child, children, rng, lines);
+        lines.push("$ cd ..".to_string());
+    }
+}
+
+fn sum_small_directories(dir: &Directory, max_size: u64) -> u64 {
+    dir.get_directories_recursive()
+        .into_iter()
+        .filter(|d| d.cached_size <= max_size)
+        .map(|d| d.cached_size)
+        .sum()
 }
 
-fn build_directories(s: &str) -> Directory {
-    let instructions = parse_input(s);
+fn build_directories(s: &str) -> Result<Directory, ParseError> {
+    let instructions = parse_input(s)?;
 
     let mut dir = Directory::new("/".to_string());
     let mut parents: Vec<Directory> = Vec::new();
@@ -50,10 +135,9 @@ fn build_directories(s: &str) -> Directory {
                 return_to_root(&mut dir, &mut parents);
             }
             Instruction::ChangeTo(name) => {
-                let next = dir
-                    .directories
-                    .remove(&name)
-                    .expect("cannot find directory to move to");
+                let next = dir.directories.remove(name).ok_or_else(|| {
+                    ParseError::new(DAY, s, name, "cannot find directory to move to")
+                })?;
                 parents.push(dir);
                 dir = next;
             }
@@ -74,44 +158,46 @@ fn build_directories(s: &str) -> Directory {
     }
     return_to_root(&mut dir, &mut parents);
     dir.update_cached_size();
-    dir
+    Ok(dir)
 }
 
-fn parse_input(s: &str) -> Vec<Instruction> {
-    let s = s.trim_start_matches("$ ");
+fn parse_input(input: &str) -> Result<Vec<Instruction<'_>>, ParseError> {
+    let s = input.trim_start_matches("$ ");
     s.split("\n$ ")
         .map(|c| {
             let mut words = c.split_whitespace();
-            match words.next().expect("invalid command") {
-                "cd" => match words.next().unwrap() {
-                    "/" => Instruction::ChangeToRoot,
-                    ".." => Instruction::ChangeToPrevious,
-                    name => Instruction::ChangeTo(name.to_string()),
+            let error = |text: &str, message: &str| ParseError::new(DAY, input, text, message);
+            Ok(
+                match words.next().ok_or_else(|| error(c, "missing command"))? {
+                    "cd" => match words.next().ok_or_else(|| error(c, "missing directory"))? {
+                        "/" => Instruction::ChangeToRoot,
+                        ".." => Instruction::ChangeToPrevious,
+                        name => Instruction::ChangeTo(name),
+                    },
+                    "ls" => Instruction::List(
+                        c.lines()
+                            .skip(1)
+                            .map(|line| {
+                                let (size, name) = line.split_once(' ').ok_or_else(|| {
+                                    error(line, "expected '<size> <name>' or 'dir <name>'")
+                                })?;
+                                if size == "dir" {
+                                    Ok((None, name))
+                                } else {
+                                    Ok((Some(parse_number(DAY, input, size)?), name))
+                                }
+                            })
+                            .collect::<Result<_, _>>()?,
+                    ),
+                    other => return Err(error(other, "invalid instruction")),
                 },
-                "ls" => Instruction::List(
-                    c.lines()
-                        .skip(1)
-                        .map(|line| {
-                            let mut parts = line.split_whitespace();
-                            let size = parts.next().unwrap();
-                            if size == "dir" {
-                                (None, parts.next().unwrap().to_string())
-                            } else {
-                                (
-                                    Some(size.parse::<u64>().unwrap()),
-                                    parts.next().unwrap().to_string(),
-                                )
-                            }
-                        })
-                        .collect(),
-                ),
-                other => panic!("invalid instruction '{}'", other),
-            }
+            )
         })
         .collect()
 }
 
-struct Directory {
+#[derive(Debug)]
+pub struct Directory {
     name: String,
     files: HashMap<String, u64>,
     directories: HashMap<String, Directory>,
@@ -119,9 +205,6 @@ struct Directory {
 }
 
 impl Directory {
-    pub const TOTAL_SPACE: u64 = 70_000_000;
-    pub const NECESSARY_SPACE: u64 = 30_000_000;
-
     pub fn new(name: String) -> Self {
         Self {
             name,
@@ -152,21 +235,80 @@ impl Directory {
         }
         directories
     }
-    pub fn get_smallest_directory_big_enough(&self) -> &Directory {
-        let needed = Self::NECESSARY_SPACE - (Self::TOTAL_SPACE - self.cached_size);
+    /// Panics if even deleting everything doesn't free `necessary` bytes of a `total` byte disk.
+    pub fn get_smallest_directory_big_enough(&self, total: u64, necessary: u64) -> &Directory {
+        let needed = necessary.saturating_sub(total.saturating_sub(self.cached_size));
         self.get_directories_recursive()
             .into_iter()
             .filter(|dir| dir.cached_size >= needed)
             .min_by_key(|dir| dir.cached_size)
-            .unwrap()
+            .expect("no directory is big enough")
+    }
+    /// The directory at `path` below this one, such as `/a/e`.
+    pub fn get(&self, path: &str) -> Option<&Directory> {
+        path.split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(self, |dir, name| dir.directories.get(name))
+    }
+    fn write_tree(&self, f: &mut Formatter<'_>, depth: usize) -> std::fmt::Result {
+        let indent = "  ".repeat(depth);
+        writeln!(
+            f,
+            "{}- {} (dir, size={})",
+            indent, self.name, self.cached_size
+        )?;
+        let mut names: Vec<&String> = self.directories.keys().chain(self.files.keys()).collect();
+        names.sort();
+        for name in names {
+            match self.directories.get(name) {
+                Some(dir) => dir.write_tree(f, depth + 1)?,
+                None => writeln!(
+                    f,
+                    "{}  - {} (file, size={})",
+                    indent, name, self.files[name]
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The tree as the puzzle shows it, with the total size of each directory.
+impl Display for Directory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.write_tree(f, 0)
+    }
+}
+
+struct DirectoryExplorer<'a>(&'a Directory);
+
+impl Explore for DirectoryExplorer<'_> {
+    fn commands(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "dir [path]",
+            "show the tree below a directory, / by default",
+        )]
+    }
+    fn run(&mut self, command: &str, args: &[&str]) -> Option<anyhow::Result<String>> {
+        match command {
+            "dir" => Some(
+                repl::optional_argument(args, 0, "path", "/".to_string()).and_then(
+                    |path| match self.0.get(&path) {
+                        Some(dir) => Ok(dir.to_string().trim_end().to_string()),
+                        None => Err(anyhow::anyhow!("no directory {}", path)),
+                    },
+                ),
+            ),
+            _ => None,
+        }
     }
 }
 
-enum Instruction {
+enum Instruction<'a> {
     ChangeToRoot,
-    ChangeTo(String),
+    ChangeTo(&'a str),
     ChangeToPrevious,
-    List(Vec<(Option<u64>, String)>),
+    List(Vec<(Option<u64>, &'a str)>),
 }
 
 #[cfg(test)]
@@ -199,19 +341,37 @@ $ ls
 5626152 d.ext
 7214296 k";
 
-        let dir = build_directories(input);
+        let dir = build_directories(input).unwrap();
         assert_eq!(
-            95437,
-            dir.get_directories_recursive()
-                .into_iter()
-                .filter(|d| d.cached_size <= 100_000)
-                .map(|d| d.cached_size)
-                .sum::<u64>()
+            "- e (dir, size=584)\n  - i (file, size=584)\n",
+            dir.get("/a/e").unwrap().to_string()
         );
+        assert!(dir.get("a/x").is_none());
+        assert_eq!(
+            4,
+            dir.to_string()
+                .lines()
+                .filter(|l| l.contains("dir"))
+                .count()
+        );
+        assert_eq!(95437, sum_small_directories(&dir, 100_000));
+        assert_eq!(584, sum_small_directories(&dir, 1000));
 
         assert_eq!(
             24933642,
-            dir.get_smallest_directory_big_enough().cached_size
-        )
+            dir.get_smallest_directory_big_enough(70_000_000, 30_000_000)
+                .cached_size
+        );
+        assert_eq!(
+            94853,
+            dir.get_smallest_directory_big_enough(70_000_000, 21_700_000)
+                .cached_size
+        );
+
+        let error = build_directories(&input.replace("$ cd d", "$ cd x")).unwrap_err();
+        assert_eq!(
+            (18, 6, "x"),
+            (error.line, error.column, error.text.as_str())
+        );
     }
 }
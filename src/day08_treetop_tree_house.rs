@@ -1,193 +1,113 @@
-use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input8.txt").unwrap();
-    let forest: Forest = input.parse().unwrap();
-    println!("trees visible from outside: {}", forest.trees_visible());
-    println!("highest scenic score: {}", forest.highest_scenic_score());
-}
+use rand::rngs::StdRng;
+use rand::Rng;
 
-struct Forest {
-    grid: HashMap<Coord, Tree>,
-    x_len: usize,
-    y_len: usize,
-}
+use crate::grid::{Coord, Grid2D};
+use crate::parse_error::ParseError;
+use crate::solution::Solution;
 
-#[derive(Clone)]
-struct ReversibleRange {
-    range_from: usize,
-    range_to: usize,
-    reverse: bool,
-}
+const DAY: u8 = 8;
 
-impl Iterator for ReversibleRange {
-    type Item = usize;
+pub struct TreetopTreeHouse;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if !self.reverse {
-            if self.range_from < self.range_to {
-                let val = self.range_from;
-                self.range_from += 1;
-                Some(val)
-            } else {
-                None
-            }
-        } else {
-            if self.range_to > self.range_from {
-                self.range_to -= 1;
-                Some(self.range_to)
-            } else {
-                None
-            }
-        }
+impl Solution for TreetopTreeHouse {
+    type Input = Forest;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(input.parse::<Forest>()?)
+    }
+    fn part1(forest: &Self::Input) -> usize {
+        forest.trees_visible()
+    }
+    fn part2(forest: &Self::Input) -> usize {
+        forest.highest_scenic_score()
+    }
+    /// A forest of `size` by `size` trees.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                (0..size)
+                    .map(|_| rng.gen_range('0'..='9'))
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
     }
 }
 
+pub struct Forest {
+    grid: Grid2D<u8>,
+}
+
 impl Forest {
     pub fn trees_visible(&self) -> usize {
-        let mut visible = HashSet::new();
-        self.trees_visible_traverse(true, true, &mut visible);
-        self.trees_visible_traverse(false, true, &mut visible);
-        self.trees_visible_traverse(true, false, &mut visible);
-        self.trees_visible_traverse(false, false, &mut visible);
-        visible.len()
+        let from_sides = Self::visible_in_rows(&self.grid);
+        let from_top_or_bottom = Self::visible_in_rows(&self.grid.transpose()).transpose();
+        from_sides
+            .iter()
+            .filter(|&(c, &visible)| visible || from_top_or_bottom[c])
+            .count()
     }
-    fn trees_visible_traverse(&self, is_x: bool, low_to_high: bool, visible: &mut HashSet<Coord>) {
-        let (max_first, max_second) = if is_x {
-            (self.x_len, self.y_len)
-        } else {
-            (self.y_len, self.x_len)
-        };
-        let iter = ReversibleRange {
-            range_from: 0,
-            range_to: max_second,
-            reverse: !low_to_high,
-        };
-        for first in 0..max_first {
-            let mut iter = iter.clone();
-            let mut coord = if is_x {
-                Coord::new(first, iter.next().unwrap())
-            } else {
-                Coord::new(iter.next().unwrap(), first)
-            };
-            visible.insert(coord.clone());
-            let mut max = self.grid.get(&coord).unwrap().0;
-            for second in iter {
-                if is_x {
-                    coord.y = second;
-                } else {
-                    coord.x = second;
-                }
-                let val = self.grid.get(&coord).unwrap().0;
-                if val > max {
-                    max = val;
-                    visible.insert(coord.clone());
+    /// Marks the trees that can be seen from the left or the right of their row.
+    fn visible_in_rows(grid: &Grid2D<u8>) -> Grid2D<bool> {
+        let mut visible = Grid2D::new(grid.width(), grid.height(), false);
+        for (y, row) in grid.rows().enumerate() {
+            let mut look_along = |xs: &mut dyn Iterator<Item = usize>| {
+                let mut tallest = None;
+                for x in xs {
+                    if tallest.is_none_or(|t| row[x] > t) {
+                        tallest = Some(row[x]);
+                        visible[Coord::new(x as i64, y as i64)] = true;
+                    }
                 }
-            }
+            };
+            look_along(&mut (0..row.len()));
+            look_along(&mut (0..row.len()).rev());
         }
+        visible
     }
-    fn trees_visible_traverse_from(&self, coord: &Coord, on_x: bool, positive_dir: bool) -> usize {
-        let iter: ReversibleRange = if on_x {
-            if positive_dir {
-                ReversibleRange {
-                    range_from: coord.x + 1,
-                    range_to: self.x_len,
-                    reverse: false,
-                }
-            } else {
-                ReversibleRange {
-                    range_from: 0,
-                    range_to: coord.x,
-                    reverse: true,
-                }
-            }
-        } else {
-            if positive_dir {
-                ReversibleRange {
-                    range_from: coord.y + 1,
-                    range_to: self.y_len,
-                    reverse: false,
-                }
-            } else {
-                ReversibleRange {
-                    range_from: 0,
-                    range_to: coord.y,
-                    reverse: true,
-                }
-            }
-        };
-        let height = self.grid.get(coord).unwrap().0;
+    fn viewing_distance(&self, from: Coord, direction: Coord) -> usize {
+        let height = self.grid[from];
         let mut n = 0;
-        for i in iter {
+        let mut coord = from + direction;
+        while let Some(&h) = self.grid.get(coord) {
             n += 1;
-            let c = if on_x {
-                Coord::new(i, coord.y)
-            } else {
-                Coord::new(coord.x, i)
-            };
-            if self.grid.get(&c).unwrap().0 >= height {
+            if h >= height {
                 break;
             }
+            coord = coord + direction;
         }
         n
     }
-    fn get_tree_score(&self, coord: &Coord) -> usize {
-        self.trees_visible_traverse_from(coord, true, true)
-            * self.trees_visible_traverse_from(coord, true, false)
-            * self.trees_visible_traverse_from(coord, false, true)
-            * self.trees_visible_traverse_from(coord, false, false)
+    fn get_tree_score(&self, coord: Coord) -> usize {
+        coord
+            .neighbors4()
+            .map(|n| self.viewing_distance(coord, n - coord))
+            .product()
     }
     pub fn highest_scenic_score(&self) -> usize {
-        (0..self.x_len)
-            .flat_map(|x| (0..self.y_len).map(move |y| (x, y)))
-            .map(|(x, y)| {
-                let coord = Coord::new(x, y);
-                self.get_tree_score(&coord)
-            })
+        self.grid
+            .coords()
+            .map(|coord| self.get_tree_score(coord))
             .max()
             .unwrap()
     }
 }
 
-#[derive(Eq, PartialEq, Hash, Clone)]
-struct Coord {
-    x: usize,
-    y: usize,
-}
-
-impl Coord {
-    pub fn new(x: usize, y: usize) -> Self {
-        Self { x, y }
-    }
-}
-
-struct Tree(u8);
-
-impl Tree {
-    pub fn new(height: u8) -> Self {
-        Self(height)
-    }
-}
-
 impl FromStr for Forest {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let grid: HashMap<Coord, Tree> = s
-            .lines()
-            .enumerate()
-            .flat_map(|(y, line)| {
-                line.chars()
-                    .enumerate()
-                    .map(move |(x, c)| (Coord::new(x, y), Tree::new(c.to_digit(10).unwrap() as u8)))
-            })
-            .collect();
-
-        let x_len = grid.keys().map(|c| c.x).max().unwrap() + 1;
-        let y_len = grid.keys().map(|c| c.y).max().unwrap() + 1;
-
-        Ok(Self { grid, x_len, y_len })
+        let grid = Grid2D::parse(DAY, s, "invalid tree height", |_, c| {
+            c.to_digit(10).map(|height| height as u8)
+        })?;
+        if grid.width() == 0 {
+            return Err(ParseError::new(DAY, s, s, "empty forest"));
+        }
+        Ok(Self { grid })
     }
 }
 
@@ -205,5 +125,8 @@ mod tests {
         let forest: Forest = input.parse().unwrap();
         assert_eq!(21, forest.trees_visible());
         assert_eq!(8, forest.highest_scenic_score());
+
+        let error = "303\n2a5".parse::<Forest>().err().unwrap();
+        assert_eq!((2, 2, "a"), (error.line, error.column, error.text.as_str()));
     }
 }
@@ -2,23 +2,58 @@ use std::collections::HashSet;
 use std::ops::{AddAssign, Sub};
 use std::str::FromStr;
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input9.txt").unwrap();
-    let instructions: Vec<Instruction> = input.lines().map(|l| l.parse().unwrap()).collect();
-    let mut grid = Grid::new(2);
-    for instruction in instructions.iter() {
-        grid.step(instruction);
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::params::{Parameter, Params};
+use crate::parse_error::{parse_lines, parse_number, ParseError};
+use crate::solution::Solution;
+
+const DAY: u8 = 9;
+
+pub struct RopeBridge;
+
+impl Solution for RopeBridge {
+    type Input = Vec<Instruction>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+    const PARAMETERS: &'static [Parameter] = &[
+        Parameter::integer("part1_knots", 2, "knots of the rope in part 1").range(1, 10_000),
+        Parameter::integer("part2_knots", 10, "knots of the rope in part 2").range(1, 10_000),
+    ];
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(parse_lines(input)?)
+    }
+    fn part1(instructions: &Self::Input) -> usize {
+        Self::part1_with(instructions, &Params::defaults(Self::PARAMETERS))
+    }
+    fn part2(instructions: &Self::Input) -> usize {
+        Self::part2_with(instructions, &Params::defaults(Self::PARAMETERS))
     }
-    println!("tail visits: {}", grid.amount_visited_tail());
+    fn part1_with(instructions: &Self::Input, params: &Params) -> usize {
+        amount_visited_tail(instructions, params.integer("part1_knots") as usize)
+    }
+    fn part2_with(instructions: &Self::Input, params: &Params) -> usize {
+        amount_visited_tail(instructions, params.integer("part2_knots") as usize)
+    }
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                let direction = ['U', 'D', 'L', 'R'][rng.gen_range(0..4)];
+                format!("{} {}", direction, rng.gen_range(1..=20))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
 
-    let mut grid = Grid::new(10);
+fn amount_visited_tail(instructions: &[Instruction], length: usize) -> usize {
+    let mut grid = Grid::new(length);
     for instruction in instructions.iter() {
         grid.step(instruction);
     }
-    println!(
-        "tail visits with 10 segments: {}",
-        grid.amount_visited_tail()
-    );
+    grid.amount_visited_tail()
 }
 
 #[derive(Clone, Default, Eq, PartialEq, Hash)]
@@ -107,38 +142,42 @@ impl Grid {
 }
 
 #[derive(Copy, Clone)]
-enum Direction {
+pub(crate) enum Direction {
     Up,
     Down,
     Left,
     Right,
 }
 
-impl From<char> for Direction {
-    fn from(value: char) -> Self {
-        match value {
-            'U' => Self::Up,
-            'D' => Self::Down,
-            'L' => Self::Left,
-            'R' => Self::Right,
-            other => panic!("invalid direction '{}'", other),
-        }
+impl FromStr for Direction {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "U" => Self::Up,
+            "D" => Self::Down,
+            "L" => Self::Left,
+            "R" => Self::Right,
+            other => return Err(ParseError::new(DAY, s, other, "invalid direction")),
+        })
     }
 }
 
-struct Instruction {
+pub struct Instruction {
     direction: Direction,
     amount: usize,
 }
 
 impl FromStr for Instruction {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.split_whitespace();
+        let (direction, amount) = s
+            .split_once(' ')
+            .ok_or_else(|| ParseError::new(DAY, s, s, "expected '<direction> <amount>'"))?;
         Ok(Self {
-            direction: Direction::from(parts.next().unwrap().chars().next().unwrap()),
-            amount: parts.next().unwrap().parse().unwrap(),
+            direction: direction.parse()?,
+            amount: parse_number(DAY, s, amount)?,
         })
     }
 }
@@ -163,6 +202,9 @@ R 2";
             grid.step(instruction);
         }
         assert_eq!(13, grid.amount_visited_tail());
+
+        let error = parse_lines::<Instruction>("R 4\nX 4").err().unwrap();
+        assert_eq!((2, 1, "X"), (error.line, error.column, error.text.as_str()));
     }
     #[test]
     fn test2() {
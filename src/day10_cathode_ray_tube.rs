@@ -1,51 +1,107 @@
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input10.txt").unwrap();
-    let mut cpu = Cpu::new(InstructionSequence::new(
-        input.lines().map(|l| l.parse().unwrap()).collect(),
-    ));
-    println!(
-        "sum signal strengths: {}",
-        cpu.clone()
-            .sum_signal_strengths(vec![20, 60, 100, 140, 180, 220])
-    );
-
-    let mut crt = Crt::new(&mut cpu);
-    crt.run();
-    println!("result:\n{}", crt);
-}
-
-struct Crt<'a> {
-    cpu: &'a mut Cpu,
-    screen: [[bool; 40]; 6],
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::params::{Parameter, Params};
+use crate::parse_error::{parse_lines, parse_number, ParseError};
+use crate::solution::Solution;
+use crate::visualize::{Frame, Visualize};
+
+const DAY: u8 = 10;
+
+pub struct CathodeRayTube;
+
+impl Solution for CathodeRayTube {
+    type Input = Vec<Instruction>;
+    type Answer1 = i64;
+    type Answer2 = String;
+    const PARAMETERS: &'static [Parameter] = &[Parameter::list(
+        "cycles",
+        &[20, 60, 100, 140, 180, 220],
+        "cycles whose signal strengths part 1 sums",
+    )
+    .range(1, i64::MAX)];
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(parse_lines(input)?)
+    }
+    fn part1(instructions: &Self::Input) -> i64 {
+        Self::part1_with(instructions, &Params::defaults(Self::PARAMETERS))
+    }
+    fn part1_with(instructions: &Self::Input, params: &Params) -> i64 {
+        Cpu::new(InstructionSequence::new(instructions.clone()))
+            .sum_signal_strengths(params.list("cycles"))
+    }
+    fn part2(instructions: &Self::Input) -> String {
+        let mut crt = Crt::new(Cpu::new(InstructionSequence::new(instructions.clone())));
+        crt.run();
+        crt.to_string()
+    }
+    /// At least `size` instructions, and enough of them to draw the whole screen. The sprite
+    /// stays on the screen.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        let (mut lines, mut cycles, mut register) = (vec![], 0, 1);
+        while lines.len() < size || cycles < Crt::WIDTH * Crt::HEIGHT {
+            if rng.gen_bool(0.3) {
+                lines.push("noop".to_string());
+                cycles += 1;
+            } else {
+                let value = rng.gen_range(-10..=10).clamp(-1 - register, 40 - register);
+                register += value;
+                lines.push(format!("addx {}", value));
+                cycles += 2;
+            }
+        }
+        lines.join("\n")
+    }
+    /// The CRT drawing part 2's letters one pixel per cycle.
+    fn visualize(instructions: &Self::Input, part: u8) -> Option<Box<dyn Visualize + '_>> {
+        if part != 2 {
+            return None;
+        }
+        let mut crt = Crt::new(Cpu::new(InstructionSequence::new(instructions.clone())));
+        Some(Box::new((0..Crt::WIDTH * Crt::HEIGHT).map(move |i| {
+            crt.draw_pixel(i);
+            Frame::new(format!("cycle {}", i + 1), &crt)
+        })))
+    }
+}
+
+struct Crt {
+    cpu: Cpu,
+    screen: [[bool; Crt::WIDTH]; Crt::HEIGHT],
 }
 
-impl<'a> Crt<'a> {
-    pub fn new(cpu: &'a mut Cpu) -> Self {
+impl Crt {
+    const WIDTH: usize = 40;
+    const HEIGHT: usize = 6;
+
+    pub fn new(cpu: Cpu) -> Self {
         Self {
             cpu,
-            screen: [[false; 40]; 6],
+            screen: [[false; Self::WIDTH]; Self::HEIGHT],
         }
     }
     pub fn run(&mut self) {
-        for (i, pixel) in self
-            .screen
-            .iter_mut()
-            .flat_map(|pixel_row| pixel_row.iter_mut().enumerate())
-        {
-            *pixel = self
-                .cpu
-                .cycles()
-                .next()
-                .expect("ran out of instructions")
-                .sprite_seen_at_pixel(i);
+        for i in 0..Self::WIDTH * Self::HEIGHT {
+            self.draw_pixel(i);
         }
     }
+    /// Runs one cycle, drawing the `i`th pixel counting row by row.
+    fn draw_pixel(&mut self, i: usize) {
+        let (row, column) = (i / Self::WIDTH, i % Self::WIDTH);
+        self.screen[row][column] = self
+            .cpu
+            .cycles()
+            .next()
+            .expect("ran out of instructions")
+            .sprite_seen_at_pixel(column);
+    }
 }
 
-impl<'a> Display for Crt<'a> {
+impl Display for Crt {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
@@ -94,22 +150,12 @@ impl Cpu {
             instructions,
         }
     }
-    pub fn sum_signal_strengths(&mut self, cycles: Vec<usize>) -> i64 {
+    /// Cycles after the program ends have no signal.
+    pub fn sum_signal_strengths(&mut self, cycles: &[i64]) -> i64 {
         self.cycles()
-            .skip(cycles[0] - 1)
-            .next()
-            .unwrap()
-            .signal_strength()
-            + cycles
-                .windows(2)
-                .map(|w| {
-                    self.cycles()
-                        .skip(w[1] - w[0] - 1)
-                        .next()
-                        .unwrap()
-                        .signal_strength()
-                })
-                .sum::<i64>()
+            .filter(|state| cycles.contains(&(state.cycle as i64)))
+            .map(|state| state.signal_strength())
+            .sum()
     }
     pub fn cycles(&mut self) -> CpuCycles {
         CpuCycles { cpu: self }
@@ -178,20 +224,19 @@ impl Iterator for InstructionSequence {
 }
 
 #[derive(Clone)]
-enum Instruction {
+pub enum Instruction {
     NoOperation,
     AddX(i64),
 }
 
 impl FromStr for Instruction {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut words = s.split_whitespace();
-        Ok(match words.next().unwrap() {
-            "noop" => Self::NoOperation,
-            "addx" => Self::AddX(words.next().unwrap().parse().unwrap()),
-            other => panic!("invalid instruction '{}'", other),
+        Ok(match s.split_once(' ') {
+            None if s == "noop" => Self::NoOperation,
+            Some(("addx", value)) => Self::AddX(parse_number(DAY, s, value)?),
+            _ => return Err(ParseError::new(DAY, s, s, "invalid instruction")),
         })
     }
 }
@@ -203,15 +248,18 @@ mod tests {
     #[test]
     fn test1() {
         let input = "addx 15\naddx -11\naddx 6\naddx -3\naddx 5\naddx -1\naddx -8\naddx 13\naddx 4\nnoop\naddx -1\naddx 5\naddx -1\naddx 5\naddx -1\naddx 5\naddx -1\naddx 5\naddx -1\naddx -35\naddx 1\naddx 24\naddx -19\naddx 1\naddx 16\naddx -11\nnoop\nnoop\naddx 21\naddx -15\nnoop\nnoop\naddx -3\naddx 9\naddx 1\naddx -3\naddx 8\naddx 1\naddx 5\nnoop\nnoop\nnoop\nnoop\nnoop\naddx -36\nnoop\naddx 1\naddx 7\nnoop\nnoop\nnoop\naddx 2\naddx 6\nnoop\nnoop\nnoop\nnoop\nnoop\naddx 1\nnoop\nnoop\naddx 7\naddx 1\nnoop\naddx -13\naddx 13\naddx 7\nnoop\naddx 1\naddx -33\nnoop\nnoop\nnoop\naddx 2\nnoop\nnoop\nnoop\naddx 8\nnoop\naddx -1\naddx 2\naddx 1\nnoop\naddx 17\naddx -9\naddx 1\naddx 1\naddx -3\naddx 11\nnoop\nnoop\naddx 1\nnoop\naddx 1\nnoop\nnoop\naddx -13\naddx -19\naddx 1\naddx 3\naddx 26\naddx -30\naddx 12\naddx -1\naddx 3\naddx 1\nnoop\nnoop\nnoop\naddx -9\naddx 18\naddx 1\naddx 2\nnoop\nnoop\naddx 9\nnoop\nnoop\nnoop\naddx -1\naddx 2\naddx -37\naddx 1\naddx 3\nnoop\naddx 15\naddx -21\naddx 22\naddx -6\naddx 1\nnoop\naddx 2\naddx 1\nnoop\naddx -10\nnoop\nnoop\naddx 20\naddx 1\naddx 2\naddx 2\naddx -6\naddx -11\nnoop\nnoop\nnoop";
-        let mut cpu = Cpu::new(InstructionSequence::new(
-            input.lines().map(|l| l.parse().unwrap()).collect(),
-        ));
+        let instructions: Vec<Instruction> = input.lines().map(|l| l.parse().unwrap()).collect();
+        let cpu = Cpu::new(InstructionSequence::new(instructions.clone()));
         assert_eq!(
             13140,
             cpu.clone()
-                .sum_signal_strengths(vec![20, 60, 100, 140, 180, 220])
+                .sum_signal_strengths(&[20, 60, 100, 140, 180, 220])
         );
-        let mut crt = Crt::new(&mut cpu);
+        assert_eq!(
+            20 * 21 + 220 * 18,
+            cpu.clone().sum_signal_strengths(&[220, 20, 20, 1000])
+        );
+        let mut crt = Crt::new(cpu);
         crt.run();
         assert_eq!(
             "\
@@ -223,5 +271,17 @@ mod tests {
 #######.......#######.......#######.....",
             format!("{}", crt)
         );
+        let mut frames = CathodeRayTube::visualize(&instructions, 2).unwrap();
+        let last = std::iter::from_fn(|| frames.next_frame()).last().unwrap();
+        assert_eq!(
+            ("cycle 240", crt.to_string()),
+            (last.caption.as_str(), last.picture)
+        );
+
+        let error = parse_lines::<Instruction>("noop\naddx x1").err().unwrap();
+        assert_eq!(
+            (2, 6, "x1"),
+            (error.line, error.column, error.text.as_str())
+        );
     }
 }
@@ -1,30 +1,127 @@
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 use std::sync::atomic::{AtomicU64, Ordering};
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input11.txt").unwrap();
-    let mut monkeys: Vec<Monkey> = input
-        .split("\n\n")
-        .map(|line| line.parse().unwrap())
-        .collect();
-    println!(
-        "level of monkey business: {}",
-        get_monkey_business_level(&mut monkeys, 20)
-    );
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
 
-    let mut monkeys: Vec<Monkey> = input
-        .split("\n\n")
-        .map(|line| line.parse().unwrap())
-        .collect();
-    monkeys
-        .iter_mut()
-        .for_each(|m| m.reduce_worry_level = false);
-    assign_lcd(&mut monkeys);
-    println!(
-        "without reducing worry level: {}",
+use crate::parse_error::{parse_number, ParseError};
+use crate::solution::Solution;
+
+const DAY: u8 = 11;
+
+pub struct MonkeyInTheMiddle;
+
+impl Solution for MonkeyInTheMiddle {
+    type Input = Vec<Monkey>;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(parse_monkeys(input)?)
+    }
+    fn part1(monkeys: &Self::Input) -> u64 {
+        get_monkey_business_level(&mut monkeys.clone(), 20)
+    }
+    fn part2(monkeys: &Self::Input) -> u64 {
+        let mut monkeys = monkeys.clone();
+        monkeys
+            .iter_mut()
+            .for_each(|m| m.reduce_worry_level = false);
+        assign_lcd(&mut monkeys);
         get_monkey_business_level(&mut monkeys, 10_000)
-    );
+    }
+    /// `size` monkeys, between 2 and 9 so that each can test a different prime and squaring
+    /// stays within a `u64` in part 2. Monkeys are drawn until part 1 doesn't overflow either,
+    /// giving up on the monkey that squares, then on multiplications, after a while.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        let count = size.clamp(2, 9);
+        for attempt in 0.. {
+            let mut primes = vec![2, 3, 5, 7, 11, 13, 17, 19, 23];
+            primes.shuffle(rng);
+            let squarer = (attempt < 100).then(|| rng.gen_range(0..count));
+            let monkeys: Vec<Monkey> = (0..count)
+                .map(|i| {
+                    let other = |rng: &mut StdRng| (i + rng.gen_range(1..count)) % count;
+                    Monkey {
+                        items: (0..rng.gen_range(1..=8))
+                            .map(|_| Item::new(rng.gen_range(50..=99)))
+                            .collect(),
+                        operation: match squarer == Some(i) {
+                            true => Operation::Square,
+                            false if attempt >= 200 || rng.gen_bool(0.5) => {
+                                Operation::Add(rng.gen_range(1..=8))
+                            }
+                            false => Operation::Multiply(rng.gen_range(2..=19)),
+                        },
+                        test: MonkeyTest {
+                            divisible_by: primes[i],
+                        },
+                        next: NextMonkey {
+                            if_true: other(rng),
+                            if_false: other(rng),
+                        },
+                        items_inspected: 0,
+                        reduce_worry_level: true,
+                        least_common_denominator: u64::MAX,
+                    }
+                })
+                .collect();
+            if fits_in_u64(monkeys.clone(), 20) {
+                return monkeys
+                    .iter()
+                    .enumerate()
+                    .map(|(i, monkey)| format!("Monkey {}:\n{}", i, monkey))
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+            }
+        }
+        unreachable!()
+    }
+}
+
+/// Whether every worry level stays within a `u64` for `rounds` rounds of part 1.
+fn fits_in_u64(mut monkeys: Vec<Monkey>, rounds: u32) -> bool {
+    for _ in 0..rounds {
+        for i in 0..monkeys.len() {
+            for item in std::mem::take(&mut monkeys[i].items) {
+                let value = match monkeys[i].operation {
+                    Operation::Add(rhs) => item.value.checked_add(rhs),
+                    Operation::Multiply(rhs) => item.value.checked_mul(rhs),
+                    Operation::Square => item.value.checked_mul(item.value),
+                };
+                let Some(value) = value.map(|v| v / 3) else {
+                    return false;
+                };
+                let next = match monkeys[i].test.test(value) {
+                    true => monkeys[i].next.if_true,
+                    false => monkeys[i].next.if_false,
+                };
+                monkeys[next].items.push(Item::new(value));
+            }
+        }
+    }
+    true
+}
+
+fn parse_monkeys(input: &str) -> Result<Vec<Monkey>, ParseError> {
+    let monkeys = input
+        .split("\n\n")
+        .map(|s| s.parse().map_err(|e: ParseError| e.within(input, s)))
+        .collect::<Result<Vec<Monkey>, _>>()?;
+    if let Some(line) = input.lines().find(|line| {
+        line.trim_start().starts_with("If ")
+            && line
+                .rsplit(' ')
+                .next()
+                .and_then(|n| n.parse::<usize>().ok())
+                .is_some_and(|n| n >= monkeys.len())
+    }) {
+        return Err(ParseError::new(DAY, input, line, "no such monkey"));
+    }
+    Ok(monkeys)
 }
 
 fn assign_lcd(monkeys: &mut [Monkey]) {
@@ -58,7 +155,8 @@ fn get_monkey_business_level(monkeys: &mut [Monkey], rounds: u32) -> u64 {
     times.next().unwrap() * times.next().unwrap()
 }
 
-struct Monkey {
+#[derive(Clone)]
+pub struct Monkey {
     items: Vec<Item>,
     operation: Operation,
     test: MonkeyTest,
@@ -96,13 +194,15 @@ impl Monkey {
     }
 }
 
+#[derive(Clone)]
 struct NextMonkey {
     if_true: usize,
     if_false: usize,
 }
 
 #[allow(unused)]
-struct Item {
+#[derive(Clone)]
+pub struct Item {
     value: u64,
     history: Vec<usize>,
     id: u64,
@@ -127,7 +227,8 @@ impl Item {
     }
 }
 
-enum Operation {
+#[derive(Clone)]
+pub(crate) enum Operation {
     Add(u64),
     Multiply(u64),
     Square,
@@ -143,6 +244,7 @@ impl Operation {
     }
 }
 
+#[derive(Clone)]
 struct MonkeyTest {
     divisible_by: u64,
 }
@@ -154,82 +256,77 @@ impl MonkeyTest {
 }
 
 impl FromStr for Operation {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s
-            .split('=')
-            .skip(1)
-            .next()
-            .unwrap()
-            .trim()
-            .split_whitespace();
-        assert_eq!("old", parts.next().unwrap());
-        let operation = parts.next().unwrap();
-        let rhs = parts.next().unwrap();
-        if rhs == "old" {
-            assert_eq!("*", operation);
-            Ok(Self::Square)
-        } else {
-            let number: u64 = rhs.parse().unwrap();
-            Ok(match operation {
-                "+" => Self::Add(number),
-                "*" => Self::Multiply(number),
-                other => panic!("invalid operation '{}'", other),
-            })
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        Ok(match parts[..] {
+            ["new", "=", "old", "*", "old"] => Self::Square,
+            ["new", "=", "old", "+", rhs] => Self::Add(parse_number(DAY, s, rhs)?),
+            ["new", "=", "old", "*", rhs] => Self::Multiply(parse_number(DAY, s, rhs)?),
+            _ => return Err(ParseError::new(DAY, s, s.trim(), "invalid operation")),
+        })
+    }
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operation::Add(rhs) => write!(f, "new = old + {}", rhs),
+            Operation::Multiply(rhs) => write!(f, "new = old * {}", rhs),
+            Operation::Square => write!(f, "new = old * old"),
         }
     }
 }
 
+/// Everything but the `Monkey N:` header, which [`FromStr`] skips too.
+impl Display for Monkey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let items: Vec<String> = self.items.iter().map(|i| i.value.to_string()).collect();
+        writeln!(f, "  Starting items: {}", items.join(", "))?;
+        writeln!(f, "  Operation: {}", self.operation)?;
+        writeln!(f, "  Test: divisible by {}", self.test.divisible_by)?;
+        writeln!(f, "    If true: throw to monkey {}", self.next.if_true)?;
+        write!(f, "    If false: throw to monkey {}", self.next.if_false)
+    }
+}
+
 impl FromStr for Monkey {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.lines();
-        lines.next();
-        let items: Vec<_> = lines
-            .next()
-            .unwrap()
-            .split(':')
-            .skip(1)
-            .next()
-            .unwrap()
+        let mut lines = s.lines().skip(1);
+        let mut field = |label: &str| {
+            let line = lines.next().unwrap_or_default();
+            line.trim_start()
+                .strip_prefix(label)
+                .ok_or_else(|| ParseError::new(DAY, s, line, format!("expected '{}'", label)))
+        };
+        let number = |text: &str| parse_number(DAY, s, text.trim());
+
+        let items = field("Starting items:")?
             .split(',')
-            .map(|n| Item::new(n.trim().parse().unwrap()))
-            .collect();
-        let operation: Operation = lines
-            .next()
-            .unwrap()
-            .split(':')
-            .skip(1)
-            .next()
-            .unwrap()
+            .map(|n| Ok(Item::new(number(n)?)))
+            .collect::<Result<_, ParseError>>()?;
+        let operation = field("Operation:")?;
+        let operation = operation
             .parse()
-            .unwrap();
-        let test = lines
-            .next()
-            .unwrap()
-            .split_whitespace()
-            .last()
-            .unwrap()
-            .parse()
-            .unwrap();
-        let if_true = lines
-            .next()
-            .unwrap()
-            .split_whitespace()
-            .last()
-            .unwrap()
-            .parse()
-            .unwrap();
-        let if_false = lines
-            .next()
-            .unwrap()
-            .split_whitespace()
-            .last()
-            .unwrap()
-            .parse()
-            .unwrap();
+            .map_err(|e: ParseError| e.within(s, operation))?;
+        let test = field("Test: divisible by")?;
+        let test = match number(test)? {
+            0 => {
+                return Err(ParseError::new(
+                    DAY,
+                    s,
+                    test.trim(),
+                    "cannot divide by zero",
+                ))
+            }
+            n => n,
+        };
+        let monkey = |text: &str| parse_number(DAY, s, text.trim());
+        let if_true = monkey(field("If true: throw to monkey")?)?;
+        let if_false = monkey(field("If false: throw to monkey")?)?;
         Ok(Self {
             items,
             operation,
@@ -244,6 +341,8 @@ impl FromStr for Monkey {
 
 #[cfg(test)]
 mod tests {
+    use rand::SeedableRng;
+
     use super::*;
 
     #[test]
@@ -275,16 +374,10 @@ Monkey 3:
   Test: divisible by 17
     If true: throw to monkey 0
     If false: throw to monkey 1";
-        let mut monkeys: Vec<Monkey> = input
-            .split("\n\n")
-            .map(|line| line.parse().unwrap())
-            .collect();
+        let mut monkeys = parse_monkeys(input).unwrap();
         assert_eq!(10_605, get_monkey_business_level(&mut monkeys, 20));
 
-        let mut monkeys: Vec<Monkey> = input
-            .split("\n\n")
-            .map(|line| line.parse().unwrap())
-            .collect();
+        let mut monkeys = parse_monkeys(input).unwrap();
         monkeys
             .iter_mut()
             .for_each(|m| m.reduce_worry_level = false);
@@ -293,5 +386,29 @@ Monkey 3:
             2_713_310_158,
             get_monkey_business_level(&mut monkeys, 10_000)
         );
+
+        let error = parse_monkeys(&input.replace("old + 6", "old - 6"))
+            .err()
+            .unwrap();
+        assert_eq!(
+            (10, 14, "new = old - 6"),
+            (error.line, error.column, error.text.as_str())
+        );
+        let error = parse_monkeys(&input.replace("monkey 3", "monkey 4"))
+            .err()
+            .unwrap();
+        assert_eq!((6, "no such monkey"), (error.line, error.message.as_str()));
+
+        for seed in 0..20 {
+            let input = MonkeyInTheMiddle::generate(&mut StdRng::seed_from_u64(seed), 9);
+            let printed = parse_monkeys(&input)
+                .unwrap()
+                .iter()
+                .enumerate()
+                .map(|(i, monkey)| format!("Monkey {}:\n{}", i, monkey))
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            assert_eq!(input, printed);
+        }
     }
 }
@@ -1,128 +1,176 @@
-use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
-use std::hash::Hash;
 use std::str::FromStr;
 
-use derivative::Derivative;
 use log::debug;
-use utils::a_star::{a_star_search, Node, Options, CurrentNodeDetails, Successor};
+use rand::rngs::StdRng;
+use rand::Rng;
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input12.txt").unwrap();
-    let hill: Hill = input.parse().unwrap();
-    println!(
-        "shortest path: {}",
-        find_shortest_path(&hill).expect("no solution found")
-    );
-    println!(
-        "shortest global path: {}",
-        find_shortest_global_path_reverse(&hill)
-    );
+use crate::grid::{Coord, Grid2D};
+use crate::parse_error::ParseError;
+use crate::search::{Graph, Search, SearchError, SearchResult};
+use crate::solution::{Answer, Solution};
+use crate::visualize::{Frame, Visualize};
+
+const DAY: u8 = 12;
+
+pub struct HillClimbingAlgorithm;
+
+impl Solution for HillClimbingAlgorithm {
+    type Input = Hill;
+    type Answer1 = i32;
+    type Answer2 = i32;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(input.parse::<Hill>()?)
+    }
+    fn part1(hill: &Self::Input) -> i32 {
+        find_shortest_path(hill).expect("no solution found")
+    }
+    fn part2(hill: &Self::Input) -> i32 {
+        find_shortest_global_path_reverse(hill)
+    }
+    /// Walks the shortest path found by the given part one step at a time.
+    fn visualize(hill: &Self::Input, part: u8) -> Option<Box<dyn Visualize + '_>> {
+        let path = shortest_path(hill, part == 2).ok()?.path;
+        Some(Box::new((1..=path.len()).map(move |steps| {
+            Frame::new(
+                format!("step {} of {}", steps - 1, path.len() - 1),
+                HillState {
+                    hill,
+                    trail: &path[..steps],
+                },
+            )
+        })))
+    }
+    /// A hill at least `size` squares wide, with a winding path from the start on the left to
+    /// the end on the right that climbs evenly from `a` to `z`, so that both parts have a way up.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        let (width, height) = (size.max(26), (size / 2).max(5));
+        let mut path = vec![];
+        let mut y = rng.gen_range(0..height);
+        for x in 0..width {
+            let target = rng.gen_range(0..height);
+            path.push((x, y));
+            while y != target {
+                y = if target > y { y + 1 } else { y - 1 };
+                path.push((x, y));
+            }
+        }
+        let mut rows: Vec<Vec<char>> = (0..height)
+            .map(|_| (0..width).map(|_| rng.gen_range('a'..='z')).collect())
+            .collect();
+        for (i, &(x, y)) in path.iter().enumerate() {
+            rows[y][x] = (b'a' + (i * 25 / (path.len() - 1)) as u8) as char;
+        }
+        let (start, end) = (path[0], path[path.len() - 1]);
+        rows[start.1][start.0] = 'S';
+        rows[end.1][end.0] = 'E';
+        rows.into_iter()
+            .map(String::from_iter)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
-#[allow(unused)]
-fn find_shortest_global_path(hill: Hill) -> i32 {
+fn find_shortest_global_path(hill: Hill) -> Result<i32, SearchError> {
     let lowest_height = Square(0);
     let lowest_squares: Vec<_> = hill
         .map
         .iter()
         .filter(|(_, s)| **s == lowest_height)
-        .map(|(c, _)| c.clone())
+        .map(|(c, _)| c)
         .collect();
-    lowest_squares
-        .into_iter()
-        .filter_map(|start| {
-            let mut hill = hill.clone();
-            hill.start = start;
-            find_shortest_path(&hill)
-        })
-        .min()
-        .unwrap()
+    Search::new()
+        .multi_source_bfs(
+            &mut Climb {
+                hill: &hill,
+                reverse: false,
+            },
+            lowest_squares,
+            |current| *current == hill.end,
+        )
+        .map(|result| result.cost as i32)
 }
 
-fn find_shortest_global_path_reverse(hill: &Hill) -> i32 {
-    let get_successors = |current: &State| -> Vec<Successor<State, i32>> {
-        let result: Vec<Coord> = current
-            .position
-            .get_neighbors(hill.max_x, hill.max_y)
+/// The fewest steps from any lowest square, searching forwards from all of them at once and
+/// backwards from the end, for differential testing.
+pub(crate) fn global_path_both_ways(input: &str) -> anyhow::Result<(Answer, Answer)> {
+    let hill: Hill = input.parse()?;
+    let answer = |steps: Result<i32, SearchError>| steps.map_or(Answer::None, Answer::from);
+    let backwards = answer(shortest_path(&hill, true).map(|result| result.cost as i32));
+    Ok((answer(find_shortest_global_path(hill)), backwards))
+}
+
+/// Steps between neighbouring squares; uphill at most one higher, or when `reverse`d (walking
+/// back from the end), downhill at most one lower.
+struct Climb<'a> {
+    hill: &'a Hill,
+    reverse: bool,
+}
+
+impl Graph for Climb<'_> {
+    type Node = Coord;
+
+    fn successors(&mut self, current: &Coord) -> Vec<(Coord, u64)> {
+        let hill = self.hill;
+        let result: Vec<Coord> = hill
+            .map
+            .neighbors4(*current)
             .filter(|c| {
-                *c != current.last_position
-                    && hill.get_height_difference(&current.position, c) >= -1
+                let difference = hill.get_height_difference(current, c);
+                if self.reverse {
+                    difference >= -1
+                } else {
+                    difference <= 1
+                }
             })
             .collect();
         debug!(
             "\n{}\ncurrent: {:?}, next: {:?}",
             HillState {
                 hill,
-                current: &current.position,
+                trail: std::slice::from_ref(current)
             },
-            current.position,
+            current,
             result
         );
-        result
-            .into_iter()
-            .map(|c| Successor::new(State::next(c, current.position.clone()), 1))
-            .collect()
-    };
-    let distance_function = |details: CurrentNodeDetails<State, i32>| -> i32 {
-        hill.map.get(&details.current_node.position).unwrap().0 as i32
-    };
-    let map = hill.map.clone();
-    a_star_search(
-        State::new(hill.end.clone()),
-        get_successors,
-        distance_function,
-        move |current: &State| -> bool { map.get(&current.position).unwrap().0 == 0 },
-        Some(
-            &Options::default()
-                .with_no_logs(),
-        )
-    )
-    .map(|result| result.shortest_path_cost)
-    .unwrap()
+        result.into_iter().map(|c| (c, 1)).collect()
+    }
+    fn heuristic(&mut self, current: &Coord) -> u64 {
+        if self.reverse {
+            self.hill.map[*current].0 as u64
+        } else {
+            current.manhattan_distance(&self.hill.end)
+        }
+    }
+}
+
+/// From the start to the end, or when `reverse`d from the end to the nearest lowest square.
+fn shortest_path(hill: &Hill, reverse: bool) -> Result<SearchResult<Coord>, SearchError> {
+    let mut climb = Climb { hill, reverse };
+    if reverse {
+        Search::new().a_star(&mut climb, hill.end, |current| hill.map[*current].0 == 0)
+    } else {
+        Search::new().a_star(&mut climb, hill.start, |current| *current == hill.end)
+    }
+}
+
+fn find_shortest_global_path_reverse(hill: &Hill) -> i32 {
+    shortest_path(hill, true)
+        .map(|result| result.cost as i32)
+        .unwrap()
 }
 
 fn find_shortest_path(hill: &Hill) -> Option<i32> {
-    let get_successors = |current: &State| -> Vec<Successor<State, i32>> {
-        let result: Vec<Coord> = current
-            .position
-            .get_neighbors(hill.max_x, hill.max_y)
-            .filter(|c| {
-                *c != current.last_position && hill.get_height_difference(&current.position, c) <= 1
-            })
-            .collect();
-        debug!(
-            "\n{}\ncurrent: {:?}, next: {:?}",
-            HillState {
-                hill,
-                current: &current.position,
-            },
-            current.position,
-            result
-        );
-        result
-            .into_iter()
-            .map(|c| Successor::new(State::next(c, current.position.clone()), 1))
-            .collect()
-    };
-    let end = State::new(hill.end.clone());
-    let distance_function = |details: CurrentNodeDetails<State, i32>| -> i32 {
-        details.current_node.position.manhattan_distance(&hill.end) as i32
-    };
-    a_star_search(
-        State::new(hill.start.clone()),
-        get_successors,
-        distance_function,
-        |current| current == &end,
-        Some(&Options::default().with_no_logs()),
-    )
-    .map(|result| result.shortest_path_cost)
-    .ok()
+    shortest_path(hill, false)
+        .map(|result| result.cost as i32)
+        .ok()
 }
 
 struct HillState<'a> {
     hill: &'a Hill,
-    current: &'a Coord,
+    /// The squares walked so far, ending with the current one.
+    trail: &'a [Coord],
 }
 
 impl<'a> Display for HillState<'a> {
@@ -130,111 +178,33 @@ impl<'a> Display for HillState<'a> {
         write!(
             f,
             "{}",
-            (0..=self.hill.max_y)
-                .map(|y| (0..=self.hill.max_x)
-                    .map(|x| {
-                        let c = Coord::new(x, y);
-                        if *self.current == c {
-                            '#'
-                        } else if c == self.hill.end {
-                            'E'
-                        } else {
-                            char::from(*self.hill.map.get(&c).unwrap())
-                        }
-                    })
-                    .collect::<String>())
-                .collect::<Vec<String>>()
-                .join("\n")
+            self.hill.map.render(|c, &square| {
+                if self.trail.contains(&c) {
+                    '#'
+                } else if c == self.hill.end {
+                    'E'
+                } else {
+                    char::from(square)
+                }
+            })
         )
     }
 }
 
 #[derive(Debug, Clone)]
-struct Hill {
-    map: HashMap<Coord, Square>,
-    max_x: i64,
-    max_y: i64,
+pub struct Hill {
+    map: Grid2D<Square>,
     start: Coord,
     end: Coord,
 }
 
 impl Hill {
     pub fn get_height_difference(&self, from: &Coord, to: &Coord) -> i32 {
-        let h_from = self.map.get(from).unwrap().0 as i32;
-        let h_to = if let Some(h) = self.map.get(to) {
-            h.0 as i32
-        } else {
-            panic!("out of bounds - tried to access {:?}", to)
-        };
-        h_to - h_from
+        self.map[*to].0 as i32 - self.map[*from].0 as i32
     }
 }
 
-#[derive(Derivative)]
-#[derivative(Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-struct State {
-    position: Coord,
-    #[derivative(PartialEq = "ignore")]
-    #[derivative(PartialOrd = "ignore")]
-    #[derivative(Hash = "ignore")]
-    last_position: Coord,
-}
-
-impl State {
-    pub fn new(position: Coord) -> Self {
-        Self {
-            last_position: position.clone(),
-            position,
-        }
-    }
-    pub fn next(position: Coord, last_position: Coord) -> Self {
-        Self {
-            position,
-            last_position,
-        }
-    }
-}
-
-impl Node for State {}
-
-#[derive(Ord, PartialOrd, Eq, PartialEq, Hash, Clone)]
-struct Coord {
-    x: i64,
-    y: i64,
-}
-
-impl Debug for Coord {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "({},{})", self.x, self.y)
-    }
-}
-
-impl Coord {
-    pub fn new(x: i64, y: i64) -> Self {
-        Self { x, y }
-    }
-    pub fn get_neighbors(&self, max_x: i64, max_y: i64) -> impl Iterator<Item = Self> + '_ {
-        let mut neighbors = Vec::with_capacity(3);
-        if self.y > 0 {
-            neighbors.push(Coord::new(self.x, self.y - 1));
-        }
-        if self.y < max_y {
-            neighbors.push(Coord::new(self.x, self.y + 1));
-        }
-        if self.x > 0 {
-            neighbors.push(Coord::new(self.x - 1, self.y));
-        }
-        if self.x < max_x {
-            neighbors.push(Coord::new(self.x + 1, self.y));
-        }
-        neighbors.into_iter()
-    }
-    pub fn manhattan_distance(&self, other: &Self) -> u64 {
-        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
-    }
-}
-
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, Default, PartialEq)]
 struct Square(u8);
 
 impl Debug for Square {
@@ -256,35 +226,32 @@ impl From<Square> for char {
 }
 
 impl FromStr for Hill {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut map: HashMap<Coord, Square> = HashMap::new();
         let mut start = None;
         let mut end = None;
-        for (coord, c) in s.lines().enumerate().flat_map(|(y, line)| {
-            line.chars().enumerate().map(move |(x, c)| {
-                let coord = Coord::new(x as i64, y as i64);
-                (coord, c)
-            })
-        }) {
-            let height = if c == 'S' {
-                start = Some(coord.clone());
-                'a'
-            } else if c == 'E' {
-                end = Some(coord.clone());
-                'z'
-            } else {
-                c
+        let map = Grid2D::parse(DAY, s, "invalid height", |coord, c| {
+            let height = match c {
+                'S' => {
+                    start = Some(coord);
+                    'a'
+                }
+                'E' => {
+                    end = Some(coord);
+                    'z'
+                }
+                'a'..='z' => c,
+                _ => return None,
             };
-            map.insert(coord, Square::from(height));
-        }
+            Some(Square::from(height))
+        })?;
+        let missing =
+            |what: &str| ParseError::new(DAY, s, &s[s.len()..], format!("missing {}", what));
         Ok(Self {
-            max_x: map.keys().map(|k| k.x).max().unwrap(),
-            max_y: map.keys().map(|k| k.y).max().unwrap(),
             map,
-            start: start.unwrap(),
-            end: end.unwrap(),
+            start: start.ok_or_else(|| missing("start 'S'"))?,
+            end: end.ok_or_else(|| missing("end 'E'"))?,
         })
     }
 }
@@ -303,5 +270,16 @@ abdefghi";
         let hill: Hill = input.parse().unwrap();
         assert_eq!(31, find_shortest_path(&hill).unwrap());
         assert_eq!(29, find_shortest_global_path_reverse(&hill));
+        assert_eq!(29, find_shortest_global_path(hill.clone()).unwrap());
+
+        let mut frames = HillClimbingAlgorithm::visualize(&hill, 1).unwrap();
+        let first = frames.next_frame().unwrap();
+        assert_eq!("step 0 of 31", first.caption);
+        assert!(first.picture.starts_with("#abqponm\nabcryxxl\naccszExk"));
+
+        let error = input.replace('E', "z").parse::<Hill>().err().unwrap();
+        assert_eq!("missing end 'E'", error.message);
+        let error = input.replace('y', "Y").parse::<Hill>().err().unwrap();
+        assert_eq!((2, 5, "Y"), (error.line, error.column, error.text.as_str()));
     }
 }
@@ -3,24 +3,48 @@ use std::fmt::{Debug, Display, Formatter};
 use std::str::FromStr;
 
 use log::{debug, info};
+use rand::rngs::StdRng;
+use rand::Rng;
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input13.txt").unwrap();
-    let pairs = parse(&input);
-    info!("which pairs go first:\n{}", PairVisualiser(&pairs));
-    println!(
-        "sum of pair indices in order: {}",
-        sum_pairs_indices_in_order(&pairs)
-    );
-    println!(
-        "decoder key: {}",
+use crate::parse_error::{parse_number, ParseError};
+use crate::repl::{self, Explore};
+use crate::solution::Solution;
+
+const DAY: u8 = 13;
+
+pub struct DistressSignal;
+
+impl Solution for DistressSignal {
+    type Input = Vec<(Packet, Packet)>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        let pairs = parse(input)?;
+        info!("which pairs go first:\n{}", PairVisualiser(&pairs));
+        Ok(pairs)
+    }
+    fn part1(pairs: &Self::Input) -> usize {
+        sum_pairs_indices_in_order(pairs)
+    }
+    fn part2(pairs: &Self::Input) -> usize {
         generate_decoder_key(
             &pairs
-                .into_iter()
-                .flat_map(|p| vec![p.0, p.1])
-                .collect::<Vec<_>>()
+                .iter()
+                .flat_map(|p| vec![p.0.clone(), p.1.clone()])
+                .collect::<Vec<_>>(),
         )
-    );
+    }
+    fn explore(pairs: &Self::Input) -> Option<Box<dyn Explore + '_>> {
+        Some(Box::new(PairsExplorer(pairs)))
+    }
+    /// `size` pairs of packets.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        (0..size)
+            .map(|_| format!("{}\n{}", Packet::random(rng, 4), Packet::random(rng, 4)))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
 }
 
 fn generate_decoder_key(packets: &[Packet]) -> usize {
@@ -57,6 +81,37 @@ fn sum_pairs_indices_in_order(pairs: &[(Packet, Packet)]) -> usize {
         .sum()
 }
 
+struct PairsExplorer<'a>(&'a [(Packet, Packet)]);
+
+impl Explore for PairsExplorer<'_> {
+    fn commands(&self) -> &'static [(&'static str, &'static str)] {
+        &[(
+            "compare <pair>",
+            "show which packet of a pair, counted from 1, goes first",
+        )]
+    }
+    fn run(&mut self, command: &str, args: &[&str]) -> Option<anyhow::Result<String>> {
+        match command {
+            "compare" => Some(repl::argument(args, 0, "pair").and_then(|index: usize| {
+                let Some(pair) = index.checked_sub(1).and_then(|i| self.0.get(i..=i)) else {
+                    anyhow::bail!("there are {} pairs", self.0.len());
+                };
+                let order = match pair[0].0 <= pair[0].1 {
+                    true => "in the right order",
+                    false => "not in the right order",
+                };
+                Ok(format!(
+                    "{}\npair {} is {}",
+                    PairVisualiser(pair),
+                    index,
+                    order
+                ))
+            })),
+            _ => None,
+        }
+    }
+}
+
 struct PairVisualiser<'a>(&'a [(Packet, Packet)]);
 
 impl<'a> Display for PairVisualiser<'a> {
@@ -82,29 +137,43 @@ impl<'a> Display for PairVisualiser<'a> {
     }
 }
 
-fn parse(s: &str) -> Vec<(Packet, Packet)> {
+fn parse(s: &str) -> Result<Vec<(Packet, Packet)>, ParseError> {
     s.split("\n\n")
-        .map(|pairs| {
-            let mut p = pairs.lines();
-            (
-                p.next().unwrap().parse().unwrap(),
-                p.next().unwrap().parse().unwrap(),
-            )
+        .map(|pair| {
+            let mut lines = pair.lines();
+            let mut packet = || {
+                let line = lines
+                    .next()
+                    .ok_or_else(|| ParseError::new(DAY, s, pair, "expected a pair of packets"))?;
+                line.parse().map_err(|e: ParseError| e.within(s, line))
+            };
+            Ok((packet()?, packet()?))
         })
         .collect()
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct Packet(Vec<Element>);
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet(Vec<Element>);
 
 impl Packet {
     pub fn new(elements: Vec<Element>) -> Self {
         Self(elements)
     }
+    /// Up to five elements, with lists nested at most `depth` levels deeper.
+    pub fn random(rng: &mut StdRng, depth: u32) -> Self {
+        Self(
+            (0..rng.gen_range(0..=5))
+                .map(|_| match depth > 0 && rng.gen_bool(0.3) {
+                    true => Element::Packet(Self::random(rng, depth - 1)),
+                    false => Element::Number(rng.gen_range(0..=10)),
+                })
+                .collect(),
+        )
+    }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-enum Element {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Element {
     Packet(Packet),
     Number(u64),
 }
@@ -195,61 +264,69 @@ impl Display for Element {
 }
 
 impl FromStr for Packet {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        assert_eq!(s.bytes().next().unwrap(), '[' as u8, "missing [");
-        assert_eq!(s.bytes().last().unwrap(), ']' as u8, "missing ]");
-        let input = &s[1..s.len() - 1];
+        let input = s
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .ok_or_else(|| ParseError::new(DAY, s, s, "expected a list in brackets"))?;
 
-        if input.len() == 0 {
+        if input.is_empty() {
             return Ok(Self(vec![]));
         }
 
-        let mut commas = vec![0];
-        let mut pos = 0;
+        let mut elements = vec![];
+        let mut start = 0;
         let mut open_brackets = 0;
-        let mut last = 0;
-        for c in input.chars() {
-            last = pos;
+        for (pos, c) in input.char_indices() {
             match c {
-                ',' => {
-                    if open_brackets == 0 {
-                        commas.push(pos);
-                    }
+                ',' if open_brackets == 0 => {
+                    elements.push(&input[start..pos]);
+                    start = pos + c.len_utf8();
                 }
                 '[' => {
                     open_brackets += 1;
                 }
+                ']' if open_brackets == 0 => {
+                    return Err(ParseError::new(DAY, s, &input[pos..=pos], "unbalanced ']'"));
+                }
                 ']' => {
                     open_brackets -= 1;
                 }
                 _ => {}
             }
-            pos += c.len_utf8();
         }
-        commas.push(last + ','.len_utf8());
+        if open_brackets != 0 {
+            return Err(ParseError::new(DAY, s, s, "unbalanced '['"));
+        }
+        elements.push(&input[start..]);
 
         Ok(Self(
-            commas
-                .windows(2)
-                .map(|range| input[range[0]..range[1]].trim_matches(','))
+            elements
+                .into_iter()
                 .map(|element| {
-                    if element.chars().next().unwrap() == '[' {
+                    if element.starts_with('[') {
                         debug!("parsing list: {}", element);
-                        Element::Packet(element.parse().unwrap())
+                        Ok(Element::Packet(
+                            element
+                                .parse()
+                                .map_err(|e: ParseError| e.within(s, element))?,
+                        ))
                     } else {
                         debug!("parsing number: {}", element);
-                        Element::Number(element.parse().unwrap())
+                        Ok(Element::Number(parse_number(DAY, s, element)?))
                     }
                 })
-                .collect(),
+                .collect::<Result<_, _>>()?,
         ))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use rand::SeedableRng;
+
     use super::*;
 
     #[test]
@@ -278,7 +355,7 @@ mod tests {
 [1,[2,[3,[4,[5,6,7]]]],8,9]
 [1,[2,[3,[4,[5,6,0]]]],8,9]";
 
-        let pairs = parse(input);
+        let pairs = parse(input).unwrap();
         assert_eq!(
             input,
             pairs
@@ -288,6 +365,12 @@ mod tests {
                 .join("\n\n")
         );
         assert_eq!(13, sum_pairs_indices_in_order(&pairs));
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..200 {
+            let packet = Packet::random(&mut rng, 4);
+            assert_eq!(packet, packet.to_string().parse().unwrap());
+        }
         assert_eq!(
             140,
             generate_decoder_key(
@@ -297,5 +380,10 @@ mod tests {
                     .collect::<Vec<_>>()
             )
         );
+
+        let error = parse(&input.replace("[[1],4]", "[[1],[x]]")).unwrap_err();
+        assert_eq!((5, 7, "x"), (error.line, error.column, error.text.as_str()));
+        let error = parse(&input.replace("[[8,7,6]]", "[[8,7,6]")).unwrap_err();
+        assert_eq!((8, "unbalanced '['"), (error.line, error.message.as_str()));
     }
 }
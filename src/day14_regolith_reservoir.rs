@@ -1,96 +1,175 @@
-use std::collections::HashSet;
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
-use std::time::Duration;
-
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input14.txt").unwrap();
-    println!(
-        "{} units of sand come to rest.",
-        simulate_falling_sand(&input, false, false)
-    );
-    println!(
-        "with floor: {} units of sand come to rest.",
-        simulate_falling_sand(&input, false, true)
-    );
-}
 
-fn simulate_falling_sand(input: &str, draw: bool, has_floor: bool) -> usize {
-    let rock_path: Vec<Path> = input.lines().map(|l| l.parse().unwrap()).collect();
-    let mut scan = Scan2D::new(Coord::new(500, 0), &rock_path, has_floor);
-    if draw {
-        let mut canvas = utils::canvas::Canvas::new()
-            .unwrap()
-            .with_delay(Duration::from_millis(200));
-        while scan.add_sand_grain() {
-            canvas.draw(&format!("{}", scan)).unwrap();
-        }
-    } else {
-        while scan.add_sand_grain() {}
+use log::debug;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::grid::{Coord, Grid2D};
+use crate::parse_error::{parse_lines, parse_number, ParseError};
+use crate::solution::Solution;
+use crate::visualize::{Frame, Visualize};
+
+const DAY: u8 = 14;
+
+pub struct RegolithReservoir;
+
+impl Solution for RegolithReservoir {
+    type Input = Vec<Path>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(parse_rock_paths(input)?)
+    }
+    fn part1(rock_path: &Self::Input) -> usize {
+        simulate_falling_sand(rock_path, false)
+    }
+    fn part2(rock_path: &Self::Input) -> usize {
+        simulate_falling_sand(rock_path, true)
     }
-    println!("{}", scan);
-    scan.settled_sand.len()
+    fn visualize(rock_path: &Self::Input, part: u8) -> Option<Box<dyn Visualize + '_>> {
+        let mut scan = Scan2D::new(Coord::new(500, 0), rock_path, part == 2);
+        let mut done = false;
+        Some(Box::new(std::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            done = !scan.add_sand_grain();
+            Some(Frame::new(
+                format!("{} units of sand at rest", scan.settled_sand),
+                &scan,
+            ))
+        })))
+    }
+    /// `size` paths of rock below and around the source of the sand.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                let mut point = (rng.gen_range(470..=530), rng.gen_range(2..=40));
+                let mut points = vec![point];
+                for _ in 0..rng.gen_range(1..=4) {
+                    let step = *[-1, 1].choose(rng).unwrap() * rng.gen_range(1..=8);
+                    // starting sideways, which never clamps, so that there are two points
+                    match points.len() % 2 == 1 {
+                        true => point.0 += step,
+                        false => point.1 = (point.1 + step).max(2),
+                    }
+                    if point != *points.last().unwrap() {
+                        points.push(point);
+                    }
+                }
+                points
+                    .iter()
+                    .map(|(x, y)| format!("{},{}", x, y))
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn parse_rock_paths(input: &str) -> Result<Vec<Path>, ParseError> {
+    parse_lines(input)
+}
+
+fn simulate_falling_sand(rock_path: &[Path], has_floor: bool) -> usize {
+    let mut scan = Scan2D::new(Coord::new(500, 0), rock_path, has_floor);
+    while scan.add_sand_grain() {}
+    debug!("\n{}", scan);
+    scan.settled_sand
+}
+
+#[derive(Copy, Clone, Default, PartialEq)]
+enum Tile {
+    #[default]
+    Air,
+    Rock,
+    Sand,
+    /// The path of the last grain, which fell into the abyss.
+    Falling,
 }
 
 struct Scan2D {
-    rock: HashSet<Coord>,
-    settled_sand: HashSet<Coord>,
+    tiles: Grid2D<Tile>,
+    settled_sand: usize,
     sand_entry: Coord,
-    falling_sand: HashSet<Coord>,
     has_floor: bool,
     drop_off_zone_y: i64,
 }
 
 impl Scan2D {
     pub fn new(sand_entry: Coord, rock_path: &[Path], has_floor: bool) -> Self {
-        let rock: HashSet<Coord> = rock_path
+        let rock: Vec<Coord> = rock_path
             .iter()
             .flat_map(|path| {
                 path.0
                     .windows(2)
-                    .flat_map(|range| range[0].path_to_inclusive(&range[1]))
+                    .flat_map(|range| path_to_inclusive(range[0], range[1]))
             })
             .collect();
         let drop_off_zone_y = rock.iter().map(|r| r.y).max().unwrap() + 2;
+        // sand spreads at most one column per row, so it stays within drop_off_zone_y of the entry
+        let width = rock
+            .iter()
+            .map(|r| r.x)
+            .chain([sand_entry.x + drop_off_zone_y])
+            .max()
+            .unwrap()
+            + 2;
+        let mut tiles = Grid2D::new(width as usize, drop_off_zone_y as usize + 2, Tile::Air);
+        for r in rock {
+            tiles[r] = Tile::Rock;
+        }
         Self {
-            rock,
-            settled_sand: Default::default(),
+            tiles,
+            settled_sand: 0,
             sand_entry,
-            falling_sand: Default::default(),
             has_floor,
             drop_off_zone_y,
         }
     }
     pub fn add_sand_grain(&mut self) -> bool {
-        let mut position = self.sand_entry.clone();
+        let mut position = self.sand_entry;
         let mut path = Vec::new();
         'outer: loop {
             if position.y > self.drop_off_zone_y {
-                self.falling_sand = path.into_iter().collect();
+                for c in path {
+                    self.tiles[c] = Tile::Falling;
+                }
                 return false;
             }
             let mut next = NextPosition::new(&position);
             while let Some(next) = next.next() {
                 if !self.is_blocked(&next) {
-                    path.push(next.clone());
+                    path.push(next);
                     position = next;
                     continue 'outer;
                 }
             }
             break;
         }
-        self.settled_sand.insert(position.clone());
+        self.tiles[position] = Tile::Sand;
+        self.settled_sand += 1;
         position != self.sand_entry
     }
     fn is_blocked(&self, coord: &Coord) -> bool {
-        self.rock.contains(coord)
-            || self.settled_sand.contains(coord)
+        matches!(self.tiles.get(*coord), Some(Tile::Rock | Tile::Sand))
             || (self.has_floor && coord.y >= self.drop_off_zone_y)
     }
     fn range(&self) -> ((i64, i64), (i64, i64)) {
-        let max_y = self.rock.iter().map(|c| c.y).max().unwrap();
-        let max_x = self.rock.iter().map(|c| c.x).max().unwrap();
-        let min_x = self.rock.iter().map(|c| c.x).min().unwrap();
+        // with a floor, the sand spreads beyond the rock
+        let filled: Vec<Coord> = self
+            .tiles
+            .iter()
+            .filter(|&(_, &t)| t == Tile::Rock || t == Tile::Sand)
+            .map(|(c, _)| c)
+            .collect();
+        let max_y = self.drop_off_zone_y - 2;
+        let max_x = filled.iter().map(|c| c.x).max().unwrap();
+        let min_x = filled.iter().map(|c| c.x).min().unwrap();
         ((0, max_y), (min_x, max_x))
     }
 }
@@ -124,17 +203,13 @@ impl Display for Scan2D {
                 "{}",
                 (min_x..=max_x)
                     .map(|x| {
-                        let c = &Coord::new(x, y);
-                        if self.rock.contains(c) {
-                            '#'
-                        } else if self.settled_sand.contains(c) {
-                            'o'
-                        } else if &self.sand_entry == c {
-                            '+'
-                        } else if self.falling_sand.contains(c) {
-                            '~'
-                        } else {
-                            '.'
+                        let c = Coord::new(x, y);
+                        match self.tiles.get(c).copied().unwrap_or_default() {
+                            Tile::Rock => '#',
+                            Tile::Sand => 'o',
+                            _ if self.sand_entry == c => '+',
+                            Tile::Falling => '~',
+                            Tile::Air => '.',
                         }
                     })
                     .collect::<String>()
@@ -144,60 +219,55 @@ impl Display for Scan2D {
     }
 }
 
-struct Path(Vec<Coord>);
+pub struct Path(Vec<Coord>);
 
 impl FromStr for Path {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self(
-            s.split(" -> ")
-                .map(|s| {
-                    let mut parts = s.split(',');
-                    Coord::new(
-                        parts.next().unwrap().parse().unwrap(),
-                        parts.next().unwrap().parse().unwrap(),
-                    )
-                })
-                .collect(),
-        ))
+        let points: Vec<&str> = s.split(" -> ").collect();
+        let coords = points
+            .iter()
+            .map(|point| {
+                let (x, y) = point
+                    .split_once(',')
+                    .ok_or_else(|| ParseError::new(DAY, s, point, "expected 'x,y'"))?;
+                Ok(Coord::new(
+                    parse_number(DAY, s, x)?,
+                    parse_number(DAY, s, y)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, ParseError>>()?;
+        if let Some(i) = coords
+            .windows(2)
+            .position(|w| (w[0].x == w[1].x) == (w[0].y == w[1].y))
+        {
+            return Err(ParseError::new(
+                DAY,
+                s,
+                points[i + 1],
+                "segment is not horizontal or vertical",
+            ));
+        }
+        Ok(Self(coords))
     }
 }
 
-#[derive(Eq, PartialEq, Hash, Clone)]
-struct Coord {
-    x: i64,
-    y: i64,
-}
-
-impl Coord {
-    pub fn new(x: i64, y: i64) -> Self {
-        Self { x, y }
-    }
-    pub fn path_to_inclusive(&self, other: &Self) -> impl Iterator<Item = Self> + '_ {
-        let horizontal = self.y == other.y;
-        assert_eq!(self.x == other.x, !horizontal);
-        let range = if horizontal {
-            if self.x < other.x {
-                (self.x, other.x)
-            } else {
-                (other.x, self.x)
-            }
+fn path_to_inclusive(from: Coord, to: Coord) -> impl Iterator<Item = Coord> {
+    let horizontal = from.y == to.y;
+    assert_eq!(from.x == to.x, !horizontal);
+    let range = if horizontal {
+        (from.x.min(to.x), from.x.max(to.x))
+    } else {
+        (from.y.min(to.y), from.y.max(to.y))
+    };
+    (range.0..=range.1).map(move |i| {
+        if horizontal {
+            Coord::new(i, from.y)
         } else {
-            if self.y < other.y {
-                (self.y, other.y)
-            } else {
-                (other.y, self.y)
-            }
-        };
-        (range.0..=range.1).map(move |i| {
-            if horizontal {
-                Self::new(i, self.y)
-            } else {
-                Self::new(self.x, i)
-            }
-        })
-    }
+            Coord::new(from.x, i)
+        }
+    })
 }
 
 #[cfg(test)]
@@ -208,7 +278,21 @@ mod tests {
     fn test1() {
         let input = "498,4 -> 498,6 -> 496,6
 503,4 -> 502,4 -> 502,9 -> 494,9";
-        assert_eq!(24, simulate_falling_sand(input, true, false));
-        assert_eq!(93, simulate_falling_sand(input, true, true));
+        let rock_path = parse_rock_paths(input).unwrap();
+        assert_eq!(24, simulate_falling_sand(&rock_path, false));
+        assert_eq!(93, simulate_falling_sand(&rock_path, true));
+
+        let mut frames = RegolithReservoir::visualize(&rock_path, 2).unwrap();
+        let last = std::iter::from_fn(|| frames.next_frame()).last().unwrap();
+        assert_eq!("93 units of sand at rest", last.caption);
+        assert!(last.picture.starts_with("..........o..........\n"));
+
+        let error = parse_rock_paths(&input.replace("502,9", "501,9"))
+            .err()
+            .unwrap();
+        assert_eq!(
+            (2, 19, "501,9"),
+            (error.line, error.column, error.text.as_str())
+        );
     }
 }
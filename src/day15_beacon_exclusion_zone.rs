@@ -1,89 +1,150 @@
-use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
 use log::debug;
-
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input15.txt").unwrap();
-    let y = 2_000_000;
-    println!(
-        "positions with no beacons at y= {}: {}",
-        y,
-        count_positions_without_beacon(&input, y)
-    );
-    println!(
-        "frequency of the only empty cell: {}",
-        find_frequency_only_empty_cell(&input, 4_000_000)
-    );
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::interval::{Interval, IntervalSet};
+use crate::params::{Parameter, Params};
+use crate::parse_error::{parse_lines, parse_number, ParseError};
+use crate::progress::Progress;
+use crate::solution::Solution;
+
+const DAY: u8 = 15;
+
+pub struct BeaconExclusionZone;
+
+impl Solution for BeaconExclusionZone {
+    type Input = Grid;
+    type Answer1 = usize;
+    type Answer2 = i64;
+    const PARAMETERS: &'static [Parameter] = &[
+        Parameter::integer("row", 2_000_000, "row part 1 counts positions in"),
+        Parameter::integer(
+            "bound",
+            4_000_000,
+            "largest x and y part 2 searches for the beacon",
+        )
+        .range(0, i64::MAX),
+    ];
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(input.parse::<Grid>()?)
+    }
+    fn part1(grid: &Self::Input) -> usize {
+        Self::part1_with(grid, &Params::defaults(Self::PARAMETERS))
+    }
+    fn part2(grid: &Self::Input) -> i64 {
+        Self::part2_with(grid, &Params::defaults(Self::PARAMETERS))
+    }
+    fn part1_with(grid: &Self::Input, params: &Params) -> usize {
+        count_positions_without_beacon(grid, params.integer("row"))
+    }
+    fn part2_with(grid: &Self::Input, params: &Params) -> i64 {
+        find_frequency_only_empty_cell(grid, params.integer("bound"))
+    }
+    /// Four big sensors that together cover the whole search area but one cell, and `size`
+    /// smaller ones that stay clear of it.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        const BOUND: i64 = 4_000_000;
+        let hidden = Coord::new(rng.gen_range(1..BOUND), rng.gen_range(1..BOUND));
+        // along the diagonals u = x + y and v = x - y the sensors' areas are squares; around
+        // the hidden cell, one on each side leaves exactly that cell uncovered
+        let (u, v) = (hidden.x + hidden.y, hidden.x - hidden.y);
+        let mut sensors = vec![];
+        for (du, dv) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+            let radius = 2 * BOUND + 3 + 2 * rng.gen_range(0..1000);
+            let (su, sv) = (u + du * (radius + 1), v + dv * (radius + 1));
+            let position = Coord::new((su + sv) / 2, (su - sv) / 2);
+            let closest_beacon = Coord::new(position.x + radius, position.y);
+            sensors.push(Sensor {
+                position,
+                closest_beacon,
+            });
+        }
+        while sensors.len() < size + 4 {
+            let position = Coord::new(rng.gen_range(0..=BOUND), rng.gen_range(0..=BOUND));
+            let distance = position.manhattan_distance(&hidden);
+            if distance < 2 {
+                continue;
+            }
+            let radius = rng.gen_range(1..distance);
+            let dx = rng.gen_range(-radius..=radius);
+            let dy = (radius - dx.abs()) * if rng.gen_bool(0.5) { 1 } else { -1 };
+            let closest_beacon = Coord::new(position.x + dx, position.y + dy);
+            sensors.push(Sensor {
+                position,
+                closest_beacon,
+            });
+        }
+        sensors
+            .iter()
+            .map(|s| {
+                format!(
+                    "Sensor at x={}, y={}: closest beacon is at x={}, y={}",
+                    s.position.x, s.position.y, s.closest_beacon.x, s.closest_beacon.y
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
-fn count_positions_without_beacon(input: &str, row: i64) -> usize {
-    let grid: Grid = input.parse().unwrap();
-    let mut ranges: RangesInclusive = grid
+fn count_positions_without_beacon(grid: &Grid, row: i64) -> usize {
+    let mut covered: IntervalSet<i64> = grid
         .sensors
         .iter()
         .filter_map(|sensor| sensor.intersection(row))
-        .collect::<Vec<_>>()
-        .into();
-    for beacon in grid
-        .sensors
-        .iter()
-        .map(|s| s.closest_beacon.clone())
-        .filter(|b| b.y == row)
-        .map(|b| b.x)
-    {
-        ranges.add_exception(beacon);
+        .collect();
+    for beacon in grid.sensors.iter().map(|s| &s.closest_beacon) {
+        if beacon.y == row {
+            covered.remove(Interval::single(beacon.x));
+        }
     }
-    debug!("y={}: {}", row, ranges);
-    debug!("{:?}", ranges);
-    ranges.count_ints()
+    debug!("y={}: {}", row, covered);
+    covered.len() as usize
 }
 
-fn find_frequency_only_empty_cell(input: &str, max: i64) -> i64 {
-    let full_row = RangeInclusive::new(0, max);
-    let grid: Grid = input.parse().unwrap();
+fn find_frequency_only_empty_cell(grid: &Grid, max: i64) -> i64 {
+    let full_row = Interval::new(0, max);
+    let progress = Progress::new("day 15 rows").with_total(max as u64);
     let rows_with_empty_cells = (0..max)
         .map(|y| {
-            let mut ranges: RangesInclusive = grid
+            progress.explored(1);
+            progress.set_done(y as u64 + 1);
+            let covered: IntervalSet<i64> = grid
                 .sensors
                 .iter()
                 .filter_map(|s| s.intersection(y))
-                .collect::<Vec<_>>()
-                .into();
-            ranges.remove_outliers(0, max);
-            (y, ranges)
+                .collect();
+            (y, covered.complement(full_row))
         })
-        .filter(|(_, ranges)| !(ranges.0.len() == 1 && ranges.0[0] == full_row))
+        .filter(|(_, empty)| !empty.is_empty())
         .collect::<Vec<_>>();
     assert_eq!(1, rows_with_empty_cells.len());
-    assert_eq!(2, rows_with_empty_cells[0].1 .0.len());
-    let empty_cell = Coord::new(
-        rows_with_empty_cells[0].1 .0[0].to + 1,
-        rows_with_empty_cells[0].0,
-    );
-    println!("empty cell: {:?}", empty_cell);
+    let (y, empty) = &rows_with_empty_cells[0];
+    assert_eq!(1, empty.len());
+    let empty_cell = Coord::new(empty.intervals()[0].start(), *y);
+    debug!("empty cell: {:?}", empty_cell);
     empty_cell.tuning_frequency()
 }
 
-struct Grid {
+pub struct Grid {
     sensors: Vec<Sensor>,
 }
 
-struct Sensor {
+pub(crate) struct Sensor {
     position: Coord,
     closest_beacon: Coord,
 }
 
 impl Sensor {
-    pub fn intersection(&self, y: i64) -> Option<RangeInclusive> {
+    pub fn intersection(&self, y: i64) -> Option<Interval<i64>> {
         let distance = self.position.manhattan_distance(&self.closest_beacon);
         let dy = (self.position.y - y).abs();
         if dy <= distance {
             let dx = distance - dy;
-            Some(RangeInclusive::new(
-                self.position.x - dx,
-                self.position.x + dx,
-            ))
+            Some(Interval::new(self.position.x - dx, self.position.x + dx))
         } else {
             None
         }
@@ -108,181 +169,44 @@ impl Coord {
     }
 }
 
-#[derive(Ord, PartialOrd, Eq, PartialEq, Debug)]
-struct RangeInclusive {
-    from: i64,
-    to: i64,
-}
+impl FromStr for Sensor {
+    type Err = ParseError;
 
-impl RangeInclusive {
-    pub fn new(from: i64, to: i64) -> Self {
-        Self { from, to }
-    }
-    pub fn includes(&self, value: i64) -> bool {
-        self.from <= value && self.to >= value
-    }
-    pub fn len(&self) -> usize {
-        (self.to - self.from + 1) as usize
-    }
-    pub fn try_split_at(&self, value: i64) -> Option<Vec<Self>> {
-        assert!(self.includes(value));
-        if self.from == self.to {
-            return None;
-        }
-        Some(if self.from == value {
-            vec![Self {
-                from: self.from + 1,
-                to: self.to,
-            }]
-        } else if self.to == value {
-            vec![Self {
-                from: self.from,
-                to: self.to - 1,
-            }]
-        } else {
-            vec![
-                Self {
-                    from: self.from,
-                    to: value - 1,
-                },
-                Self {
-                    from: value + 1,
-                    to: self.to,
-                },
-            ]
-        })
-    }
-    pub fn try_combine(&self, other: &Self) -> Option<Self> {
-        let (left, right) = if self <= other {
-            (self, other)
-        } else {
-            (other, self)
-        };
-        if (left.to + 1) >= right.from {
-            Some(Self {
-                from: left.from,
-                to: left.to.max(right.to),
-            })
-        } else {
-            None
-        }
-    }
-}
-
-#[derive(Debug)]
-struct RangesInclusive(Vec<RangeInclusive>);
-
-impl RangesInclusive {
-    pub fn count_ints(&self) -> usize {
-        self.0.iter().map(|range| range.len()).sum()
-    }
-    pub fn add_exception(&mut self, exception: i64) {
-        if let Some(i) = self.0.iter().position(|r| r.includes(exception)) {
-            if let Some(new_ranges) = self.0[i].try_split_at(exception) {
-                let mut new_ranges = new_ranges.into_iter();
-                self.0[i] = new_ranges.next().unwrap();
-                for (j, range) in new_ranges.enumerate() {
-                    self.0.insert(i + j + 1, range);
-                }
-            } else {
-                self.0.remove(i);
-            }
-        }
-    }
-    pub fn remove_outliers(&mut self, min: i64, max: i64) {
-        let mut result = Vec::new();
-        for range in std::mem::take(&mut self.0)
-            .into_iter()
-            .filter(|range| range.from <= max && range.to >= min)
-        {
-            result.push(RangeInclusive {
-                from: range.from.max(min),
-                to: range.to.min(max),
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_coord = |coord: &str| {
+            let (x, y) = coord
+                .strip_prefix("x=")
+                .and_then(|coord| coord.split_once(", y="))
+                .ok_or_else(|| ParseError::new(DAY, s, coord, "expected 'x=<x>, y=<y>'"))?;
+            Ok::<_, ParseError>(Coord {
+                x: parse_number(DAY, s, x)?,
+                y: parse_number(DAY, s, y)?,
             })
-        }
-        self.0 = result;
-    }
-}
-
-impl Display for RangesInclusive {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let from = self.0[0].from;
-        let to = self.0[self.0.len() - 1].to;
-        writeln!(f, "range {} -> {}:", from, to)?;
-        let mut values = self.0.iter().flat_map(|range| range.from..=range.to);
-        let mut value = values.next();
-        write!(
-            f,
-            "{}",
-            (from..=to)
-                .map(|i| {
-                    if Some(i) == value {
-                        value = values.next();
-                        '#'
-                    } else {
-                        '.'
-                    }
-                })
-                .collect::<String>()
-        )
-    }
-}
-
-impl From<Vec<RangeInclusive>> for RangesInclusive {
-    fn from(mut values: Vec<RangeInclusive>) -> Self {
-        if values.len() < 2 {
-            return Self(values);
-        }
-        values.sort_by(|a, b| b.cmp(a));
-        let mut result = Vec::new();
-        let mut left = values.pop().unwrap();
-        let mut right_opt = values.pop();
-        while let Some(right) = right_opt {
-            if let Some(combined) = left.try_combine(&right) {
-                left = combined;
-            } else {
-                result.push(left);
-                left = right;
-            }
-            right_opt = values.pop();
-        }
-        result.push(left);
-
-        Self(result)
+        };
+        let (position, closest_beacon) = s
+            .strip_prefix("Sensor at ")
+            .and_then(|s| s.split_once(": closest beacon is at "))
+            .ok_or_else(|| {
+                ParseError::new(
+                    DAY,
+                    s,
+                    s,
+                    "expected 'Sensor at <coord>: closest beacon is at <coord>'",
+                )
+            })?;
+        Ok(Self {
+            position: parse_coord(position)?,
+            closest_beacon: parse_coord(closest_beacon)?,
+        })
     }
 }
 
 impl FromStr for Grid {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        fn parse_coord(s: &str) -> Coord {
-            let mut parts = s.split_whitespace().rev();
-            fn parse(s: &str) -> i64 {
-                s.split('=')
-                    .skip(1)
-                    .next()
-                    .unwrap()
-                    .trim_matches(',')
-                    .parse()
-                    .unwrap()
-            }
-            Coord {
-                y: parse(parts.next().unwrap()),
-                x: parse(parts.next().unwrap()),
-            }
-        }
         Ok(Self {
-            sensors: s
-                .lines()
-                .map(|line| {
-                    let mut parts = line.split(':');
-                    Sensor {
-                        position: parse_coord(parts.next().unwrap()),
-                        closest_beacon: parse_coord(parts.next().unwrap()),
-                    }
-                })
-                .collect(),
+            sensors: parse_lines(s)?,
         })
     }
 }
@@ -308,7 +232,20 @@ Sensor at x=16, y=7: closest beacon is at x=15, y=3
 Sensor at x=14, y=3: closest beacon is at x=15, y=3
 Sensor at x=20, y=1: closest beacon is at x=15, y=3";
 
-        assert_eq!(26, count_positions_without_beacon(input, 10));
-        assert_eq!(56000011, find_frequency_only_empty_cell(&input, 20));
+        let grid: Grid = input.parse().unwrap();
+        assert_eq!(26, count_positions_without_beacon(&grid, 10));
+        assert_eq!(56000011, find_frequency_only_empty_cell(&grid, 20));
+
+        let error = input.replace("y=7:", "y=7;").parse::<Grid>().err().unwrap();
+        assert_eq!((7, 1), (error.line, error.column));
+        let error = input
+            .replace("x=25", "x=2.5")
+            .parse::<Grid>()
+            .err()
+            .unwrap();
+        assert_eq!(
+            (10, 46, "2.5"),
+            (error.line, error.column, error.text.as_str())
+        );
     }
 }
@@ -1,25 +1,97 @@
 use std::collections::{HashMap, HashSet};
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use log::debug;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
 use string_interner::{DefaultSymbol, StringInterner, Symbol};
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input16.txt").unwrap();
-    let network = Network::parse_from(&input).unwrap();
-    // let best = network.run::<State>(30);
-    // println!("open history: {}", best.formatted_history());
-    println!(
-        "max pressure released after 30 min: {}",
+use crate::cancel::{Cancellation, Outcome};
+use crate::params::Params;
+use crate::parse_error::{parse_number, ParseError};
+use crate::progress::{Batch, Progress};
+use crate::search::{Graph, Search};
+use crate::solution::{Answer, Solution};
+
+const DAY: u8 = 16;
+
+pub struct ProboscideaVolcanium;
+
+impl Solution for ProboscideaVolcanium {
+    type Input = Network;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        Ok(Network::parse_from(input)?)
+    }
+    fn part1(network: &Self::Input) -> u64 {
+        // let best = network.run::<State>(30);
+        // println!("open history: {}", best.formatted_history());
         network.find_max_pressure_faster(30, 1) // best.total_pressure
-    );
-    // let best = network.run::<CoopState>(26);
-    println!("with coop:");
-    // println!("open history: {}", best.formatted_history());
-    println!(
-        "coop max pressure released after 26 min: {}",
+    }
+    fn part2(network: &Self::Input) -> u64 {
+        // let best = network.run::<CoopState>(26);
+        // println!("open history: {}", best.formatted_history());
         network.find_max_pressure_faster(26, 2) // best.total_pressure
-    );
+    }
+    fn part1_until(network: &Self::Input, _: &Params, cancellation: &Cancellation) -> Outcome<u64> {
+        network.find_max_pressure_until(30, 1, cancellation)
+    }
+    fn part2_until(network: &Self::Input, _: &Params, cancellation: &Cancellation) -> Outcome<u64> {
+        network.find_max_pressure_until(26, 2, cancellation)
+    }
+    /// A connected network of `size` valves joined by long tunnels, a quarter of which, at most
+    /// 15, have a flow rate, like the puzzle's. Closer or more valves make the search explode.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        let count = size.max(2);
+        let mut names = vec!["AA".to_string()];
+        while names.len() < count {
+            let name: String = (0..2).map(|_| rng.gen_range('A'..='Z')).collect();
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        let mut tunnels: Vec<HashSet<usize>> = vec![HashSet::new(); count];
+        let mut connect = |a: usize, b: usize| {
+            tunnels[a].insert(b);
+            tunnels[b].insert(a);
+        };
+        // a tree of winding tunnels keeps every valve reachable, the extra tunnels add loops
+        for valve in 1..count {
+            connect(valve, rng.gen_range(valve.saturating_sub(3)..valve));
+        }
+        for _ in 0..count / 8 {
+            let (a, b) = (rng.gen_range(0..count), rng.gen_range(0..count));
+            if a != b {
+                connect(a, b);
+            }
+        }
+        let mut flowing: Vec<usize> = (1..count).collect();
+        flowing.shuffle(rng);
+        flowing.truncate((count / 4).clamp(1, 15));
+        (0..count)
+            .map(|valve| {
+                let flow_rate = match flowing.contains(&valve) {
+                    true => rng.gen_range(1..=25),
+                    false => 0,
+                };
+                let mut neighbours: Vec<&str> =
+                    tunnels[valve].iter().map(|&t| names[t].as_str()).collect();
+                neighbours.sort();
+                let tunnels = match neighbours[..] {
+                    [single] => format!("tunnel leads to valve {}", single),
+                    _ => format!("tunnels lead to valves {}", neighbours.join(", ")),
+                };
+                format!(
+                    "Valve {} has flow rate={}; {}",
+                    names[valve], flow_rate, tunnels
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
 struct Valve {
@@ -28,138 +100,164 @@ struct Valve {
     tunnels: Vec<DefaultSymbol>,
 }
 
-struct Network {
+struct Tunnels<'a>(&'a HashMap<DefaultSymbol, Valve>);
+
+impl Graph for Tunnels<'_> {
+    type Node = DefaultSymbol;
+
+    fn successors(&mut self, valve: &DefaultSymbol) -> Vec<(DefaultSymbol, u64)> {
+        self.0[valve].tunnels.iter().map(|&t| (t, 1)).collect()
+    }
+}
+
+/// The valves passed through between every two valves.
+type ShortestPaths = HashMap<(DefaultSymbol, DefaultSymbol), Vec<DefaultSymbol>>;
+
+pub struct Network {
     interner: StringInterner,
     valves: HashMap<DefaultSymbol, Valve>,
-    shortest_paths: HashMap<(DefaultSymbol, DefaultSymbol), Vec<DefaultSymbol>>,
+    shortest_paths: ShortestPaths,
     start: DefaultSymbol,
 }
 
-#[derive(Clone)]
-struct Actor {
-    minutes_left: usize,
-    pressure_opened: u64,
-    position: DefaultSymbol,
-}
+/// A search tracks the valves it opened as bits of a `u64`.
+const MAX_FLOWING_VALVES: usize = 64;
 
 impl Network {
-    fn find_max_pressure_step(
+    /// The most pressure a single actor releases in `minutes` for each set of valves it ends up
+    /// opening, as a bitmask over `flowing`; of the orders that open the same set, only the best
+    /// is kept.
+    fn best_per_valve_set(
         &self,
-        actors: Vec<Actor>,
-        closed_valves: HashSet<DefaultSymbol>,
-    ) -> u64 {
-        if closed_valves.is_empty() {
-            return actors.iter().map(|a| a.pressure_opened).sum();
-        }
-        let next: Vec<Vec<(Actor, DefaultSymbol)>> = actors
-            .iter()
-            .map(|actor| {
-                let from = actor.position;
-                closed_valves
-                    .iter()
-                    .copied()
-                    .filter_map(|to| {
-                        let cost = self.shortest_paths.get(&(from, to)).unwrap().len() + 2;
-                        if cost < actor.minutes_left {
-                            let mut actor = actor.clone();
-                            actor.minutes_left -= cost;
-                            actor.pressure_opened += self.valves.get(&to).unwrap().flow_rate
-                                * (actor.minutes_left as u64);
-                            actor.position = to;
-                            Some((actor, to))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect()
-            })
-            .collect();
-        match next.len() {
-            1 => next
-                .into_iter()
-                .next()
-                .unwrap()
-                .into_iter()
-                .map(|(actor, to)| {
-                    let mut closed_valves = closed_valves.clone();
-                    closed_valves.remove(&to);
-                    self.find_max_pressure_step(vec![actor], closed_valves)
-                })
-                .max()
-                .unwrap_or(actors[0].pressure_opened),
-            2 => {
-                let mut results: Vec<_> = {
-                    let next = &next;
-                    let closed_valves = &closed_valves;
-                    (0..next[0].len())
-                        .flat_map(|i| {
-                            (0..next[1].len()).filter(move |&j| j != i).map(move |j| {
-                                let first = &next[0][i];
-                                let second = &next[1][j];
-                                let actors = vec![first.0.clone(), second.0.clone()];
-                                let mut closed_valves = closed_valves.clone();
-                                closed_valves.remove(&first.1);
-                                closed_valves.remove(&second.1);
-                                self.find_max_pressure_step(actors, closed_valves)
-                            })
-                        })
-                        .collect()
-                };
-                if next[0].len() <= 1 {
-                    results.extend(next.into_iter().nth(1).unwrap().into_iter().map(
-                        |(actor, to)| {
-                            let mut closed_valves = closed_valves.clone();
-                            closed_valves.remove(&to);
-                            self.find_max_pressure_step(vec![actor], closed_valves)
-                        },
-                    ));
+        flowing: &[DefaultSymbol],
+        minutes: usize,
+        cancellation: &Cancellation,
+        batch: &Batch,
+    ) -> HashMap<u64, u64> {
+        let mut best = HashMap::new();
+        let mut stack = vec![(self.start, minutes, 0u64, 0u64)];
+        while let Some((position, minutes_left, opened, pressure)) = stack.pop() {
+            let best = best.entry(opened).or_insert(0);
+            *best = pressure.max(*best);
+            // reading the clock for the deadline on every state would slow the search down
+            let cancelled = match batch.explored() {
+                true => cancellation.is_cancelled(),
+                false => cancellation.was_cancelled(),
+            };
+            if cancelled {
+                break;
+            }
+            for (i, &to) in flowing.iter().enumerate() {
+                if opened & (1 << i) != 0 {
+                    continue;
+                }
+                // moving through the valves on the way, then one minute to open it; only a
+                // flowing start is opened where the actor stands
+                let cost = self
+                    .shortest_paths
+                    .get(&(position, to))
+                    .map_or(1, |path| path.len() + 2);
+                if cost < minutes_left {
+                    let minutes_left = minutes_left - cost;
+                    let pressure = pressure + self.valves[&to].flow_rate * minutes_left as u64;
+                    stack.push((to, minutes_left, opened | 1 << i, pressure));
                 }
-                results
-                    .into_iter()
-                    .max()
-                    .unwrap_or(actors[0].pressure_opened)
             }
-            other => panic!("invalid number of actors: {}", other),
         }
+        best
     }
 
     pub fn find_max_pressure_faster(&self, minutes: usize, num_actors: usize) -> u64 {
-        let closed_valves: HashSet<DefaultSymbol> = self
+        self.find_max_pressure_until(minutes, num_actors, &Cancellation::new())
+            .value()
+    }
+    /// The most pressure found before `cancellation` was cancelled, if it was.
+    pub fn find_max_pressure_until(
+        &self,
+        minutes: usize,
+        num_actors: usize,
+        cancellation: &Cancellation,
+    ) -> Outcome<u64> {
+        let mut flowing: Vec<DefaultSymbol> = self
             .valves
-            .iter()
-            .filter(|(_, v)| v.flow_rate > 0)
-            .map(|(i, _)| *i)
+            .values()
+            .filter(|v| v.flow_rate > 0)
+            .map(|v| v.name)
             .collect();
-        let actors = vec![
-            Actor {
-                minutes_left: minutes,
-                pressure_opened: 0,
-                position: self.start,
-            };
-            num_actors
-        ];
-        self.find_max_pressure_step(actors, closed_valves)
+        flowing.sort();
+        let progress = Progress::new(match num_actors {
+            1 => "day 16 search alone",
+            _ => "day 16 search with an elephant",
+        });
+        let batch = Batch::new(&progress);
+        // both actors have the same time, so each opens one of the sets a single actor can
+        let best = self.best_per_valve_set(&flowing, minutes, cancellation, &batch);
+        let pressure = match num_actors {
+            1 => best.values().copied().max().unwrap_or(0),
+            2 => best_disjoint_pair(&best),
+            other => panic!("invalid number of actors: {}", other),
+        };
+        cancellation.outcome(pressure)
     }
-    pub fn parse_from(s: &str) -> Result<Self> {
+    pub fn parse_from(s: &str) -> Result<Self, ParseError> {
         let mut interner = StringInterner::default();
-        let valves: HashMap<DefaultSymbol, Valve> = s
+        let parsed: Vec<(Valve, Vec<&str>)> = s
             .lines()
-            .map(|line| {
-                let valve = Self::parse_valve(line, &mut interner);
-                valve.map(|res| (res.name, res))
-            })
-            .collect::<Result<_>>()?;
+            .map(|line| Self::parse_valve(line, &mut interner).map_err(|e| e.within(s, line)))
+            .collect::<Result<_, _>>()?;
+        let mut flowing = s
+            .lines()
+            .zip(parsed.iter())
+            .filter(|(_, (valve, _))| valve.flow_rate > 0);
+        if let Some((line, _)) = flowing.nth(MAX_FLOWING_VALVES) {
+            return Err(ParseError::new(
+                DAY,
+                s,
+                line,
+                format!("more than {} valves have a flow rate", MAX_FLOWING_VALVES),
+            ));
+        }
+        let mut tunnels = vec![];
+        let mut valves = HashMap::new();
+        for (valve, names) in parsed {
+            tunnels.extend(names);
+            valves.insert(valve.name, valve);
+        }
+        let unknown = tunnels
+            .into_iter()
+            .find(|&name| interner.get(name).is_none_or(|v| !valves.contains_key(&v)));
+        if let Some(tunnel) = unknown {
+            return Err(ParseError::new(DAY, s, tunnel, "no such valve"));
+        }
 
+        let start = interner
+            .get("AA")
+            .filter(|aa| valves.contains_key(aa))
+            .ok_or_else(|| {
+                ParseError::new(DAY, s, &s[s.len()..], "valve AA not found in network")
+            })?;
+        let shortest_paths = Self::create_shortest_paths(&valves).map_err(|(from, to)| {
+            let name = |valve| interner.resolve(valve).unwrap();
+            let prefix = format!("Valve {} has", name(from));
+            let line = s.lines().find(|line| line.starts_with(&prefix)).unwrap();
+            ParseError::new(
+                DAY,
+                s,
+                line,
+                format!("no tunnels lead from {} to {}", name(from), name(to)),
+            )
+        })?;
         Ok(Self {
-            start: interner.get("AA").expect("AA not found in network"),
+            start,
             interner,
-            shortest_paths: Self::create_shortest_paths(&valves),
+            shortest_paths,
             valves,
         })
     }
+    /// Fails with the first two valves that have no way between them.
     fn create_shortest_paths(
         valves: &HashMap<DefaultSymbol, Valve>,
-    ) -> HashMap<(DefaultSymbol, DefaultSymbol), Vec<DefaultSymbol>> {
+    ) -> Result<ShortestPaths, (DefaultSymbol, DefaultSymbol)> {
         debug!("starting create shortest paths");
         let min = valves.keys().min().unwrap().to_usize();
         let max = valves.keys().max().unwrap().to_usize();
@@ -168,81 +266,69 @@ impl Network {
                 (min..=max).filter(move |j| &i != j).map(move |j| {
                     let i = DefaultSymbol::try_from_usize(i).unwrap();
                     let j = DefaultSymbol::try_from_usize(j).unwrap();
-                    ((i, j), Self::get_shortest_path(valves, i, j))
+                    Self::get_shortest_path(valves, i, j)
+                        .map(|path| ((i, j), path))
+                        .ok_or((i, j))
                 })
             })
             .collect();
         debug!("done create shortest paths");
         result
     }
+    /// The valves passed through on the way from `from` to `to`, excluding both.
     fn get_shortest_path(
         valves: &HashMap<DefaultSymbol, Valve>,
         from: DefaultSymbol,
         to: DefaultSymbol,
-    ) -> Vec<DefaultSymbol> {
-        let mut paths: Vec<_> = valves
-            .get(&from)
-            .unwrap()
-            .tunnels
-            .iter()
-            .map(|&i| vec![i])
+    ) -> Option<Vec<DefaultSymbol>> {
+        let path = Search::new()
+            .bfs(&mut Tunnels(valves), from, |&valve| valve == to)
+            .ok()?
+            .path;
+        Some(path[1..path.len() - 1].to_vec())
+    }
+    /// The valve, and the text of each of its tunnels.
+    fn parse_valve<'s>(
+        s: &'s str,
+        interner: &mut StringInterner,
+    ) -> Result<(Valve, Vec<&'s str>), ParseError> {
+        let error = |text: &str, message: &str| ParseError::new(DAY, s, text, message);
+        let (valve, tunnels) = s
+            .split_once("; ")
+            .ok_or_else(|| error(s, "expected '<valve>; <tunnels>'"))?;
+        let (name, flow_rate) = valve
+            .strip_prefix("Valve ")
+            .and_then(|valve| valve.split_once(" has flow rate="))
+            .ok_or_else(|| error(valve, "expected 'Valve <name> has flow rate=<rate>'"))?;
+        let name = interner.get_or_intern(name);
+        let flow_rate: u64 = parse_number(DAY, s, flow_rate)?;
+        let names: Vec<&str> = tunnels
+            .strip_prefix("tunnels lead to valves ")
+            .or_else(|| tunnels.strip_prefix("tunnel leads to valve "))
+            .ok_or_else(|| error(tunnels, "expected 'tunnels lead to valves <names>'"))?
+            .split(", ")
             .collect();
-        for _ in 0..50 {
-            let mut next = vec![];
-            for mut path in paths.into_iter() {
-                let last = path.iter().last().copied().unwrap();
-                if last == to {
-                    path.pop();
-                    return path;
-                }
-                next.extend(valves.get(&last).unwrap().tunnels.iter().map(|&last| {
-                    let mut copy = path.clone();
-                    copy.push(last);
-                    copy
-                }));
-            }
-            paths = next;
-        }
-        panic!("no path between {:?} and {:?}", from, to);
-    }
-    fn parse_valve(s: &str, interner: &mut StringInterner) -> Result<Valve> {
-        let mut parts = s.split(';');
-        let mut words = parts.next().context("empty")?.split_whitespace();
-        let name = interner.get_or_intern(words.nth(1).context("no words")?);
-        let flow_rate: u64 = words
-            .last()
-            .context("no flow rate")?
-            .split('=')
-            .nth(1)
-            .context("invalid flow rate")?
-            .parse()?;
-        let mut words = parts.next().context("no ;")?.split_whitespace().rev();
-        let mut tunnels = Vec::new();
-        loop {
-            let word = words
-                .next()
-                .context("cannot find 'valves'")?
-                .trim_matches(',');
-            if word.trim_end_matches('s') == "valve" {
-                break;
-            }
-            tunnels.push(interner.get_or_intern(word));
-        }
-        Ok(Valve {
+        let tunnels = names
+            .iter()
+            .map(|&tunnel| match tunnel {
+                "" => Err(error(tunnel, "expected a valve name")),
+                _ => Ok(interner.get_or_intern(tunnel)),
+            })
+            .collect::<Result<_, _>>()?;
+        let valve = Valve {
             name,
             flow_rate,
             tunnels,
-        })
+        };
+        Ok((valve, names))
     }
-    #[allow(unused)]
     pub fn run<'a, S: Stateful<'a>>(&'a self, minutes: usize) -> S {
         let mut pending = vec![S::new(self, minutes, self.start)];
         let mut finished: Vec<S> = vec![];
 
-        let mut i = 0;
+        let progress = Progress::new("day 16 breadth-first search");
         while !pending.is_empty() {
-            i += 1;
-            debug!("round {}: {} left", i, pending.len());
+            progress.explored(pending.len() as u64);
             pending = pending
                 .into_iter()
                 .flat_map(|state| match state.next() {
@@ -262,7 +348,52 @@ impl Network {
     }
 }
 
-trait Stateful<'a>
+/// The most pressure released alone in 30 minutes, trying every order of valves and with the
+/// faster search, for differential testing.
+/// The most pressure of two sets that share no valve, with their pressures added up. Either may
+/// be the empty set, for an actor that opens nothing and leaves every valve to the other.
+fn best_disjoint_pair(best: &HashMap<u64, u64>) -> u64 {
+    let mut sets: Vec<(u64, u64)> = best
+        .iter()
+        .map(|(&set, &pressure)| (pressure, set))
+        .collect();
+    sets.sort_unstable_by(|a, b| b.cmp(a));
+    let mut max = 0;
+    for (i, &(pressure, set)) in sets.iter().enumerate() {
+        // the pressures only go down from here, so no later pair can do better
+        if 2 * pressure <= max {
+            break;
+        }
+        for &(other_pressure, other_set) in sets[i..].iter() {
+            if pressure + other_pressure <= max {
+                break;
+            }
+            if set & other_set == 0 {
+                max = pressure + other_pressure;
+            }
+        }
+    }
+    max
+}
+
+pub(crate) fn pressure_alone_both_ways(input: &str) -> anyhow::Result<(Answer, Answer)> {
+    let network = Network::parse_from(input)?;
+    Ok((
+        network.run::<State>(30).total_pressure.into(),
+        network.find_max_pressure_faster(30, 1).into(),
+    ))
+}
+
+/// The same with an elephant in 26 minutes.
+pub(crate) fn pressure_with_elephant_both_ways(input: &str) -> anyhow::Result<(Answer, Answer)> {
+    let network = Network::parse_from(input)?;
+    Ok((
+        network.run::<CoopState>(26).total_pressure.into(),
+        network.find_max_pressure_faster(26, 2).into(),
+    ))
+}
+
+pub trait Stateful<'a>
 where
     Self: Sized,
 {
@@ -456,7 +587,7 @@ impl<'a> CoopState<'a> {
         self.total_pressure += (moving.end_minute as u64) * flow_rate;
         self.open_history.push(moving.destination);
     }
-    pub fn next(mut self) -> NextState<Self> {
+    pub fn next(self) -> NextState<Self> {
         if self.moving.iter().all(|m| m.done) {
             NextState::Done(self)
         } else {
@@ -467,25 +598,24 @@ impl<'a> CoopState<'a> {
                 .max_by_key(|m| m.1.end_minute)
                 .unwrap();
             let next = self.next_candidates(index);
-            if next.is_empty() {
-                self.moving[index] = MovingCharacter {
-                    start_minute: 0,
-                    end_minute: 0,
-                    destination: self.network.start,
-                    done: true,
-                };
-                NextState::Children(vec![self])
-            } else {
-                NextState::Children(
-                    next.into_iter()
-                        .map(|next| {
-                            let mut s = self.clone();
-                            s.move_and_open(next, index);
-                            s
-                        })
-                        .collect(),
-                )
-            }
+            // stopping early can leave a valve to the other character
+            let mut stopped = self.clone();
+            stopped.moving[index] = MovingCharacter {
+                start_minute: 0,
+                end_minute: 0,
+                destination: self.network.start,
+                done: true,
+            };
+            NextState::Children(
+                next.into_iter()
+                    .map(|next| {
+                        let mut s = self.clone();
+                        s.move_and_open(next, index);
+                        s
+                    })
+                    .chain([stopped])
+                    .collect(),
+            )
         }
     }
 }
@@ -512,7 +642,7 @@ impl<'a> Stateful<'a> for CoopState<'a> {
     }
 }
 
-enum NextState<T> {
+pub enum NextState<T> {
     Done(T),
     Children(Vec<T>),
 }
@@ -539,5 +669,54 @@ Valve JJ has flow rate=21; tunnel leads to valve II";
 
         assert_eq!(1651, network.find_max_pressure_faster(30, 1));
         assert_eq!(1707, network.find_max_pressure_faster(26, 2));
+        let cancelled = Cancellation::new();
+        cancelled.cancel();
+        assert_eq!(
+            Outcome::TimedOut(0),
+            network.find_max_pressure_until(30, 1, &cancelled)
+        );
+
+        let error = Network::parse_from(&input.replace("valve GG", "valve XX"))
+            .err()
+            .unwrap();
+        assert_eq!(
+            (8, 50, "XX"),
+            (error.line, error.column, error.text.as_str())
+        );
+        let error = Network::parse_from(&input.replace("rate=13", "rate=-13"))
+            .err()
+            .unwrap();
+        assert_eq!(
+            (2, 24, "-13"),
+            (error.line, error.column, error.text.as_str())
+        );
+        let error = Network::parse_from(&input.replace("valve GG", "valve HH"))
+            .err()
+            .unwrap();
+        assert_eq!(
+            (8, "no tunnels lead from HH to AA"),
+            (error.line, error.message.as_str())
+        );
+        let many: Vec<String> = (0..=MAX_FLOWING_VALVES)
+            .map(|i| format!("Valve V{} has flow rate=1; tunnel leads to valve AA", i))
+            .collect();
+        let error = Network::parse_from(&format!(
+            "Valve AA has flow rate=0; tunnel leads to valve V0\n{}",
+            many.join("\n")
+        ))
+        .err()
+        .unwrap();
+        assert_eq!(
+            (66, "more than 64 valves have a flow rate"),
+            (error.line, error.message.as_str())
+        );
+        // a flowing start is opened without moving
+        let network = Network::parse_from(
+            "Valve AA has flow rate=10; tunnel leads to valve BB\n\
+             Valve BB has flow rate=1; tunnel leads to valve AA",
+        )
+        .unwrap();
+        assert_eq!(10 * 4 + 2, network.find_max_pressure_faster(5, 1));
+        assert_eq!(10 * 4 + 3, network.find_max_pressure_faster(5, 2));
     }
 }
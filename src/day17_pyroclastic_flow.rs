@@ -1,58 +1,120 @@
 use std::collections::hash_map::DefaultHasher;
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
-use std::ops::{Add, Sub};
 
 use lazy_static::lazy_static;
 use log::{debug, info};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input17.txt").unwrap();
-    for i in 0usize..5 {
-        let rock = Rock::new(RockKind::from(i), 0);
-        println!("{:?} parts: {:?}", rock, rock.filled_positions());
+use crate::grid::{Coord, Grid2D};
+use crate::params::{Parameter, Params};
+use crate::parse_error::ParseError;
+use crate::progress::Progress;
+use crate::solution::Solution;
+use crate::visualize::{Frame, Visualize};
+
+const DAY: u8 = 17;
+
+pub struct PyroclasticFlow;
+
+impl Solution for PyroclasticFlow {
+    type Input = Vec<Direction>;
+    type Answer1 = i64;
+    type Answer2 = i64;
+    const PARAMETERS: &'static [Parameter] = &[
+        Parameter::integer("part1_rocks", 2022, "rocks dropped in part 1").range(0, 1_000_000),
+        Parameter::integer("part2_rocks", 1_000_000_000_000, "rocks dropped in part 2")
+            .range(0, i64::MAX),
+    ];
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        for i in 0usize..5 {
+            let rock = Rock::new(RockKind::from(i), 0);
+            debug!("{:?} parts: {:?}", rock, rock.filled_positions());
+        }
+        Ok(parse_jets(input)?)
+    }
+    fn part1(jets: &Self::Input) -> i64 {
+        Self::part1_with(jets, &Params::defaults(Self::PARAMETERS))
     }
-    let mut tunnel = Tunnel::new(input.chars().map(|c| Direction::from(c)).collect());
-    // tunnel.debug = true;
-    tunnel.run(2022);
-    println!(
-        "tower height after 2022 rocks: {}",
+    fn part2(jets: &Self::Input) -> i64 {
+        Self::part2_with(jets, &Params::defaults(Self::PARAMETERS))
+    }
+    fn part1_with(jets: &Self::Input, params: &Params) -> i64 {
+        let mut tunnel = Tunnel::new(jets.clone());
+        tunnel.run(params.integer("part1_rocks") as usize);
         tunnel.highest_position()
-    );
-
-    let tunnel = Tunnel::new(input.chars().map(|c| Direction::from(c)).collect());
-    let n = 1000000000000;
-    println!(
-        "tower height after {} rocks: {}",
-        n,
-        tunnel.run_cached_get_height(n)
-    );
+    }
+    fn part2_with(jets: &Self::Input, params: &Params) -> i64 {
+        Tunnel::new(jets.clone()).run_cached_get_height(params.integer("part2_rocks") as usize)
+    }
+    /// Part 1 drops 2022 rocks; part 2 drops rocks until the tunnel repeats a state.
+    fn visualize(jets: &Self::Input, part: u8) -> Option<Box<dyn Visualize + '_>> {
+        let mut tunnel = Tunnel::new(jets.clone());
+        let mut seen = HashSet::new();
+        Some(Box::new((1..).map_while(move |rock| {
+            let repeated = !seen.insert(tunnel.get_state_hash());
+            if (part == 1 && rock > 2022) || (part == 2 && repeated) {
+                return None;
+            }
+            tunnel.next_rock();
+            Some(Frame::new(
+                format!("rock {}, height {}", rock, tunnel.highest_position()),
+                &tunnel,
+            ))
+        })))
+    }
+    /// At least `size` jets of hot gas, as many pushing left as right. Part 2 waits for the
+    /// tops of the columns to repeat, which never happens if the jets keep a column empty, so
+    /// jets are shuffled until the tunnel repeats within a couple of thousand rocks.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        let mut jets: Vec<char> = (0..size.max(40)).map(|i| ['<', '>'][i % 2]).collect();
+        loop {
+            jets.shuffle(rng);
+            let jets: String = jets.iter().collect();
+            let mut tunnel = Tunnel::new(parse_jets(&jets).unwrap());
+            let mut seen = HashSet::new();
+            if (0..2000).any(|_| {
+                let repeated = !seen.insert(tunnel.get_state_hash());
+                tunnel.next_rock();
+                repeated
+            }) {
+                return jets;
+            }
+        }
+    }
 }
 
 struct Tunnel {
-    settled_rocks: BTreeMap<i64, Vec<Rock>>,
+    /// Settled rock, with row `y - 1` holding height `y` so that the chamber grows by adding rows.
+    chamber: Grid2D<bool>,
     kind: usize,
     jets: Vec<Direction>,
     jet_index: usize,
-    debug: bool,
 }
 
 impl Tunnel {
+    const WIDTH: usize = 7;
+    const MAX_X: i64 = Self::WIDTH as i64 - 1;
+
     pub fn new(jets: Vec<Direction>) -> Self {
         Self {
-            settled_rocks: Default::default(),
+            chamber: Grid2D::new(Self::WIDTH, 0, false),
             kind: 0,
             jets,
             jet_index: 0,
-            debug: false,
         }
     }
     pub fn run_cached_get_height(mut self, amount: usize) -> i64 {
         let mut heights_cache: HashMap<u64, i64> = HashMap::new();
         let mut hashes = vec![];
 
+        // the search for a repeated state can end long before `amount`, so there's no total
+        let progress = Progress::new("day 17 rocks");
         for i in 0..amount {
+            progress.explored(1);
             let hash = self.get_state_hash();
             if heights_cache.contains_key(&hash) {
                 let start = hashes.iter().position(|&h| h == hash).unwrap();
@@ -94,24 +156,13 @@ impl Tunnel {
         hasher.finish()
     }
     fn get_floor_shape(&self) -> Vec<i64> {
-        if self.settled_rocks.is_empty() {
-            return vec![0; (Coord::MAX_X + 1) as usize];
-        }
-        let max_y = self.highest_position();
-        let shape: Vec<_> = (0..=Coord::MAX_X)
-            .map(|x| {
-                (1..=max_y)
-                    .rev()
-                    .filter_map(|y| {
-                        let c = Coord::new(x, y);
-                        self.get_rocks_in_range(y, y)
-                            .flat_map(|r| r.filled_positions())
-                            .filter(|coord| &c == coord)
-                            .map(|coord| coord.y)
-                            .next()
-                    })
-                    .next()
-                    .unwrap_or_default()
+        let shape: Vec<_> = self
+            .chamber
+            .columns()
+            .map(|mut column| {
+                column
+                    .rposition(|&filled| filled)
+                    .map_or(0, |y| y as i64 + 1)
             })
             .collect();
         let min = shape.iter().copied().min().unwrap();
@@ -120,11 +171,6 @@ impl Tunnel {
     pub fn run(&mut self, amount: usize) {
         for _ in 0..amount {
             self.next_rock();
-            if self.debug {
-                println!("{:?}", self.settled_rocks);
-                println!("{}", self);
-                let _ = std::io::stdin().read_line(&mut String::new());
-            }
         }
     }
     fn next_jet(&mut self) -> Direction {
@@ -133,10 +179,7 @@ impl Tunnel {
         dir
     }
     pub fn highest_position(&self) -> i64 {
-        self.settled_rocks
-            .last_key_value()
-            .map(|(i, _)| *i)
-            .unwrap_or_default()
+        self.chamber.height() as i64
     }
     pub fn next_rock(&mut self) {
         let kind = self.kind;
@@ -150,15 +193,18 @@ impl Tunnel {
             }
         }
         debug!("deposited rock: {:?}", rock.position);
-        self.settled_rocks
-            .entry(rock.position.y)
-            .or_default()
-            .push(rock);
+        if rock.position.y > self.highest_position() {
+            self.chamber.resize(rock.position.y as usize, false);
+        }
+        for c in rock.filled_positions() {
+            self.chamber[Coord::new(c.x, c.y - 1)] = true;
+        }
     }
-    pub fn get_rocks_in_range(&self, min_y: i64, max_y: i64) -> impl Iterator<Item = &Rock> {
-        (min_y..=max_y)
-            .filter_map(|y| self.settled_rocks.get(&y))
-            .flat_map(|lines| lines)
+    pub fn is_filled(&self, coord: &Coord) -> bool {
+        self.chamber
+            .get(Coord::new(coord.x, coord.y - 1))
+            .copied()
+            .unwrap_or_default()
     }
 }
 
@@ -177,39 +223,31 @@ impl Rock {
     }
     pub fn try_move(&mut self, direction: Direction, tunnel: &Tunnel) -> bool {
         let mut s = self.clone();
-        s.position = &self.position + &(Coord::from(direction));
+        s.position = self.position + Coord::from(direction);
         let filled = s.filled_positions();
         if filled
             .iter()
-            .any(|c| c.y <= 0 || c.x > Coord::MAX_X || c.x < 0)
+            .any(|c| c.y <= 0 || c.x > Tunnel::MAX_X || c.x < 0)
         {
             return false;
         }
-        let max_y = filled.iter().map(|c| c.y).max().unwrap() + 3;
-        let min_y = filled.iter().map(|c| c.y).min().unwrap();
-        if !tunnel
-            .get_rocks_in_range(min_y, max_y)
-            .any(|rock| filled.iter().any(|p| rock.is_filled(p)))
-        {
+        if !filled.iter().any(|p| tunnel.is_filled(p)) {
             *self = s;
             true
         } else {
             false
         }
     }
-    pub fn is_filled(&self, coord: &Coord) -> bool {
-        self.kind.is_filled(&(coord - &self.position))
-    }
     fn filled_positions(&self) -> Vec<Coord> {
         self.kind
             .filled_positions_from_top_left()
-            .map(|coord| &self.position + coord)
+            .map(|&coord| self.position + coord)
             .collect()
     }
 }
 
 #[derive(Copy, Clone)]
-enum Direction {
+pub enum Direction {
     Left,
     Right,
     Down,
@@ -225,14 +263,23 @@ impl From<Direction> for Coord {
     }
 }
 
-impl From<char> for Direction {
-    fn from(value: char) -> Self {
-        match value {
-            '<' => Self::Left,
-            '>' => Self::Right,
-            other => panic!("invalid direction '{}'", other),
-        }
+fn parse_jets(input: &str) -> Result<Vec<Direction>, ParseError> {
+    let jets = input.trim();
+    if jets.is_empty() {
+        return Err(ParseError::new(DAY, input, input, "no jets"));
     }
+    jets.char_indices()
+        .map(|(i, c)| match c {
+            '<' => Ok(Direction::Left),
+            '>' => Ok(Direction::Right),
+            _ => Err(ParseError::new(
+                DAY,
+                input,
+                &jets[i..i + c.len_utf8()],
+                "invalid direction",
+            )),
+        })
+        .collect()
 }
 
 #[derive(Clone, Debug)]
@@ -305,9 +352,6 @@ impl RockKind {
     pub fn filled_positions_from_top_left(&self) -> impl Iterator<Item = &Coord> + '_ {
         self.set().iter()
     }
-    pub fn is_filled(&self, coord: &Coord) -> bool {
-        self.set().contains(coord)
-    }
     pub fn height(&self) -> i64 {
         match self {
             RockKind::Flat => 1,
@@ -319,59 +363,16 @@ impl RockKind {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
-struct Coord {
-    y: i64,
-    x: i64,
-}
-
-impl Coord {
-    const MAX_X: i64 = 6;
-    pub fn new(x: i64, y: i64) -> Self {
-        Self { x, y }
-    }
-}
-
-impl Add for &Coord {
-    type Output = Coord;
-
-    fn add(self, rhs: Self) -> Self::Output {
-        Coord {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
-        }
-    }
-}
-
-impl Sub for &Coord {
-    type Output = Coord;
-
-    fn sub(self, rhs: Self) -> Self::Output {
-        Coord {
-            x: self.x - rhs.x,
-            y: self.y - rhs.y,
-        }
-    }
-}
-
 impl Display for Tunnel {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         for y in (1..=self.highest_position()).rev() {
             writeln!(
                 f,
                 "|{}|",
-                (0..=Coord::MAX_X)
-                    .map(|x| {
-                        let coord = Coord::new(x, y);
-                        if self
-                            .get_rocks_in_range(coord.y, coord.y + 3)
-                            .any(|r| r.is_filled(&coord))
-                        {
-                            '#'
-                        } else {
-                            '.'
-                        }
-                    })
+                self.chamber
+                    .row(y as usize - 1)
+                    .iter()
+                    .map(|&filled| if filled { '#' } else { '.' })
                     .collect::<String>()
             )?
         }
@@ -387,7 +388,7 @@ mod tests {
     fn test1() {
         let input = ">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>";
 
-        let mut tunnel = Tunnel::new(input.chars().map(|c| Direction::from(c)).collect());
+        let mut tunnel = Tunnel::new(parse_jets(input).unwrap());
         tunnel.run(10);
         let res = "\
 |....#..|
@@ -411,14 +412,19 @@ mod tests {
         println!("result:\n{}\n\nactual:\n{}", res, tunnel);
         assert_eq!(res, format!("{}", tunnel));
 
-        let mut tunnel = Tunnel::new(input.chars().map(|c| Direction::from(c)).collect());
+        let mut tunnel = Tunnel::new(parse_jets(input).unwrap());
         tunnel.run(2022);
         assert_eq!(3068, tunnel.highest_position());
+        let jets = parse_jets(input).unwrap();
+        let mut frames = PyroclasticFlow::visualize(&jets, 1).unwrap();
+        let last = std::iter::from_fn(|| frames.next_frame()).last().unwrap();
+        assert_eq!("rock 2022, height 3068", last.caption);
+        assert_eq!(tunnel.to_string(), last.picture);
 
         let test = |i: usize| {
-            let tunnel = Tunnel::new(input.chars().map(|c| Direction::from(c)).collect());
+            let tunnel = Tunnel::new(parse_jets(input).unwrap());
             let a = tunnel.run_cached_get_height(i);
-            let mut tunnel = Tunnel::new(input.chars().map(|c| Direction::from(c)).collect());
+            let mut tunnel = Tunnel::new(parse_jets(input).unwrap());
             tunnel.run(i);
             let b = tunnel.highest_position();
             assert_eq!(b, a, "i={}", i)
@@ -428,7 +434,10 @@ mod tests {
         test(150);
         test(300);
 
-        let tunnel = Tunnel::new(input.chars().map(|c| Direction::from(c)).collect());
+        let tunnel = Tunnel::new(parse_jets(input).unwrap());
         assert_eq!(1514285714288, tunnel.run_cached_get_height(1000000000000));
+
+        let error = parse_jets(">>><x<>").err().unwrap();
+        assert_eq!((1, 5, "x"), (error.line, error.column, error.text.as_str()));
     }
 }
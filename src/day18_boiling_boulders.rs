@@ -6,21 +6,61 @@ use std::str::FromStr;
 
 use lazy_static::lazy_static;
 use log::debug;
+use rand::rngs::StdRng;
+use rand::Rng;
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input18.txt").unwrap();
-    // let input = "2,2,2\n1,2,2\n3,2,2\n2,1,2\n2,3,2\n2,2,1\n2,2,3\n2,2,4\n2,2,6\n1,2,5\n3,2,5\n2,1,5\n2,3,5\n";
+use crate::parse_error::{parse_lines, parse_number, ParseError};
+use crate::solution::Solution;
 
+const DAY: u8 = 18;
+
+pub struct BoilingBoulders;
+
+impl Solution for BoilingBoulders {
+    type Input = Grid3D;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        let grid = parse_grid(input)?;
+        debug!("\n{}", grid);
+        Ok(grid)
+    }
+    fn part1(grid: &Self::Input) -> usize {
+        grid.total_surface_area()
+    }
+    fn part2(grid: &Self::Input) -> usize {
+        grid.external_surface_area()
+    }
+    /// `size` different cubes packed in a box about twice their volume, so that some touch and
+    /// some enclose air.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        let side = (2.0 * size as f64).cbrt().ceil() as u32 + 1;
+        let mut cubes = vec![];
+        while cubes.len() < size {
+            let cube = [(); 3].map(|_| rng.gen_range(0..side));
+            if !cubes.contains(&cube) {
+                cubes.push(cube);
+            }
+        }
+        cubes
+            .iter()
+            .map(|[x, y, z]| format!("{},{},{}", x, y, z))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn parse_grid(input: &str) -> Result<Grid3D, ParseError> {
     let mut grid = Grid3D::new();
-    for shape in input.lines().map(|l| Shape::new(l.parse().unwrap())) {
-        grid.add_shape(shape);
+    for coord in parse_lines(input)? {
+        grid.add_shape(Shape::new(coord));
     }
-    println!("{}", grid);
-    println!("surface area: {}", grid.total_surface_area());
-    println!("external surface area: {}", grid.external_surface_area());
+    Ok(grid)
 }
 
-struct Grid3D {
+#[derive(Default)]
+pub struct Grid3D {
     shapes: Vec<Shape>,
 }
 
@@ -186,7 +226,7 @@ impl Grid3D {
 }
 
 #[derive(Eq, PartialEq)]
-struct Shape {
+pub struct Shape {
     outer_sides: HashSet<Side>,
 }
 
@@ -221,7 +261,7 @@ impl Shape {
 }
 
 #[derive(Eq, PartialEq, Hash, Clone)]
-struct Side {
+pub struct Side {
     coord: Coord,
     direction: Direction,
 }
@@ -272,7 +312,7 @@ impl Side {
 }
 
 #[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
-enum Direction {
+pub enum Direction {
     PlusX,
     MinusX,
     PlusY,
@@ -309,7 +349,7 @@ impl Direction {
 }
 
 #[derive(Eq, PartialEq, Hash, Clone)]
-struct Coord {
+pub struct Coord {
     x: i32,
     y: i32,
     z: i32,
@@ -363,14 +403,17 @@ impl Add for &Coord {
 }
 
 impl FromStr for Coord {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.split(',');
+        let parts: Vec<&str> = s.split(',').collect();
+        let [x, y, z] = parts[..] else {
+            return Err(ParseError::new(DAY, s, s, "expected 'x,y,z'"));
+        };
         Ok(Self {
-            x: parts.next().unwrap().parse().unwrap(),
-            y: parts.next().unwrap().parse().unwrap(),
-            z: parts.next().unwrap().parse().unwrap(),
+            x: parse_number(DAY, s, x)?,
+            y: parse_number(DAY, s, y)?,
+            z: parse_number(DAY, s, z)?,
         })
     }
 }
@@ -446,12 +489,15 @@ mod tests {
 2,1,5
 2,3,5";
 
-        let mut grid = Grid3D::new();
-        for shape in input.lines().map(|l| Shape::new(l.parse().unwrap())) {
-            grid.add_shape(shape);
-        }
+        let grid = parse_grid(input).unwrap();
         println!("{}", grid);
         assert_eq!(64, grid.total_surface_area());
         assert_eq!(58, grid.external_surface_area());
+
+        let error = parse_grid("2,2,2\n1,2").err().unwrap();
+        assert_eq!(
+            (2, 1, "1,2"),
+            (error.line, error.column, error.text.as_str())
+        );
     }
 }
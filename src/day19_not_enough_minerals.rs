@@ -1,50 +1,114 @@
 use std::collections::{BTreeMap, HashMap};
 use std::str::FromStr;
 
-use anyhow::Context;
 use log::{debug, info};
+use rand::rngs::StdRng;
+use rand::Rng;
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input19.txt").unwrap();
-    // let input = "Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.
-    // Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsidian robot costs 3 ore and 8 clay. Each geode robot costs 3 ore and 12 obsidian.";
-    let blueprints: Vec<Blueprint> = input.lines().map(|l| l.parse().unwrap()).collect();
-    println!(
-        "sum quality levels: {}",
-        sum_quality_levels(24, &blueprints)
-    );
+use crate::cancel::{Cancellation, Outcome};
+use crate::params::{Parameter, Params};
+use crate::parse_error::{parse_lines, parse_number, ParseError};
+use crate::progress::{Batch, Progress};
+use crate::solution::Solution;
 
-    println!(
-        "product after 32 minutes: {}",
-        product_first_three(32, &blueprints)
-    );
+const DAY: u8 = 19;
+
+pub struct NotEnoughMinerals;
+
+impl Solution for NotEnoughMinerals {
+    type Input = Vec<Blueprint>;
+    type Answer1 = u32;
+    type Answer2 = u32;
+    const PARAMETERS: &'static [Parameter] = &[
+        Parameter::integer("part1_minutes", 24, "minutes to open geodes in part 1").range(0, 64),
+        Parameter::integer("part2_minutes", 32, "minutes to open geodes in part 2").range(0, 64),
+    ];
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(parse_lines(input)?)
+    }
+    fn part1(blueprints: &Self::Input) -> u32 {
+        Self::part1_with(blueprints, &Params::defaults(Self::PARAMETERS))
+    }
+    fn part2(blueprints: &Self::Input) -> u32 {
+        Self::part2_with(blueprints, &Params::defaults(Self::PARAMETERS))
+    }
+    fn part1_with(blueprints: &Self::Input, params: &Params) -> u32 {
+        Self::part1_until(blueprints, params, &Cancellation::new()).value()
+    }
+    fn part2_with(blueprints: &Self::Input, params: &Params) -> u32 {
+        Self::part2_until(blueprints, params, &Cancellation::new()).value()
+    }
+    fn part1_until(
+        blueprints: &Self::Input,
+        params: &Params,
+        cancellation: &Cancellation,
+    ) -> Outcome<u32> {
+        let minutes = params.integer("part1_minutes") as u32;
+        cancellation.outcome(sum_quality_levels(minutes, blueprints, cancellation))
+    }
+    fn part2_until(
+        blueprints: &Self::Input,
+        params: &Params,
+        cancellation: &Cancellation,
+    ) -> Outcome<u32> {
+        let minutes = params.integer("part2_minutes") as u32;
+        cancellation.outcome(product_first_three(minutes, blueprints, cancellation))
+    }
+    /// `size` blueprints with costs in the ranges of the puzzle's.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        (1..=size)
+            .map(|id| {
+                format!(
+                    "Blueprint {}: Each ore robot costs {} ore. Each clay robot costs {} ore. \
+                     Each obsidian robot costs {} ore and {} clay. \
+                     Each geode robot costs {} ore and {} obsidian.",
+                    id,
+                    rng.gen_range(2..=4),
+                    rng.gen_range(2..=4),
+                    rng.gen_range(2..=4),
+                    rng.gen_range(5..=20),
+                    rng.gen_range(2..=4),
+                    rng.gen_range(5..=20),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Progress through `count` blueprints, each done once every minute is.
+fn blueprints_progress(count: usize, minutes: u32) -> Progress {
+    Progress::new("day 19 blueprints").with_total(count as u64 * minutes as u64)
 }
 
-fn sum_quality_levels(minutes: u32, blueprints: &[Blueprint]) -> u32 {
+fn sum_quality_levels(minutes: u32, blueprints: &[Blueprint], cancellation: &Cancellation) -> u32 {
+    let progress = blueprints_progress(blueprints.len(), minutes);
     blueprints
         .iter()
         .map(|blueprint| {
-            let geodes = State::new(minutes).max_geodes(blueprint);
-            println!("max for blueprint {}: {}", blueprint.id, geodes);
+            let geodes = State::new(minutes).max_geodes(blueprint, cancellation, &progress);
+            info!("max for blueprint {}: {}", blueprint.id, geodes);
             blueprint.id * geodes
         })
         .sum()
 }
 
-fn product_first_three(minutes: u32, blueprints: &[Blueprint]) -> u32 {
+fn product_first_three(minutes: u32, blueprints: &[Blueprint], cancellation: &Cancellation) -> u32 {
+    let progress = blueprints_progress(blueprints.len().min(3), minutes);
     blueprints
         .iter()
         .take(3)
         .map(|blueprint| {
-            let geodes = State::new(minutes).max_geodes(blueprint);
-            println!("max for blueprint {}: {}", blueprint.id, geodes);
+            let geodes = State::new(minutes).max_geodes(blueprint, cancellation, &progress);
+            info!("max for blueprint {}: {}", blueprint.id, geodes);
             geodes
         })
         .product()
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
-struct State {
+pub struct State {
     resources: [u32; 4],
     bots: [u32; 4],
     pub minutes_left: u32,
@@ -77,32 +141,35 @@ impl State {
 // }
 type MaxResult = u32;
 
-struct Cache {
+struct Cache<'a> {
     cache: HashMap<State, MaxResult>,
-    minutes_left: u32,
-    initial_minutes: u32,
+    batch: Batch<'a>,
+    /// Of the progress, from the blueprints before this one.
+    done_before: u64,
+    /// The most minutes left of a finished state.
+    minutes_done: u32,
 }
 
-impl Cache {
-    pub fn new(minutes: u32) -> Self {
+impl<'a> Cache<'a> {
+    pub fn new(progress: &'a Progress) -> Self {
         Self {
             cache: Default::default(),
-            minutes_left: 0,
-            initial_minutes: minutes,
+            batch: Batch::new(progress),
+            done_before: progress.done(),
+            minutes_done: 0,
         }
     }
     pub fn get(&self, state: &State) -> Option<MaxResult> {
         self.cache.get(state).cloned()
     }
     pub fn set(&mut self, state: State, result: MaxResult) {
-        if state.minutes_left > self.minutes_left {
-            info!(
-                "{}% done ({}/{})",
-                100 * (state.minutes_left) / self.initial_minutes,
-                state.minutes_left,
-                self.initial_minutes
-            );
-            self.minutes_left = state.minutes_left;
+        self.batch.explored();
+        // the search is depth first, so the states with the most minutes left finish last
+        if state.minutes_left > self.minutes_done {
+            self.minutes_done = state.minutes_left;
+            self.batch
+                .progress()
+                .set_done(self.done_before + state.minutes_left as u64);
         }
         self.cache.insert(state, result);
     }
@@ -119,7 +186,17 @@ impl State {
         *s.get_bot_mut(Mineral::Ore) += 1;
         s
     }
-    fn max_geodes_work(mut self, blueprint: &Blueprint, cache: &mut Cache) -> MaxResult {
+    fn max_geodes_work(
+        mut self,
+        blueprint: &Blueprint,
+        cache: &mut Cache,
+        cancellation: &Cancellation,
+    ) -> MaxResult {
+        // once cancelled, the geode bots are left to work without building any more
+        if cancellation.is_cancelled() {
+            return self.get_resource(Mineral::Geode)
+                + self.get_bot(Mineral::Geode) * self.minutes_left;
+        }
         if self.minutes_left == 0 {
             return
                 // MaxResult {
@@ -152,7 +229,7 @@ impl State {
                     // mut
                     max = s.
                     // clone().
-                    max_geodes_work(blueprint, cache);
+                    max_geodes_work(blueprint, cache, cancellation);
                 // max.history.push(s);
                 max
             })
@@ -162,7 +239,7 @@ impl State {
             self.collect_resources();
             let
                 // mut
-                res = self.clone().max_geodes_work(blueprint, cache);
+                res = self.clone().max_geodes_work(blueprint, cache, cancellation);
             // res.history.push(self);
             if let Some(max) = max {
                 // if res.geodes_count > max.geodes_count {
@@ -180,11 +257,17 @@ impl State {
         cache.set(initial_state, result.clone());
         result
     }
-    pub fn max_geodes(self, blueprint: &Blueprint) -> u32 {
+    /// The most geodes found before `cancellation` was cancelled, if it was.
+    pub fn max_geodes(
+        self,
+        blueprint: &Blueprint,
+        cancellation: &Cancellation,
+        progress: &Progress,
+    ) -> u32 {
         // let minutes = self.minutes_left;
         let result = {
-            let mut cache = Cache::new(self.minutes_left);
-            self.max_geodes_work(blueprint, &mut cache)
+            let mut cache = Cache::new(progress);
+            self.max_geodes_work(blueprint, &mut cache, cancellation)
         };
         debug!("\nresults for {:?}:", blueprint);
         // for state in result.history.iter().rev() {
@@ -196,7 +279,7 @@ impl State {
 }
 
 #[derive(Debug)]
-struct Blueprint {
+pub struct Blueprint {
     id: u32,
     bot_costs: BTreeMap<Mineral, Cost>,
     max_ore_cost: u32,
@@ -236,76 +319,70 @@ impl Blueprint {
 }
 
 #[derive(Debug)]
-struct Cost(BTreeMap<Mineral, u32>);
+pub struct Cost(BTreeMap<Mineral, u32>);
 
 #[derive(Eq, PartialEq, Hash, Copy, Clone, Debug, Ord, PartialOrd)]
-enum Mineral {
+pub enum Mineral {
     Ore = 1,
     Clay,
     Obsidian,
     Geode,
 }
 
+impl FromStr for Mineral {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "ore" => Self::Ore,
+            "clay" => Self::Clay,
+            "obsidian" => Self::Obsidian,
+            "geode" => Self::Geode,
+            _ => return Err(ParseError::new(DAY, s, s, "invalid mineral")),
+        })
+    }
+}
+
 impl FromStr for Blueprint {
-    type Err = anyhow::Error;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.split(':');
-        let id = parts
-            .next()
-            .context("no first line")?
-            .split_whitespace()
-            .nth(1)
-            .context("no id")?
-            .parse()?;
-        let mut parts = parts.next().context("no second line")?.split('.');
-        let mut words = parts.next().context("no ore")?.split_whitespace().rev();
-        let ore = Cost(
-            vec![(Mineral::Ore, words.nth(1).context("ore")?.parse()?)]
-                .into_iter()
-                .collect(),
-        );
-        let mut words = parts.next().context("no clay")?.split_whitespace().rev();
-        let clay = Cost(
-            vec![(Mineral::Ore, words.nth(1).context("ore 2")?.parse()?)]
-                .into_iter()
-                .collect(),
-        );
-        let mut words = parts
-            .next()
-            .context("no obsidian")?
-            .split_whitespace()
-            .rev();
-        let obsidian = Cost(
-            vec![
-                (Mineral::Clay, words.nth(1).context("clay")?.parse()?),
-                (Mineral::Ore, words.nth(2).context("ore 3")?.parse()?),
-            ]
-            .into_iter()
-            .collect(),
-        );
-        let mut words = parts.next().context("no geode")?.split_whitespace().rev();
-        let geode = Cost(
-            vec![
-                (
-                    Mineral::Obsidian,
-                    words.nth(1).context("obsidian")?.parse()?,
-                ),
-                (Mineral::Ore, words.nth(2).context("ore 4")?.parse()?),
-            ]
-            .into_iter()
-            .collect(),
-        );
-        Ok(Self::build_from(
-            id,
-            vec![
-                (Mineral::Ore, ore),
-                (Mineral::Clay, clay),
-                (Mineral::Obsidian, obsidian),
-                (Mineral::Geode, geode),
-            ]
-            .into_iter(),
-        ))
+        let error = |text: &str, message: &str| ParseError::new(DAY, s, text, message);
+        let mineral = |text: &str| text.parse::<Mineral>().map_err(|e| e.within(s, text));
+        let (id, robots) = s
+            .strip_prefix("Blueprint ")
+            .and_then(|s| s.split_once(": "))
+            .ok_or_else(|| error(s, "expected 'Blueprint <id>: <robots>'"))?;
+        let id = parse_number(DAY, s, id)?;
+
+        let mut bot_costs = BTreeMap::new();
+        for robot in robots.split_terminator('.').map(str::trim) {
+            let (bot, costs) = robot
+                .strip_prefix("Each ")
+                .and_then(|robot| robot.split_once(" robot costs "))
+                .ok_or_else(|| error(robot, "expected 'Each <mineral> robot costs <costs>'"))?;
+            let costs = costs
+                .split(" and ")
+                .map(|cost| {
+                    let (amount, kind) = cost
+                        .split_once(' ')
+                        .ok_or_else(|| error(cost, "expected '<amount> <mineral>'"))?;
+                    Ok((mineral(kind)?, parse_number(DAY, s, amount)?))
+                })
+                .collect::<Result<_, ParseError>>()?;
+            bot_costs.insert(mineral(bot)?, Cost(costs));
+        }
+        for bot in [
+            Mineral::Ore,
+            Mineral::Clay,
+            Mineral::Obsidian,
+            Mineral::Geode,
+        ] {
+            if !bot_costs.contains_key(&bot) {
+                return Err(error(s, &format!("missing {:?} robot", bot)));
+            }
+        }
+        Ok(Self::build_from(id, bot_costs.into_iter()))
     }
 }
 
@@ -318,7 +395,21 @@ mod tests {
         let input = "Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.
 Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsidian robot costs 3 ore and 8 clay. Each geode robot costs 3 ore and 12 obsidian.";
 
-        let blueprints: Vec<Blueprint> = input.lines().map(|l| l.parse().unwrap()).collect();
-        assert_eq!(33, sum_quality_levels(24, &blueprints));
+        let blueprints: Vec<Blueprint> = parse_lines(input).unwrap();
+        assert_eq!(
+            33,
+            sum_quality_levels(24, &blueprints, &Cancellation::new())
+        );
+        let cancelled = Cancellation::new();
+        cancelled.cancel();
+        assert_eq!(0, sum_quality_levels(24, &blueprints, &cancelled));
+
+        let error = parse_lines::<Blueprint>(&input.replace("12 obsidian", "12 obsidain"))
+            .err()
+            .unwrap();
+        assert_eq!(
+            (2, 151, "obsidain"),
+            (error.line, error.column, error.text.as_str())
+        );
     }
 }
@@ -1,22 +1,78 @@
 use std::fmt::{Display, Formatter};
 
 use log::debug;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input20.txt").unwrap();
-    let mut file = File::new(input.lines().map(|l| l.parse().unwrap()).collect());
-    file.mix(1);
-    debug!("after mix: {}", file);
-    println!("grove coordinate: {}", file.grove_coordinate());
+use crate::params::{Parameter, Params};
+use crate::parse_error::{parse_number, ParseError};
+use crate::progress::Progress;
+use crate::solution::Solution;
 
-    let mut file = File::new(input.lines().map(|l| l.parse().unwrap()).collect());
-    file.use_decrypt_key();
-    file.mix(10);
-    debug!("after mix: {}", file);
-    println!(
-        "grove coordinate after decrypt: {}",
+const DAY: u8 = 20;
+
+pub struct GrovePositioningSystem;
+
+impl Solution for GrovePositioningSystem {
+    type Input = Vec<i64>;
+    type Answer1 = i64;
+    type Answer2 = i64;
+    const PARAMETERS: &'static [Parameter] = &[Parameter::integer(
+        "key",
+        811_589_153,
+        "decryption key part 2 multiplies every number by",
+    )
+    .range(-100_000_000_000_000, 100_000_000_000_000)];
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(parse_values(input)?)
+    }
+    fn part1(values: &Self::Input) -> i64 {
+        let mut file = File::new(values.clone());
+        file.mix(1);
+        debug!("after mix: {}", file);
+        file.grove_coordinate()
+    }
+    fn part2(values: &Self::Input) -> i64 {
+        Self::part2_with(values, &Params::defaults(Self::PARAMETERS))
+    }
+    fn part2_with(values: &Self::Input, params: &Params) -> i64 {
+        let mut file = File::new(values.clone());
+        file.use_decrypt_key(params.integer("key"));
+        file.mix(10);
+        debug!("after mix: {}", file);
         file.grove_coordinate()
-    );
+    }
+    /// `size` numbers, exactly one of which is 0.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        let mut values: Vec<i64> = (1..size.max(3))
+            .map(|_| rng.gen_range(1..=10_000) * if rng.gen_bool(0.5) { 1 } else { -1 })
+            .collect();
+        values.push(0);
+        values.shuffle(rng);
+        values
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn parse_values(input: &str) -> Result<Vec<i64>, ParseError> {
+    let values = input
+        .lines()
+        .map(|line| parse_number(DAY, input, line))
+        .collect::<Result<Vec<_>, _>>()?;
+    if !values.contains(&0) {
+        return Err(ParseError::new(
+            DAY,
+            input,
+            &input[input.len()..],
+            "the file has no 0",
+        ));
+    }
+    Ok(values)
 }
 
 struct File {
@@ -31,16 +87,15 @@ impl Display for File {
 }
 
 impl File {
-    pub fn use_decrypt_key(&mut self) {
-        const KEY: i64 = 811589153;
-        self.values.iter_mut().for_each(|v| *v *= KEY);
+    pub fn use_decrypt_key(&mut self, key: i64) {
+        self.values.iter_mut().for_each(|v| *v *= key);
     }
     pub fn grove_coordinate(&self) -> i64 {
         let zero = self.get_index(0);
         let n1 = self.nth(zero + 1000);
         let n2 = self.nth(zero + 2000);
         let n3 = self.nth(zero + 3000);
-        println!("coordinates: {}, {}, {}", n1, n2, n3);
+        debug!("coordinates: {}, {}, {}", n1, n2, n3);
         n1 + n2 + n3
     }
     pub fn new(values: Vec<i64>) -> Self {
@@ -51,8 +106,12 @@ impl File {
     }
     pub fn mix(&mut self, times: usize) {
         debug!("Initial arrangement:\n{}", self);
-        for _ in 0..times {
+        let count = self.current.len() as u64;
+        let progress = Progress::new("day 20 mixing").with_total(times as u64 * count);
+        for round in 0..times as u64 {
             for i in 0..self.current.len() {
+                progress.explored(1);
+                progress.set_done(round * count + i as u64 + 1);
                 let current = self.current.iter().position(|&p| p == i).unwrap();
                 self.current.remove(current);
                 self.current.insert(
@@ -85,15 +144,22 @@ mod tests {
     #[test]
     fn test1() {
         let input = "1\n2\n-3\n3\n-2\n0\n4";
-        let mut file = File::new(input.lines().map(|l| l.parse().unwrap()).collect());
+        let mut file = File::new(parse_values(input).unwrap());
         file.mix(1);
         println!("after mix: {}", file);
         assert_eq!(3, file.grove_coordinate());
 
-        let mut file = File::new(input.lines().map(|l| l.parse().unwrap()).collect());
-        file.use_decrypt_key();
+        let mut file = File::new(parse_values(input).unwrap());
+        file.use_decrypt_key(811589153);
         file.mix(10);
         println!("after mix: {}", file);
         assert_eq!(1623178306, file.grove_coordinate());
+
+        let error = parse_values("1\n2\n-3\n3-\n0").unwrap_err();
+        assert_eq!(
+            (4, 1, "3-"),
+            (error.line, error.column, error.text.as_str())
+        );
+        assert!(parse_values("1\n2").is_err());
     }
 }
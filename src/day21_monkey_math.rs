@@ -1,12 +1,24 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Formatter};
 use std::str::FromStr;
 use std::sync::Mutex;
+use std::time::Duration;
 
-use anyhow::{bail, ensure, Context};
+use anyhow::{bail, Context};
 use lazy_static::lazy_static;
+use log::debug;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
 use string_interner::{DefaultSymbol, StringInterner};
 
+use crate::cancel::Cancellation;
+use crate::parse_error::{parse_lines, parse_number, ParseError};
+use crate::repl::{self, Explore};
+use crate::solution::{Answer, Solution};
+
+const DAY: u8 = 21;
+
 lazy_static! {
     static ref INTERNER: Mutex<StringInterner> = Mutex::new(StringInterner::new());
 }
@@ -15,18 +27,169 @@ fn get_name(s: DefaultSymbol) -> String {
     INTERNER.lock().unwrap().resolve(s).unwrap().to_string()
 }
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input21.txt").unwrap();
-    let mut monkeys = MonkeyGroup::new(input.lines().map(|l| l.parse().unwrap()));
-    let root = monkeys.run_until_goal();
-    println!("root yells: {}", root.value);
-    let path = monkeys.get_path("humn", "root");
-    println!("path from humn to root: {:?}", path);
-    println!("need to yell: {}", monkeys.solve());
+pub struct MonkeyMath;
+
+impl Solution for MonkeyMath {
+    type Input = MonkeyGroup;
+    type Answer1 = i64;
+    type Answer2 = i64;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(parse_monkeys(input)?)
+    }
+    fn part1(monkeys: &Self::Input) -> i64 {
+        monkeys.clone().run_until_goal().value
+    }
+    fn part2(monkeys: &Self::Input) -> i64 {
+        let mut monkeys = monkeys.clone();
+        monkeys.run_until_goal();
+        debug!(
+            "path from humn to root: {:?}",
+            monkeys.get_path("humn", "root")
+        );
+        monkeys.solve()
+    }
+    fn explore(monkeys: &Self::Input) -> Option<Box<dyn Explore + '_>> {
+        let mut monkeys = monkeys.clone();
+        monkeys.run_until_goal();
+        Some(Box::new(MonkeysExplorer(monkeys)))
+    }
+    /// A tree of monkeys with about `size` / 3 operations between `humn` and `root`, and a
+    /// part 2 answer between 0 and 1000. The path from `humn` only adds, subtracts and
+    /// multiplies, so that a single number makes both sides of `root` equal.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        let mut names = HashSet::from(["root".to_string(), "humn".to_string()]);
+        let mut lines = vec![];
+        let (part1, part2): (i64, i64) = (rng.gen_range(1..=1000), rng.gen_range(0..=1000));
+        lines.push(format!("humn: {}", part1));
+        let mut path = "humn".to_string();
+        // what the current monkey on the path yells in part 1 and in part 2
+        let mut values = (part1, part2);
+        for _ in 0..(size / 3).max(1) {
+            let constant = rng.gen_range(1..=20);
+            let other = yelling(constant, rng.gen_range(0..=2), rng, &mut names, &mut lines);
+            let small = values.0.abs().max(values.1.abs()) < 100_000_000_000;
+            let (operator, path_left) = match rng.gen_range(0..4) {
+                0 if small => {
+                    values = (values.0 * constant, values.1 * constant);
+                    ('*', rng.gen_bool(0.5))
+                }
+                1 => {
+                    values = (values.0 - constant, values.1 - constant);
+                    ('-', true)
+                }
+                2 => {
+                    values = (constant - values.0, constant - values.1);
+                    ('-', false)
+                }
+                _ => {
+                    values = (values.0 + constant, values.1 + constant);
+                    ('+', rng.gen_bool(0.5))
+                }
+            };
+            let name = new_name(rng, &mut names);
+            match path_left {
+                true => lines.push(format!("{}: {} {} {}", name, path, operator, other)),
+                false => lines.push(format!("{}: {} {} {}", name, other, operator, path)),
+            }
+            path = name;
+        }
+        let other = yelling(values.1, 3, rng, &mut names, &mut lines);
+        lines.push(format!("root: {} + {}", path, other));
+        lines.shuffle(rng);
+        lines.join("\n")
+    }
+}
+
+fn new_name(rng: &mut StdRng, names: &mut HashSet<String>) -> String {
+    loop {
+        let name: String = (0..4).map(|_| rng.gen_range('a'..='z')).collect();
+        if names.insert(name.clone()) {
+            return name;
+        }
+    }
+}
+
+/// Adds a tree of monkeys about `depth` operations deep that yells `value`, where no monkey
+/// yells a negative number of its own, and returns the name of its top monkey.
+fn yelling(
+    value: i64,
+    depth: u32,
+    rng: &mut StdRng,
+    names: &mut HashSet<String>,
+    lines: &mut Vec<String>,
+) -> String {
+    let name = new_name(rng, names);
+    let job = match depth {
+        0 if value >= 0 => value.to_string(),
+        _ => {
+            let k = rng.gen_range(2..=5);
+            let (a, operator, b) = match rng.gen_range(0..4) {
+                _ if value < 0 => {
+                    let a = rng.gen_range(0..=20);
+                    (a, '-', a - value)
+                }
+                0 => {
+                    let a = rng.gen_range(0..=value);
+                    (a, '+', value - a)
+                }
+                1 => {
+                    let b = rng.gen_range(1..=20);
+                    (value + b, '-', b)
+                }
+                2 if value % k == 0 => (value / k, '*', k),
+                _ => (value * k, '/', k),
+            };
+            let depth = depth.saturating_sub(1);
+            let a = yelling(a, depth, rng, names, lines);
+            let b = yelling(b, depth, rng, names, lines);
+            format!("{} {} {}", a, operator, b)
+        }
+    };
+    lines.push(format!("{}: {}", name, job));
+    name
+}
+
+fn parse_monkeys(input: &str) -> Result<MonkeyGroup, ParseError> {
+    let monkeys: Vec<Monkey> = parse_lines(input)?;
+    let names: Vec<DefaultSymbol> = monkeys.iter().map(|m| m.name).collect();
+    let interner = INTERNER.lock().unwrap();
+    let known = |name: &str| interner.get(name).is_some_and(|n| names.contains(&n));
+    for line in input.lines() {
+        let (_, operation) = line.split_once(": ").unwrap_or_default();
+        if let Some(unknown) = operation
+            .split(' ')
+            .step_by(2)
+            .find(|&name| name.parse::<i64>().is_err() && !known(name))
+        {
+            return Err(ParseError::new(DAY, input, unknown, "no such monkey"));
+        }
+    }
+    if !known("root") {
+        return Err(ParseError::new(
+            DAY,
+            input,
+            &input[input.len()..],
+            "missing root monkey",
+        ));
+    }
+    drop(interner);
+    Ok(MonkeyGroup::new(monkeys.into_iter()))
+}
+
+/// The number `humn` yells in part 2, solving backwards from `root` and trying every number, for
+/// differential testing; an error if trying every number takes over a minute.
+pub(crate) fn humn_both_ways(input: &str) -> anyhow::Result<(Answer, Answer)> {
+    let mut monkeys = parse_monkeys(input)?;
+    let brute_force = monkeys
+        .solve_brute_force(&Cancellation::with_timeout(Duration::from_secs(60)))
+        .ok_or_else(|| anyhow::anyhow!("timed out trying every number"))?;
+    monkeys.run_until_goal();
+    Ok((monkeys.solve().into(), brute_force.into()))
 }
 
 #[derive(Clone)]
-struct MonkeyGroup {
+pub struct MonkeyGroup {
     monkeys: HashMap<DefaultSymbol, Monkey>,
     dependents: HashMap<DefaultSymbol, Vec<DefaultSymbol>>,
 }
@@ -54,15 +217,24 @@ impl MonkeyGroup {
                 next[1].make_result_by_changing(next[0].name, target, &self.monkeys)
             })
     }
+    /// Panics if [`MonkeyGroup::try_get_path`] finds no path.
     pub fn get_path(&self, from: &str, to: &str) -> Vec<Monkey> {
-        let mut result = vec![];
-        let mut current = self.monkeys.get(&Self::get_monkey(from)).unwrap();
-        let end = self.monkeys.get(&Self::get_monkey(to)).unwrap();
-        result.push(current.clone());
-        while current as *const _ != end as *const _ {
-            let next = self.dependents.get(&current.name).unwrap();
+        self.try_get_path(from, to)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+    /// The monkeys from `from` to `to`, each one needing what the one before yells, as long as
+    /// every monkey on the way is needed by a single other one.
+    pub fn try_get_path(&self, from: &str, to: &str) -> anyhow::Result<Vec<Monkey>> {
+        let mut current = self.find(from)?;
+        let end = self.find(to)?;
+        let mut result = vec![current.clone()];
+        while current.name != end.name {
+            let next = self
+                .dependents
+                .get(&current.name)
+                .map_or(&[][..], |d| d.as_slice());
             if next.len() != 1 {
-                panic!(
+                bail!(
                     "there are {} options after {}",
                     next.len(),
                     get_name(current.name)
@@ -71,7 +243,13 @@ impl MonkeyGroup {
             current = self.monkeys.get(&next[0]).unwrap();
             result.push(current.clone());
         }
-        result
+        Ok(result)
+    }
+    fn find(&self, name: &str) -> anyhow::Result<&Monkey> {
+        let symbol = INTERNER.lock().unwrap().get(name);
+        symbol
+            .and_then(|s| self.monkeys.get(&s))
+            .with_context(|| format!("no monkey named {}", name))
     }
     pub fn new(monkeys: impl Iterator<Item = Monkey>) -> Self {
         let monkeys: HashMap<DefaultSymbol, Monkey> = monkeys.map(|m| (m.name, m)).collect();
@@ -91,19 +269,23 @@ impl MonkeyGroup {
             dependents,
         }
     }
-    #[allow(unused)]
-    pub fn solve_brute_force(&self) -> i64 {
+    /// Tries every number from 0 up until `cancellation` is cancelled, which is the only way it
+    /// stops if the answer is negative.
+    pub fn solve_brute_force(&self, cancellation: &Cancellation) -> Option<i64> {
         let goal = Self::get_monkey("root");
         let start = Self::get_monkey("humn");
         for i in 0.. {
+            if cancellation.is_cancelled() {
+                break;
+            }
             let mut monkeys = self.clone();
             monkeys.monkeys.get_mut(&start).unwrap().set_value(i);
             let result = monkeys.run_until(goal);
             if result.a == result.b {
-                return i;
+                return Some(i);
             }
         }
-        panic!("no solution");
+        None
     }
     fn get_monkey(name: &str) -> DefaultSymbol {
         INTERNER
@@ -152,13 +334,13 @@ impl MonkeyGroup {
 }
 
 #[derive(Clone)]
-struct Monkey {
+pub struct Monkey {
     name: DefaultSymbol,
     operation: MonkeyOperation,
     value: Option<i64>,
 }
 
-struct MonkeyResult {
+pub struct MonkeyResult {
     value: i64,
     a: i64,
     b: i64,
@@ -261,8 +443,50 @@ impl Debug for Monkey {
     }
 }
 
+/// The monkeys once they have all yelled, so that every value is known.
+struct MonkeysExplorer(MonkeyGroup);
+
+impl MonkeysExplorer {
+    fn describe(monkey: &Monkey) -> String {
+        match monkey.value {
+            Some(value) => format!("{:?} = {}", monkey, value),
+            None => format!("{:?}", monkey),
+        }
+    }
+}
+
+impl Explore for MonkeysExplorer {
+    fn commands(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            (
+                "path <from> <to>",
+                "show the monkeys from one to another that needs it",
+            ),
+            ("monkey <name>", "show what a monkey yells"),
+        ]
+    }
+    fn run(&mut self, command: &str, args: &[&str]) -> Option<anyhow::Result<String>> {
+        let path = || -> anyhow::Result<String> {
+            let from: String = repl::argument(args, 0, "from")?;
+            let to: String = repl::argument(args, 1, "to")?;
+            let path = self.0.try_get_path(&from, &to)?;
+            Ok(path
+                .iter()
+                .map(Self::describe)
+                .collect::<Vec<_>>()
+                .join("\n"))
+        };
+        Some(match command {
+            "path" => path(),
+            "monkey" => repl::argument(args, 0, "name")
+                .and_then(|name: String| self.0.find(&name).map(Self::describe)),
+            _ => return None,
+        })
+    }
+}
+
 #[derive(Clone)]
-enum MonkeyOperation {
+pub enum MonkeyOperation {
     Value(i64),
     Sum(DefaultSymbol, DefaultSymbol),
     Difference(DefaultSymbol, DefaultSymbol),
@@ -271,33 +495,28 @@ enum MonkeyOperation {
 }
 
 impl FromStr for Monkey {
-    type Err = anyhow::Error;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, operation) = s
+            .split_once(": ")
+            .ok_or_else(|| ParseError::new(DAY, s, s, "expected '<name>: <job>'"))?;
+        let parts: Vec<_> = operation.split(' ').collect();
         let mut interner = INTERNER.lock().unwrap();
-        let mut parts = s.split(": ");
-        let name = interner.get_or_intern(parts.next().context("no name")?);
-        let parts: Vec<_> = parts
-            .next()
-            .context("no operation")?
-            .trim()
-            .split_whitespace()
-            .collect();
-        let operation = if parts.len() == 1 {
-            MonkeyOperation::Value(parts[0].parse()?)
-        } else {
-            ensure!(parts.len() == 3);
-            let values = (
-                interner.get_or_intern(parts[0]),
-                interner.get_or_intern(parts[2]),
-            );
-            match parts[1] {
-                "+" => MonkeyOperation::Sum(values.0, values.1),
-                "-" => MonkeyOperation::Difference(values.0, values.1),
-                "*" => MonkeyOperation::Product(values.0, values.1),
-                "/" => MonkeyOperation::Division(values.0, values.1),
-                other => bail!("invalid operation '{}'", other),
+        let name = interner.get_or_intern(name);
+        let operation = match parts[..] {
+            [value] => MonkeyOperation::Value(parse_number(DAY, s, value)?),
+            [a, operator, b] => {
+                let values = (interner.get_or_intern(a), interner.get_or_intern(b));
+                match operator {
+                    "+" => MonkeyOperation::Sum(values.0, values.1),
+                    "-" => MonkeyOperation::Difference(values.0, values.1),
+                    "*" => MonkeyOperation::Product(values.0, values.1),
+                    "/" => MonkeyOperation::Division(values.0, values.1),
+                    other => return Err(ParseError::new(DAY, s, other, "invalid operation")),
+                }
             }
+            _ => return Err(ParseError::new(DAY, s, operation, "invalid job")),
         };
         Ok(Self::new(name, operation))
     }
@@ -324,9 +543,49 @@ pppw: cczh / lfqf
 lgvd: ljgn * ptdq
 drzm: hmdt - zczc
 hmdt: 32";
-        let mut monkeys = MonkeyGroup::new(input.lines().map(|l| l.parse().unwrap()));
+        let mut monkeys = parse_monkeys(input).unwrap();
         let root = monkeys.run_until_goal();
         assert_eq!(152, root.value);
         assert_eq!(301, monkeys.solve());
+        let path: Vec<String> = monkeys
+            .try_get_path("humn", "pppw")
+            .unwrap()
+            .iter()
+            .map(|m| format!("{:?}", m))
+            .collect();
+        assert_eq!(
+            vec![
+                "humn: 5",
+                "ptdq: humn - dvpt",
+                "lgvd: ljgn * ptdq",
+                "cczh: sllz + lgvd",
+                "pppw: cczh / lfqf"
+            ],
+            path
+        );
+        assert!(monkeys.try_get_path("humn", "nobody").is_err());
+        // no monkey needs root
+        assert!(monkeys.try_get_path("root", "humn").is_err());
+        let monkeys = parse_monkeys(input).unwrap();
+        assert_eq!(Some(301), monkeys.solve_brute_force(&Cancellation::new()));
+        // humn yells -9
+        let negative = parse_monkeys(&input.replace("hmdt: 32", "hmdt: 1")).unwrap();
+        let timeout = Cancellation::with_timeout(Duration::from_millis(100));
+        assert_eq!(None, negative.solve_brute_force(&timeout));
+
+        let error = parse_monkeys(&input.replace("drzm * dbpl", "drzm % dbpl"))
+            .err()
+            .unwrap();
+        assert_eq!(
+            (10, 12, "%"),
+            (error.line, error.column, error.text.as_str())
+        );
+        let error = parse_monkeys(&input.replace("lgvd: ljgn", "lgvd: ljgx"))
+            .err()
+            .unwrap();
+        assert_eq!(
+            (13, 7, "ljgx"),
+            (error.line, error.column, error.text.as_str())
+        );
     }
 }
@@ -1,22 +1,99 @@
-use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::fmt::{Debug, Display, Formatter};
+use std::fmt::{Debug, Formatter};
 use std::str::FromStr;
 
-use anyhow::{bail, Context};
+use anyhow::Context;
 use itertools::Itertools;
 use log::debug;
+use rand::rngs::StdRng;
+use rand::Rng;
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input22.txt").unwrap();
-    let map: Map = input.parse().unwrap();
-    debug!("map:\n{:?}", map);
-    println!("password: {}", get_password(&map));
-    let map = use_custom_edges(map);
-    println!("as a cube - password: {}", get_password(&map));
+use crate::grid::{Coord, Grid2D};
+use crate::parse_error::{invalid_char, parse_number, ParseError};
+use crate::solution::Solution;
+use crate::visualize::{Frame, Visualize};
+
+const DAY: u8 = 22;
+
+pub struct MonkeyMap;
+
+impl Solution for MonkeyMap {
+    type Input = Map;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        let map: Map = input.parse()?;
+        debug!("map:\n{:?}", map);
+        Ok(map)
+    }
+    fn part1(map: &Self::Input) -> u64 {
+        get_password(map)
+    }
+    fn part2(map: &Self::Input) -> u64 {
+        get_password(&use_custom_edges(map))
+    }
+    /// Follows the instructions one at a time, leaving a trail of facings behind.
+    fn visualize(map: &Self::Input, part: u8) -> Option<Box<dyn Visualize + '_>> {
+        let map = match part {
+            1 => map.clone(),
+            _ => use_custom_edges(map),
+        };
+        let mut position = map.get_initial_position();
+        let mut path = Path::default();
+        path.add(&position);
+        let mut frame = 0;
+        Some(Box::new(std::iter::from_fn(move || {
+            if frame > 0 {
+                position = position.next(map.instructions.get(frame - 1)?, &map);
+                path.add(&position);
+            }
+            frame += 1;
+            Some(Frame::new(
+                format!(
+                    "instruction {} of {}, password {}",
+                    frame - 1,
+                    map.instructions.len(),
+                    position.get_password()
+                ),
+                path.render(&map),
+            ))
+        })))
+    }
+    /// A board folding into the same cube as the puzzle's, which part 2's edges are written
+    /// for, and a path of `size` moves.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        // the columns of open tiles and walls in each band of 50 rows
+        let bands = [(50, 150), (50, 100), (0, 100), (0, 50)];
+        let mut lines = vec![];
+        for (from, to) in bands {
+            for _ in 0..50 {
+                let tiles: String = (from..to)
+                    .map(|x| match lines.is_empty() && x == from {
+                        true => '.',
+                        false if rng.gen_bool(0.05) => '#',
+                        false => '.',
+                    })
+                    .collect();
+                lines.push(" ".repeat(from) + &tiles);
+            }
+        }
+        let moves: Vec<String> = (0..size.max(1))
+            .map(|_| rng.gen_range(1..=50).to_string())
+            .collect();
+        let turns: Vec<char> = (1..moves.len())
+            .map(|_| if rng.gen_bool(0.5) { 'L' } else { 'R' })
+            .collect();
+        let mut path = moves[0].clone();
+        for (turn, distance) in turns.into_iter().zip(&moves[1..]) {
+            path.push(turn);
+            path.push_str(distance);
+        }
+        lines.join("\n") + "\n\n" + &path
+    }
 }
 
-fn use_custom_edges(map: Map) -> Map {
+fn use_custom_edges(map: &Map) -> Map {
     map.with_edges(
         [
             EdgeDescription::new(
@@ -82,86 +159,54 @@ fn use_custom_edges(map: Map) -> Map {
 
 fn get_password(map: &Map) -> u64 {
     let mut position = map.get_initial_position();
-    let mut path = Path::new(map);
+    let mut path = Path::default();
     path.add(&position);
-    debug!("start:\n{}", path);
+    debug!("start:\n{}", path.render(map));
     for instruction in map.instructions.iter() {
         position = position.next(instruction, map);
         path.add(&position);
-        debug!("{:?}:\n{}", instruction, path);
+        debug!("{:?}:\n{}", instruction, path.render(map));
     }
-    println!("{}", path);
-    println!("final position: {:?}", position);
+    debug!("\n{}", path.render(map));
+    debug!("final position: {:?}", position);
     position.get_password()
 }
 
-struct Path<'a> {
+#[derive(Default)]
+struct Path {
     positions: HashMap<Coord, Position>,
-    map: &'a Map,
 }
 
-impl<'a> Path<'a> {
-    pub fn new(map: &'a Map) -> Self {
-        Self {
-            positions: Default::default(),
-            map,
-        }
-    }
+impl Path {
     pub fn add(&mut self, position: &Position) {
-        self.positions
-            .insert(position.position.clone(), position.clone());
+        self.positions.insert(position.position, position.clone());
     }
-}
-
-impl<'a> Display for Path<'a> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let max_y = self
-            .map
-            .edge_connections
-            .values()
-            .map(|(_, c)| c.y)
-            .max()
-            .unwrap();
-        let max_x = self
-            .map
-            .edge_connections
-            .values()
-            .map(|(_, c)| c.x)
-            .max()
-            .unwrap();
-        write!(
-            f,
-            "{}",
-            (1..=max_y)
-                .map(|y| {
-                    (1..=max_x)
-                        .map(|x| {
-                            let c = Coord::new(x, y);
-                            if let Some(p) = self.positions.get(&c) {
-                                char::from(p.direction)
-                            } else if let Some(s) = self.map.board.get(&c) {
-                                char::from(*s)
-                            } else {
-                                ' '
-                            }
-                        })
-                        .collect::<String>()
-                })
-                .collect::<Vec<String>>()
-                .join("\n")
-        )
+    /// The board with the last facing at every visited tile.
+    pub fn render(&self, map: &Map) -> String {
+        map.board.render(|c, space| {
+            if let Some(p) = self.positions.get(&c) {
+                char::from(p.direction)
+            } else if let Some(s) = space {
+                char::from(*s)
+            } else {
+                ' '
+            }
+        })
     }
 }
 
 #[derive(Clone, Debug)]
-struct Position {
+pub struct Position {
     position: Coord,
     direction: Direction,
 }
 
 impl Position {
+    /// Rows and columns in the password are 1-based.
     pub fn get_password(&self) -> u64 {
-        1000 * self.position.y + 4 * self.position.x + (self.direction as u64)
+        1000 * (self.position.y as u64 + 1)
+            + 4 * (self.position.x as u64 + 1)
+            + (self.direction as u64)
     }
     pub fn next(&self, instruction: &Instruction, map: &Map) -> Self {
         let mut result = self.clone();
@@ -186,33 +231,30 @@ impl Position {
         result
     }
     fn try_move_forward(&mut self, map: &Map) -> bool {
-        let next = map
-            .board
-            .get_key_value(&(self.position.move_towards(self.direction)))
-            .map(|c| (self.direction, c))
-            .unwrap_or_else(|| {
+        let ahead = self.position + self.direction.offset();
+        let (direction, position) = match map.board.get(ahead) {
+            Some(Some(_)) => (self.direction, ahead),
+            _ => {
                 // wrap around
-                let (new_direction, key) = map
-                    .edge_connections
+                *map.edge_connections
                     .get(&(
                         self.direction,
                         self.direction.perpendicular_component(&self.position),
                     ))
-                    .expect("current position is outside edges");
-                (
-                    *new_direction,
-                    map.board
-                        .get_key_value(key)
-                        .with_context(|| {
-                            format!("cannot find key ({:?}) in board for {:?}", key, self)
-                        })
-                        .unwrap(),
-                )
-            });
-        match next.1 .1 {
+                    .expect("current position is outside edges")
+            }
+        };
+        let space = map
+            .board
+            .get(position)
+            .copied()
+            .flatten()
+            .with_context(|| format!("cannot find {:?} in board for {:?}", position, self))
+            .unwrap();
+        match space {
             Space::Open => {
-                self.position = next.1 .0.clone();
-                self.direction = next.0;
+                self.position = position;
+                self.direction = direction;
                 true
             }
             Space::Wall => false,
@@ -220,11 +262,14 @@ impl Position {
     }
 }
 
-#[derive(Debug)]
-struct Map {
-    board: HashMap<Coord, Space>,
+#[derive(Clone, Debug)]
+pub struct Map {
+    /// `None` for the tiles off the board.
+    board: Grid2D<Option<Space>>,
     instructions: Vec<Instruction>,
-    edge_connections: HashMap<(Direction, u64), (Direction, Coord)>,
+    /// Keyed by the direction of travel and the row or column being left; coordinates are 0-based,
+    /// unlike the 1-based ones in [`EdgeDescription`].
+    edge_connections: HashMap<(Direction, i64), (Direction, Coord)>,
 }
 
 struct EdgeDescription {
@@ -304,9 +349,13 @@ impl Map {
             edge_connections: new_edges
                 .flat_map(|edge| {
                     edge.input_range_inclusive
-                        .into_iter()
-                        .zip_eq(edge.output_range_inclusive.into_iter())
+                        .zip_eq(edge.output_range_inclusive)
+                        .map(|(input, output)| (input as i64 - 1, output as i64 - 1))
                         .flat_map(move |(input, output)| {
+                            let (input_other, output_other) = (
+                                edge.input_other_coordinate as i64 - 1,
+                                edge.output_other_coordinate as i64 - 1,
+                            );
                             [
                                 (
                                     (edge.side, input),
@@ -314,10 +363,10 @@ impl Map {
                                         edge.output_direction,
                                         match edge.output_direction {
                                             Direction::Right | Direction::Left => {
-                                                Coord::new(edge.output_other_coordinate, output)
+                                                Coord::new(output_other, output)
                                             }
                                             Direction::Up | Direction::Down => {
-                                                Coord::new(output, edge.output_other_coordinate)
+                                                Coord::new(output, output_other)
                                             }
                                         },
                                     ),
@@ -328,10 +377,10 @@ impl Map {
                                         edge.side.opposite(),
                                         match edge.side {
                                             Direction::Right | Direction::Left => {
-                                                Coord::new(edge.input_other_coordinate, input)
+                                                Coord::new(input_other, input)
                                             }
                                             Direction::Up | Direction::Down => {
-                                                Coord::new(input, edge.input_other_coordinate)
+                                                Coord::new(input, input_other)
                                             }
                                         },
                                     ),
@@ -342,80 +391,47 @@ impl Map {
                 .collect(),
         }
     }
-    fn make_edges(board: &HashMap<Coord, Space>) -> HashMap<(Direction, u64), (Direction, Coord)> {
-        let mut keys_x: Vec<_> = board.keys().cloned().collect();
-        keys_x.sort_by_key(|c| c.x);
-        let mut keys_y = keys_x.clone();
-        keys_y.sort_by_key(|c| c.y);
-        [
-            keys_y
-                .iter()
-                .group_by(|c| c.y)
-                .into_iter()
-                .map(|(key, group)| {
-                    (
-                        (Direction::Left, key),
-                        (Direction::Left, group.max_by_key(|c| c.x).cloned().unwrap()),
-                    )
-                })
-                .collect::<Vec<_>>(),
-            keys_y
-                .iter()
-                .group_by(|c| c.y)
-                .into_iter()
-                .map(|(key, group)| {
-                    (
-                        (Direction::Right, key),
-                        (
-                            Direction::Right,
-                            group.min_by_key(|c| c.x).cloned().unwrap(),
-                        ),
-                    )
-                })
-                .collect::<Vec<_>>(),
-            keys_x
-                .iter()
-                .group_by(|c| c.x)
-                .into_iter()
-                .map(|(key, group)| {
-                    (
-                        (Direction::Down, key),
-                        (Direction::Down, group.min_by_key(|c| c.y).cloned().unwrap()),
-                    )
-                })
-                .collect::<Vec<_>>(),
-            keys_x
-                .iter()
-                .group_by(|c| c.x)
-                .into_iter()
-                .map(|(key, group)| {
-                    (
-                        (Direction::Up, key),
-                        (Direction::Up, group.max_by_key(|c| c.y).cloned().unwrap()),
-                    )
-                })
-                .collect::<Vec<_>>(),
-        ]
-        .into_iter()
-        .flatten()
-        .collect()
+    /// Connects each edge of the board to the opposite edge of the same row or column.
+    fn make_edges(board: &Grid2D<Option<Space>>) -> HashMap<(Direction, i64), (Direction, Coord)> {
+        fn ends<'a>(
+            line: impl DoubleEndedIterator<Item = &'a Option<Space>>,
+        ) -> Option<(i64, i64)> {
+            let cells: Vec<_> = line.collect();
+            let first = cells.iter().position(|s| s.is_some())?;
+            let last = cells.iter().rposition(|s| s.is_some())?;
+            Some((first as i64, last as i64))
+        }
+        let rows = board.rows().enumerate().filter_map(|(y, row)| {
+            let y = y as i64;
+            let (first, last) = ends(row.iter())?;
+            Some([
+                ((Direction::Left, y), (Direction::Left, Coord::new(last, y))),
+                (
+                    (Direction::Right, y),
+                    (Direction::Right, Coord::new(first, y)),
+                ),
+            ])
+        });
+        let columns = board.columns().enumerate().filter_map(|(x, column)| {
+            let x = x as i64;
+            let (first, last) = ends(column)?;
+            Some([
+                (
+                    (Direction::Down, x),
+                    (Direction::Down, Coord::new(x, first)),
+                ),
+                ((Direction::Up, x), (Direction::Up, Coord::new(x, last))),
+            ])
+        });
+        rows.chain(columns).flatten().collect()
     }
     pub fn get_initial_position(&self) -> Position {
         Position {
             position: self
                 .board
                 .iter()
-                .filter(|(_, s)| s == &&Space::Open)
+                .find(|(_, s)| s == &&Some(Space::Open))
                 .map(|(c, _)| c)
-                .min_by(|a, b| {
-                    let cmp = a.y.cmp(&b.y);
-                    if cmp == Ordering::Equal {
-                        a.x.cmp(&b.x)
-                    } else {
-                        cmp
-                    }
-                })
-                .cloned()
                 .unwrap(),
             direction: Direction::Right,
         }
@@ -423,7 +439,7 @@ impl Map {
 }
 
 #[derive(PartialEq, Copy, Clone)]
-enum Space {
+pub(crate) enum Space {
     Open,
     Wall,
 }
@@ -434,6 +450,18 @@ impl Debug for Space {
     }
 }
 
+impl TryFrom<char> for Space {
+    type Error = ParseError;
+
+    fn try_from(value: char) -> Result<Self, ParseError> {
+        Ok(match value {
+            '.' => Space::Open,
+            '#' => Space::Wall,
+            other => return Err(invalid_char(DAY, other, "invalid space")),
+        })
+    }
+}
+
 impl From<Space> for char {
     fn from(value: Space) -> Self {
         match value {
@@ -443,30 +471,8 @@ impl From<Space> for char {
     }
 }
 
-#[derive(Eq, PartialEq, Clone, Hash, Debug)]
-struct Coord {
-    x: u64,
-    y: u64,
-}
-
-impl Coord {
-    pub fn new(x: u64, y: u64) -> Self {
-        Self { x, y }
-    }
-    pub fn move_towards(&self, direction: Direction) -> Self {
-        let mut result = self.clone();
-        match direction {
-            Direction::Right => result.x += 1,
-            Direction::Down => result.y += 1,
-            Direction::Left => result.x = result.x.saturating_sub(1),
-            Direction::Up => result.y = result.y.saturating_sub(1),
-        }
-        result
-    }
-}
-
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
-enum Direction {
+pub(crate) enum Direction {
     Right = 0,
     Down,
     Left,
@@ -484,6 +490,20 @@ impl From<Direction> for char {
     }
 }
 
+impl TryFrom<char> for Direction {
+    type Error = ParseError;
+
+    fn try_from(value: char) -> Result<Self, ParseError> {
+        Ok(match value {
+            '>' => Direction::Right,
+            'v' => Direction::Down,
+            '<' => Direction::Left,
+            '^' => Direction::Up,
+            other => return Err(invalid_char(DAY, other, "invalid direction")),
+        })
+    }
+}
+
 impl Direction {
     pub fn turn_right(self) -> Self {
         self.turn_left().opposite()
@@ -504,7 +524,15 @@ impl Direction {
             Direction::Up => Direction::Down,
         }
     }
-    pub fn perpendicular_component(self, coord: &Coord) -> u64 {
+    pub fn offset(self) -> Coord {
+        match self {
+            Direction::Right => Coord::RIGHT,
+            Direction::Down => Coord::DOWN,
+            Direction::Left => Coord::LEFT,
+            Direction::Up => Coord::UP,
+        }
+    }
+    pub fn perpendicular_component(self, coord: &Coord) -> i64 {
         match self {
             Direction::Right | Direction::Left => coord.y,
             Direction::Down | Direction::Up => coord.x,
@@ -513,63 +541,51 @@ impl Direction {
 }
 
 #[derive(Debug, Clone)]
-enum Instruction {
+pub enum Instruction {
     Move(u64),
     TurnLeft,
     TurnRight,
 }
 
 impl FromStr for Map {
-    type Err = anyhow::Error;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.lines();
-        let board: HashMap<Coord, Space> = lines
-            .by_ref()
-            .take_while(|l| !l.is_empty())
-            .enumerate()
-            .flat_map(|(row, line)| {
-                line.chars().enumerate().filter_map(move |(col, c)| {
-                    Some((
-                        Coord::new(col as u64 + 1, row as u64 + 1),
-                        match c {
-                            '.' => Space::Open,
-                            '#' => Space::Wall,
-                            ' ' => return None,
-                            other => panic!("invalid character '{}'", other),
-                        },
-                    ))
-                })
-            })
-            .collect();
-        fn parse_num(s: &[char]) -> anyhow::Result<Instruction> {
-            Ok(Instruction::Move(
-                s.iter().copied().collect::<String>().parse()?,
-            ))
+        let board_end = s.find("\n\n").unwrap_or(s.len());
+        let board = Grid2D::parse(DAY, &s[..board_end], "invalid character", |_, c| match c {
+            ' ' => Some(None),
+            c => Space::try_from(c).ok().map(Some),
+        })?;
+        if board.iter().all(|(_, s)| s.is_none()) {
+            return Err(ParseError::new(DAY, s, &s[..0], "empty board"));
         }
-        let (mut instructions, leftover) = lines
+
+        let path = s[board_end..]
+            .trim_start_matches('\n')
+            .lines()
             .next()
-            .context("cannot find instructions")?
-            .chars()
-            .fold(Ok((vec![], vec![])), |previous, c| {
-                let (mut result, mut leftover) = previous?;
-                if c.is_numeric() {
-                    leftover.push(c);
-                } else {
-                    if !leftover.is_empty() {
-                        result.push(parse_num(&leftover)?);
-                        leftover.clear();
-                    }
-                    result.push(match c {
-                        'L' => Instruction::TurnLeft,
-                        'R' => Instruction::TurnRight,
-                        other => bail!("invalid instruction: '{}'", other),
-                    });
+            .ok_or_else(|| ParseError::new(DAY, s, &s[s.len()..], "cannot find instructions"))?;
+        let mut instructions = vec![];
+        let mut number_start = None;
+        for (i, c) in path.char_indices() {
+            if c.is_ascii_digit() {
+                number_start.get_or_insert(i);
+                continue;
+            }
+            if let Some(start) = number_start.take() {
+                instructions.push(Instruction::Move(parse_number(DAY, s, &path[start..i])?));
+            }
+            instructions.push(match c {
+                'L' => Instruction::TurnLeft,
+                'R' => Instruction::TurnRight,
+                _ => {
+                    let text = &path[i..i + c.len_utf8()];
+                    return Err(ParseError::new(DAY, s, text, "invalid instruction"));
                 }
-                Ok((result, leftover))
-            })?;
-        if !leftover.is_empty() {
-            instructions.push(parse_num(&leftover)?);
+            });
+        }
+        if let Some(start) = number_start {
+            instructions.push(Instruction::Move(parse_number(DAY, s, &path[start..])?));
         }
 
         Ok(Self {
@@ -603,6 +619,22 @@ mod tests {
 
         let map: Map = input.parse().unwrap();
         assert_eq!(6032, get_password(&map));
+        for space in [Space::Open, Space::Wall] {
+            assert_eq!(Ok(space), Space::try_from(char::from(space)));
+        }
+        assert_eq!("invalid space", Space::try_from('v').unwrap_err().message);
+        for direction in [
+            Direction::Right,
+            Direction::Down,
+            Direction::Left,
+            Direction::Up,
+        ] {
+            assert_eq!(Ok(direction), Direction::try_from(char::from(direction)));
+        }
+        assert!(Direction::try_from('.').is_err());
+        let mut frames = MonkeyMap::visualize(&map, 1).unwrap();
+        let last = std::iter::from_fn(|| frames.next_frame()).last().unwrap();
+        assert_eq!("instruction 13 of 13, password 6032", last.caption);
 
         let map = map.with_edges(
             [
@@ -666,5 +698,16 @@ mod tests {
             .into_iter(),
         );
         assert_eq!(5031, get_password(&map));
+
+        let error = input.replace("R5L5R", "R5X5R").parse::<Map>().unwrap_err();
+        assert_eq!(
+            (14, 5, "X"),
+            (error.line, error.column, error.text.as_str())
+        );
+        let error = input
+            .replace("..#....#", "..#..o.#")
+            .parse::<Map>()
+            .unwrap_err();
+        assert_eq!((7, 6, "o"), (error.line, error.column, error.text.as_str()));
     }
 }
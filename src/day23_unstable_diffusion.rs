@@ -1,34 +1,148 @@
-use std::collections::{HashMap, HashSet};
-use std::fmt::{Debug, Display, Formatter};
-use std::ops::Add;
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
 use lazy_static::lazy_static;
 use log::{debug, info};
+use rand::rngs::StdRng;
+use rand::Rng;
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input23.txt").unwrap();
+use crate::grid::{Coord, Grid2D};
+use crate::parse_error::ParseError;
+use crate::progress::Progress;
+use crate::repl::{self, Explore};
+use crate::solution::Solution;
+use crate::visualize::{Frame, Visualize};
 
-    let mut ground: Ground = input.parse().unwrap();
-    ground.run(10);
-    println!(
-        "empty ground tiles after 10 rounds: {}",
-        ground.empty_spaces()
-    );
+const DAY: u8 = 23;
+
+pub struct UnstableDiffusion;
+
+impl Solution for UnstableDiffusion {
+    type Input = Ground;
+    type Answer1 = usize;
+    type Answer2 = u32;
 
-    let mut ground: Ground = input.parse().unwrap();
-    println!("elves stop after {} rounds", ground.run_to_end());
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(input.parse::<Ground>()?)
+    }
+    fn part1(ground: &Self::Input) -> usize {
+        let mut ground = ground.clone();
+        ground.run(10);
+        ground.empty_spaces()
+    }
+    fn part2(ground: &Self::Input) -> u32 {
+        ground.clone().run_to_end()
+    }
+    /// Part 1 stops after 10 rounds, part 2 once no elf moves.
+    fn visualize(ground: &Self::Input, part: u8) -> Option<Box<dyn Visualize + '_>> {
+        let mut ground = ground.clone();
+        let mut moved = true;
+        Some(Box::new((0..).map_while(move |round| {
+            if round > 0 {
+                if !moved || (part == 1 && round > 10) {
+                    return None;
+                }
+                moved = ground.next();
+            }
+            let caption = if moved {
+                format!("round {}", round)
+            } else {
+                format!("round {}: no elf moved", round)
+            };
+            // without the offsets on the first line, so that exported images only show the grid
+            let picture = ground.to_string();
+            Some(Frame::new(caption, picture.split_once('\n').unwrap().1))
+        })))
+    }
+    fn explore(ground: &Self::Input) -> Option<Box<dyn Explore + '_>> {
+        Some(Box::new(GroundExplorer {
+            ground: ground.clone(),
+            rounds: 0,
+            settled: false,
+        }))
+    }
+    /// `size` by `size` tiles, about 40% of which hold an elf, and always the top left one.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        (0..size)
+            .map(|y| {
+                (0..size)
+                    .map(|x| match x + y == 0 || rng.gen_bool(0.4) {
+                        true => '#',
+                        false => '.',
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
-struct Ground {
-    elves: HashSet<Coord>,
+#[derive(Clone)]
+pub struct Ground {
+    elves: Grid2D<bool>,
     step: usize,
 }
 
+/// Runs the rounds of a copy of the parsed ground as the REPL asks for them.
+struct GroundExplorer {
+    ground: Ground,
+    rounds: u32,
+    /// No elf moved in the last round, so none ever will again.
+    settled: bool,
+}
+
+impl GroundExplorer {
+    fn show(&self) -> String {
+        let ground = self.ground.to_string();
+        let ground = ground.trim_end();
+        if self.settled {
+            format!("round {}: no elf moved\n{}", self.rounds, ground)
+        } else {
+            format!("round {}\n{}", self.rounds, ground)
+        }
+    }
+}
+
+impl Explore for GroundExplorer {
+    fn commands(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            (
+                "step [rounds]",
+                "run rounds (default 1) and show the ground",
+            ),
+            ("show", "show the ground"),
+            ("empty", "count the empty tiles around the elves"),
+        ]
+    }
+    fn run(&mut self, command: &str, args: &[&str]) -> Option<anyhow::Result<String>> {
+        Some(match command {
+            "step" => repl::optional_argument(args, 0, "rounds", 1).map(|rounds: u32| {
+                for _ in 0..rounds {
+                    if self.settled {
+                        break;
+                    }
+                    self.rounds += 1;
+                    self.settled = !self.ground.next();
+                }
+                self.show()
+            }),
+            "show" => Ok(self.show()),
+            "empty" => Ok(self.ground.empty_spaces().to_string()),
+            _ => return None,
+        })
+    }
+}
+
 impl Ground {
+    /// Elves move at most one tile per round, so the grid is grown by this much at a time.
+    const MARGIN: usize = 10;
+
     pub fn run(&mut self, max_steps: u32) {
         info!("start:\n{}", self);
+        let progress = Progress::new("day 23 rounds").with_total(max_steps as u64);
         for i in 0..max_steps {
+            progress.explored(1);
+            progress.set_done(i as u64 + 1);
             if !self.next() {
                 break;
             }
@@ -38,7 +152,9 @@ impl Ground {
     }
     pub fn run_to_end(&mut self) -> u32 {
         info!("start:\n{}", self);
+        let progress = Progress::new("day 23 rounds");
         for i in 0.. {
+            progress.explored(1);
             if !self.next() {
                 info!("end:\n{}", self);
                 return i + 1;
@@ -51,19 +167,17 @@ impl Ground {
         let range = self.range();
         let dx = (range.1 - range.0 + 1) as usize;
         let dy = (range.3 - range.2 + 1) as usize;
-        dx * dy
-            - (range.0..=range.1)
-                .flat_map(|x| {
-                    (range.2..=range.3).filter(move |&y| {
-                        let c = Coord::new(x, y);
-                        self.elves.contains(&c)
-                    })
-                })
-                .count()
+        dx * dy - self.elf_positions().count()
+    }
+    fn elf_positions(&self) -> impl Iterator<Item = Coord> + '_ {
+        self.elves.iter().filter(|(_, &elf)| elf).map(|(c, _)| c)
     }
-    fn range(&self) -> (i32, i32, i32, i32) {
-        let first = self.elves.iter().next().unwrap();
-        self.elves.iter().fold(
+    fn is_elf(&self, coord: Coord) -> bool {
+        self.elves.get(coord).copied().unwrap_or_default()
+    }
+    fn range(&self) -> (i64, i64, i64, i64) {
+        let first = self.elf_positions().next().unwrap();
+        self.elf_positions().fold(
             (first.x, first.x, first.y, first.y),
             |(mut min_x, mut max_x, mut min_y, mut max_y), next| {
                 min_x = min_x.min(next.x);
@@ -76,78 +190,55 @@ impl Ground {
         )
     }
     fn next(&mut self) -> bool {
-        let directions = self.get_directions_in_order();
-        let (proposed, mapping) = self
-            .elves
-            .iter()
-            .map(|c| {
-                (
-                    c.clone(),
-                    self.get_elf_proposed_next_position(c, &directions),
-                )
-            })
-            .fold::<(HashMap<Coord, u32>, HashMap<Coord, Coord>), _>(
-                (Default::default(), Default::default()),
-                |(mut proposed, mut mapping), (c, next)| {
-                    *proposed.entry(next.clone()).or_default() += 1;
-                    mapping.insert(c, next);
-                    (proposed, mapping)
-                },
-            );
+        let (min_x, max_x, min_y, max_y) = self.range();
+        let (width, height) = (self.elves.width() as i64, self.elves.height() as i64);
+        if min_x == 0 || min_y == 0 || max_x == width - 1 || max_y == height - 1 {
+            self.elves = self.elves.padded(Self::MARGIN, false);
+        }
 
-        let next: HashSet<_> = self
-            .elves
-            .iter()
-            .map(|c| {
-                let next = mapping.get(c).unwrap();
-                if proposed.get(&next).unwrap() < &2 {
-                    next.clone()
-                } else {
-                    c.clone()
-                }
-            })
+        let directions = self.get_directions_in_order();
+        let moves: Vec<(Coord, Coord)> = self
+            .elf_positions()
+            .map(|c| (c, self.get_elf_proposed_next_position(c, &directions)))
             .collect();
-        assert_eq!(next.len(), self.elves.len());
-        if next.iter().all(|c| self.elves.contains(c)) {
-            // no one moved
-            false
-        } else {
-            self.elves = next;
-            true
+        let mut proposed = Grid2D::new(self.elves.width(), self.elves.height(), 0u8);
+        for &(_, next) in moves.iter() {
+            proposed[next] = proposed[next].saturating_add(1);
         }
+
+        let mut moved = false;
+        for (c, next) in moves {
+            if next != c && proposed[next] < 2 {
+                self.elves[c] = false;
+                self.elves[next] = true;
+                moved = true;
+            }
+        }
+        moved
     }
-    fn get_elf_proposed_next_position(&self, coord: &Coord, directions: &[Direction]) -> Coord {
-        if Self::get_neighbors().all(|n| !self.elves.contains(&(coord + &n))) {
+    fn get_elf_proposed_next_position(&self, coord: Coord, directions: &[Direction]) -> Coord {
+        if coord.neighbors8().all(|n| !self.is_elf(n)) {
             debug!("{:?} is staying still", coord);
-            return coord.clone();
+            return coord;
         }
         directions
             .iter()
             .copied()
-            .filter(|&d| {
-                Self::get_neighbors_on_side(d).all(|n| !self.elves.contains(&(coord + &n)))
-            })
-            .next()
+            .find(|&d| Self::get_neighbors_on_side(d).all(|n| !self.is_elf(coord + n)))
             .map(|d| {
-                let next = coord + &Coord::from(d);
+                let next = coord + Coord::from(d);
                 debug!("{:?} is proposing {:?} {:?}", coord, d, next);
                 next
             })
             .unwrap_or_else(|| {
                 debug!("{:?} has nowhere to go :(", coord);
-                coord.clone()
+                coord
             })
     }
-    fn get_neighbors() -> impl Iterator<Item = Coord> {
-        (-1..=1).flat_map(move |x| {
-            (-1..=1)
-                .filter(move |&y| y != 0 || x != 0)
-                .map(move |y| Coord::new(x, y))
-        })
-    }
     fn get_neighbors_on_side(direction: Direction) -> impl Iterator<Item = Coord> {
         let direction_coord = Coord::from(direction);
-        Self::get_neighbors()
+        Coord::default()
+            .neighbors8()
             .filter(move |c| {
                 if c.x < 0 {
                     direction != Direction::East
@@ -197,42 +288,13 @@ enum Direction {
     West = 8,
 }
 
-#[derive(Clone, Eq, PartialEq, Hash)]
-struct Coord {
-    x: i32,
-    y: i32,
-}
-
-impl Debug for Coord {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "({},{})", self.x, self.y)
-    }
-}
-
-impl Coord {
-    pub fn new(x: i32, y: i32) -> Self {
-        Self { x, y }
-    }
-}
-
-impl Add for &Coord {
-    type Output = Coord;
-
-    fn add(self, rhs: Self) -> Self::Output {
-        Coord {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
-        }
-    }
-}
-
 impl From<Direction> for Coord {
     fn from(value: Direction) -> Self {
         match value {
-            Direction::North => Coord::new(0, -1),
-            Direction::South => Coord::new(0, 1),
-            Direction::East => Coord::new(1, 0),
-            Direction::West => Coord::new(-1, 0),
+            Direction::North => Coord::UP,
+            Direction::South => Coord::DOWN,
+            Direction::East => Coord::RIGHT,
+            Direction::West => Coord::LEFT,
         }
     }
 }
@@ -247,8 +309,7 @@ impl Display for Ground {
                 "{}",
                 (range.0..=range.1)
                     .map(|x| {
-                        let c = Coord::new(x, y);
-                        if self.elves.contains(&c) {
+                        if self.is_elf(Coord::new(x, y)) {
                             '#'
                         } else {
                             '.'
@@ -262,22 +323,18 @@ impl Display for Ground {
 }
 
 impl FromStr for Ground {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self {
-            elves: s
-                .lines()
-                .enumerate()
-                .flat_map(move |(y, line)| {
-                    line.chars()
-                        .enumerate()
-                        .filter(|(_, c)| *c == '#')
-                        .map(move |(x, _)| Coord::new(x as i32, y as i32))
-                })
-                .collect(),
-            step: 0,
-        })
+        let elves = Grid2D::parse(DAY, s, "invalid tile", |_, c| match c {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        })?;
+        if !elves.iter().any(|(_, &elf)| elf) {
+            return Err(ParseError::new(DAY, s, &s[..0], "no elves"));
+        }
+        Ok(Self { elves, step: 0 })
     }
 }
 
@@ -296,6 +353,11 @@ mod tests {
 .#..#..";
 
         let mut ground: Ground = input.parse().unwrap();
+        let last = {
+            let mut frames = UnstableDiffusion::visualize(&ground, 2).unwrap();
+            std::iter::from_fn(|| frames.next_frame()).last().unwrap()
+        };
+        assert_eq!("round 20: no elf moved", last.caption);
         ground.run(10);
         assert_eq!(
             format!("{}", ground)
@@ -321,5 +383,8 @@ mod tests {
 
         let mut ground: Ground = input.parse().unwrap();
         assert_eq!(20, ground.run_to_end());
+
+        let error = "..#\n.O#".parse::<Ground>().err().unwrap();
+        assert_eq!((2, 2, "O"), (error.line, error.column, error.text.as_str()));
     }
 }
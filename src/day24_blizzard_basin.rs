@@ -1,25 +1,193 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
-use anyhow::{bail, Context};
 use log::{debug, trace};
-use utils::a_star::{a_star_search, Node, Options, CurrentNodeDetails, Successor};
-use utils::pretty_print::PrettyPrint;
-
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input24.txt").unwrap();
-    //     let input = "#.######
-    // #>>.<^<#
-    // #.<..<<#
-    // #>v.><>#
-    // #<^v^^>#
-    // ######.#";
-    let valley: Valley = input.parse().unwrap();
-    let path = get_shortest_path(valley.clone());
-    println!("fewest minutes: {}", path.len() - 1);
-    let path = get_shortest_path_3x(valley);
-    println!("fewest minutes v2: {}", path.len() - 1);
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::grid::{Coord, Grid2D};
+use crate::parse_error::{invalid_char, ParseError};
+use crate::progress::Progress;
+use crate::search::{Graph, Search};
+use crate::solution::Solution;
+use crate::visualize::{Frame, Visualize};
+
+const DAY: u8 = 24;
+
+pub struct BlizzardBasin;
+
+impl Solution for BlizzardBasin {
+    type Input = Valley;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(input.parse::<Valley>()?)
+    }
+    fn part1(valley: &Self::Input) -> usize {
+        get_shortest_path(valley.clone()).len() - 1
+    }
+    fn part2(valley: &Self::Input) -> usize {
+        get_shortest_path_3x(valley.clone()).len() - 1
+    }
+    /// Replays the shortest path minute by minute.
+    fn visualize(valley: &Self::Input, part: u8) -> Option<Box<dyn Visualize + '_>> {
+        let path = match part {
+            1 => get_shortest_path(valley.clone()),
+            _ => get_shortest_path_3x(valley.clone()),
+        };
+        let mut valley = valley.clone();
+        let mut last = path[0].position;
+        Some(Box::new(path.into_iter().map(move |state| {
+            valley
+                .blizzards_cache
+                .get_blizzard_state(state.time, &valley.ground);
+            valley.time = state.time;
+            valley.position = state.position;
+            let caption = match state.time {
+                0 => "initial state".to_string(),
+                time => format!(
+                    "minute {}, {}",
+                    time,
+                    formatted_direction(&state.position, &last)
+                ),
+            };
+            last = state.position;
+            Frame::new(caption, &valley)
+        })))
+    }
+    /// A valley `size` / 4 tiles high and four times as wide, entered at the top left and
+    /// left at the bottom right. The search never gives up, so valleys are drawn until both
+    /// ways across are possible, with fewer blizzards after each failure.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        let height = (size / 4).max(2);
+        let width = 4 * height;
+        let mut density = 0.3;
+        loop {
+            let rows: Vec<Vec<char>> = (0..height)
+                .map(|_| {
+                    (0..width)
+                        .map(|x| match rng.gen_bool(density) {
+                            false => '.',
+                            // blizzards going up or down would pass in front of the openings
+                            true if x == 0 || x == width - 1 => *['<', '>'].choose(rng).unwrap(),
+                            true => *['<', '>', '^', 'v'].choose(rng).unwrap(),
+                        })
+                        .collect()
+                })
+                .collect();
+            let (start, end) = ((0, -1), (width as i64 - 1, height as i64));
+            if has_way(&rows, start, end) && has_way(&rows, end, start) {
+                let wall = |opening: usize| {
+                    (0..width + 2)
+                        .map(|x| if x == opening + 1 { '.' } else { '#' })
+                        .collect::<String>()
+                };
+                let inside = rows
+                    .iter()
+                    .map(|row| format!("#{}#", String::from_iter(row)));
+                return std::iter::once(wall(0))
+                    .chain(inside)
+                    .chain([wall(width - 1)])
+                    .collect::<Vec<_>>()
+                    .join("\n");
+            }
+            density *= 0.9;
+        }
+    }
+}
+
+/// Whether `to` can be reached from `from` in the valley whose inside is `rows`, where the
+/// openings are the tiles just above and below it.
+fn has_way(rows: &[Vec<char>], from: (i64, i64), to: (i64, i64)) -> bool {
+    let (height, width) = (rows.len() as i64, rows[0].len() as i64);
+    let tile = |x: i64, y: i64| rows[y.rem_euclid(height) as usize][x.rem_euclid(width) as usize];
+    let clear = |(x, y): (i64, i64), t: i64| {
+        if (x, y) == from || (x, y) == to {
+            return true;
+        }
+        (0..width).contains(&x)
+            && (0..height).contains(&y)
+            && tile(x - t, y) != '>'
+            && tile(x + t, y) != '<'
+            && tile(x, y - t) != 'v'
+            && tile(x, y + t) != '^'
+    };
+    // the blizzards are back where they started after this many minutes
+    let period = width * height;
+    let mut seen = HashSet::from([(from, 0)]);
+    let mut queue = VecDeque::from([(from, 0)]);
+    while let Some(((x, y), t)) = queue.pop_front() {
+        if (x, y) == to {
+            return true;
+        }
+        let t = t + 1;
+        for next in [(x, y), (x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+            if clear(next, t) && seen.insert((next, t % period)) {
+                queue.push_back((next, t % period));
+            }
+        }
+    }
+    false
+}
+
+/// Moves or waits for a minute at a time, heading for `end`.
+struct Basin<'a> {
+    ground: &'a Ground,
+    blizzards: &'a mut BlizzardsCache,
+    end: Coord,
+    /// Done as the distance left to the end shrinks.
+    progress: Progress,
+}
+
+impl<'a> Basin<'a> {
+    fn new(
+        ground: &'a Ground,
+        blizzards: &'a mut BlizzardsCache,
+        start: &Coord,
+        end: Coord,
+        task: impl Into<String>,
+    ) -> Self {
+        Self {
+            ground,
+            blizzards,
+            progress: Progress::new(task).with_total(start.manhattan_distance(&end)),
+            end,
+        }
+    }
+}
+
+impl Graph for Basin<'_> {
+    type Node = State;
+
+    fn successors(&mut self, current: &State) -> Vec<(State, u64)> {
+        self.progress.explored(1);
+        let ground = self.ground;
+        let blizzards = self.blizzards.get_blizzard_state(current.time + 1, ground);
+        trace!("blizzards at t={}:\n{}", current.time + 1, blizzards);
+        trace!(
+            "possible: {:?}",
+            ground.get_neighbors(&current.position).collect::<Vec<_>>()
+        );
+        let next: Vec<Coord> = ground
+            .get_neighbors(&current.position)
+            .chain([current.position])
+            .filter(|n| !blizzards.has_blizzard(n))
+            .collect();
+        trace!("next: {:?}", next);
+        next.into_iter()
+            .map(|c| (State::new(current.time + 1, c), 1))
+            .collect()
+    }
+    fn heuristic(&mut self, current: &State) -> u64 {
+        let distance = current.position.manhattan_distance(&self.end);
+        if let Some(total) = self.progress.total() {
+            self.progress.set_done(total.saturating_sub(distance));
+        }
+        distance
+    }
 }
 
 fn get_shortest_path_3x(valley: Valley) -> Vec<State> {
@@ -30,45 +198,22 @@ fn get_shortest_path_3x(valley: Valley) -> Vec<State> {
         blizzards_cache: mut blizzards,
     } = valley;
     let mut state = State { time: 0, position };
-    fn distance(details: CurrentNodeDetails<State, i32>, end: &State) -> i32 {
-        details
-            .current_node
-            .position
-            .manhattan_distance(&end.position)
-    }
     let mut path = vec![state.clone()];
     for i in 0..3 {
-        let start = state;
-        let end = State::new(
-            0,
-            if i % 2 == 0 {
-                ground.get_end_position()
-            } else {
-                ground.get_initial_position()
-            },
-        );
-        let options = Options::default()
-            .with_no_logs();
+        let end = if i % 2 == 0 {
+            ground.get_end_position()
+        } else {
+            ground.get_initial_position()
+        };
+        let task = format!("day 24 path search, trip {}", i + 1);
+        let mut basin = Basin::new(&ground, &mut blizzards, &state.position, end, task);
         path.extend(
-            a_star_search(
-                start,
-                |current| {
-                    let blizzards = blizzards.get_blizzard_state(current.time + 1, &ground);
-                    ground
-                        .get_neighbors(&current.position)
-                        .chain(vec![current.position.clone()])
-                        .filter(|n| !blizzards.has_blizzard(n))
-                        .map(|c| Successor::new(State::new(current.time + 1, c), 1))
-                        .collect()
-                },
-                |current| distance(current, &end),
-                |current| current.position == end.position,
-                Some(&options),
-            )
-            .map(|res| res.shortest_path)
-            .unwrap()
-            .into_iter()
-            .skip(1),
+            Search::new()
+                .a_star(&mut basin, state, |current| current.position == end)
+                .unwrap()
+                .path
+                .into_iter()
+                .skip(1),
         );
         state = path.last().unwrap().clone();
     }
@@ -83,92 +228,46 @@ fn get_shortest_path(valley: Valley) -> Vec<State> {
         ground,
         blizzards_cache: mut blizzards,
     } = valley;
+    debug!("initial blizzards:\n{}", blizzards.initial_state);
+    let end = ground.get_end_position();
+    let mut basin = Basin::new(&ground, &mut blizzards, &start, end, "day 24 path search");
+    let path = Search::new()
+        .a_star(&mut basin, State::new(0, start), |current| {
+            current.position == end
+        })
+        .unwrap()
+        .path;
     debug!(
-        "initial blizzards:\n{}",
-        blizzards.initial_state.0.pretty_print()
-    );
-    let start = State::new(0, start);
-    let end = State::new(0, ground.get_end_position());
-    fn distance(details: CurrentNodeDetails<State, i32>, end: &State) -> i32 {
-        details
-            .current_node
-            .position
-            .manhattan_distance(&end.position)
-    }
-    let options = Options::default()
-        // .with_no_logs()
-    ;
-    a_star_search(
-        start,
-        |current| {
-            let blizzards = blizzards.get_blizzard_state(current.time + 1, &ground);
-            trace!(
-                "blizzards at t={}:\n{}",
-                current.time + 1,
-                blizzards.0.pretty_print()
-            );
-            trace!(
-                "possible: {}",
-                ground
-                    .get_neighbors(&current.position)
-                    .collect::<Vec<_>>()
-                    .pretty_print()
-            );
-            trace!(
-                "next: {}",
-                ground
-                    .get_neighbors(&current.position)
-                    .chain(vec![current.position.clone()])
-                    .filter(|n| !blizzards.has_blizzard(n))
-                    .collect::<Vec<_>>()
-                    .pretty_print()
-            );
-            ground
-                .get_neighbors(&current.position)
-                .chain(vec![current.position.clone()])
-                .filter(|n| !blizzards.has_blizzard(n))
-                .map(|c| Successor::new(State::new(current.time + 1, c), 1))
-                .collect()
+        "shortest path:\nInitial state:\n{}\n{}",
+        Valley {
+            time: 0,
+            position: ground.get_initial_position(),
+            ground: ground.clone(),
+            blizzards_cache: blizzards.clone(),
         },
-        |current| distance(current, &end),
-        |current| current.position == end.position,
-        Some(&options),
-    )
-    .map(|res| {
-        debug!(
-            "shortest path:\nInitial state:\n{}\n{}",
-            Valley {
-                time: 0,
-                position: ground.get_initial_position(),
-                ground: ground.clone(),
-                blizzards_cache: blizzards.clone(),
-            },
-            res.shortest_path
-                .windows(2)
-                .map(|s| {
-                    let last = &s[0];
-                    let current = &s[1];
-                    format!(
-                        "Minute {}, {}:\n{}",
-                        current.time,
-                        current.position.formatted_direction(&last.position),
-                        Valley {
-                            time: current.time,
-                            position: current.position.clone(),
-                            ground: ground.clone(),
-                            blizzards_cache: blizzards.clone(),
-                        }
-                    )
-                })
-                .collect::<Vec<_>>()
-                .join("\n")
-        );
-        res.shortest_path
-    })
-    .unwrap()
+        path.windows(2)
+            .map(|s| {
+                let last = &s[0];
+                let current = &s[1];
+                format!(
+                    "Minute {}, {}:\n{}",
+                    current.time,
+                    formatted_direction(&current.position, &last.position),
+                    Valley {
+                        time: current.time,
+                        position: current.position,
+                        ground: ground.clone(),
+                        blizzards_cache: blizzards.clone(),
+                    }
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+    path
 }
 
-#[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 struct State {
     time: u32,
     position: Coord,
@@ -180,10 +279,8 @@ impl State {
     }
 }
 
-impl Node for State {}
-
 #[derive(Clone)]
-struct Valley {
+pub struct Valley {
     time: u32,
     position: Coord,
     ground: Ground,
@@ -191,7 +288,7 @@ struct Valley {
 }
 
 impl Valley {
-    pub fn new(start: i32, end: i32, height: i32, width: i32, blizzards: BlizzardsState) -> Self {
+    pub fn new(start: i64, end: i64, height: i64, width: i64, blizzards: BlizzardsState) -> Self {
         let ground = Ground {
             start,
             end,
@@ -239,15 +336,23 @@ impl BlizzardsCache {
     }
 }
 
+/// The blizzards on each tile, including the wall rows at the top and bottom, which stay empty.
 #[derive(Clone)]
-struct BlizzardsState(HashMap<Coord, Vec<Direction>>);
+pub struct BlizzardsState(Grid2D<Vec<Direction>>);
 
 impl BlizzardsState {
     pub fn has_blizzard(&self, coord: &Coord) -> bool {
-        self.0.contains_key(coord)
+        self.0.get(*coord).is_some_and(|b| !b.is_empty())
+    }
+    fn symbol(blizzards: &[Direction]) -> char {
+        match blizzards {
+            [] => '.',
+            [d] => char::from(*d),
+            _ => blizzards.len().to_string().chars().next().unwrap(),
+        }
     }
     pub fn next(&self, ground: &Ground) -> Self {
-        fn add_wrap(val: &mut i32, min: i32, max: i32) {
+        fn add_wrap(val: &mut i64, min: i64, max: i64) {
             if *val == max {
                 *val = min;
             } else {
@@ -255,42 +360,42 @@ impl BlizzardsState {
             }
         }
 
-        fn sub_wrap(val: &mut i32, min: i32, max: i32) {
+        fn sub_wrap(val: &mut i64, min: i64, max: i64) {
             if *val == min {
                 *val = max;
             } else {
                 *val -= 1;
             }
         }
-        Self(
-            self.0
-                .iter()
-                .flat_map(|(c, blizzards)| {
-                    blizzards.iter().map(|&d| {
-                        let mut c = c.clone();
-                        match d {
-                            Direction::Up => sub_wrap(&mut c.y, 1, ground.height - 2),
-                            Direction::Down => add_wrap(&mut c.y, 1, ground.height - 2),
-                            Direction::Left => sub_wrap(&mut c.x, 0, ground.width - 1),
-                            Direction::Right => add_wrap(&mut c.x, 0, ground.width - 1),
-                        }
-                        (c, d)
-                    })
-                })
-                .fold(Default::default(), |mut result, (coord, direction)| {
-                    result.entry(coord).or_default().push(direction);
-                    result
-                }),
-        )
+        let mut next = Grid2D::new(self.0.width(), self.0.height(), vec![]);
+        for (c, blizzards) in self.0.iter() {
+            for &d in blizzards {
+                let mut c = c;
+                match d {
+                    Direction::Up => sub_wrap(&mut c.y, 1, ground.height - 2),
+                    Direction::Down => add_wrap(&mut c.y, 1, ground.height - 2),
+                    Direction::Left => sub_wrap(&mut c.x, 0, ground.width - 1),
+                    Direction::Right => add_wrap(&mut c.x, 0, ground.width - 1),
+                }
+                next[c].push(d);
+            }
+        }
+        Self(next)
+    }
+}
+
+impl Display for BlizzardsState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.render(|_, b| Self::symbol(b)))
     }
 }
 
 #[derive(Clone)]
-struct Ground {
-    start: i32,
-    end: i32,
-    height: i32,
-    width: i32,
+pub struct Ground {
+    start: i64,
+    end: i64,
+    height: i64,
+    width: i64,
 }
 
 impl Ground {
@@ -300,95 +405,18 @@ impl Ground {
     pub fn get_end_position(&self) -> Coord {
         Coord::new(self.end, self.height - 1)
     }
-    pub fn get_neighbors(&self, position: &Coord) -> Neighbors {
-        let mut neighbors = Neighbors {
-            i: 0,
-            position: position.clone(),
-            can_move_up: true,
-            can_move_down: true,
-            can_move_sides: true,
-            width: self.width,
-        };
-        if position.y == 0 {
-            neighbors.can_move_sides = false;
-            neighbors.can_move_up = false;
-        } else if position.y == 1 {
-            if position.x != self.start {
-                neighbors.can_move_up = false;
-            }
-        } else if position.y == self.height - 2 {
-            if position.x != self.end {
-                neighbors.can_move_down = false;
-            }
-        } else if position.y == self.height - 1 {
-            neighbors.can_move_down = false;
-            neighbors.can_move_sides = false;
-        }
-        neighbors
+    pub fn get_neighbors(&self, position: &Coord) -> impl Iterator<Item = Coord> + '_ {
+        position.neighbors4().filter(|c| self.is_open(c))
     }
-}
-
-struct Neighbors {
-    i: u32,
-    position: Coord,
-    can_move_up: bool,
-    can_move_down: bool,
-    can_move_sides: bool,
-    width: i32,
-}
-
-impl Iterator for Neighbors {
-    type Item = Coord;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.i > 3 {
-            return None;
-        }
-        if self.i == 0 {
-            self.i += 1;
-            if self.can_move_up {
-                return Some(Coord {
-                    x: self.position.x,
-                    y: self.position.y - 1,
-                });
-            }
-        }
-        if self.i == 1 {
-            self.i += 1;
-            if self.can_move_down {
-                return Some(Coord {
-                    x: self.position.x,
-                    y: self.position.y + 1,
-                });
-            }
-        }
-        if !self.can_move_sides {
-            self.i += 2;
-            return None;
-        }
-        if self.i == 2 {
-            self.i += 1;
-            if self.position.x > 0 {
-                return Some(Coord {
-                    x: self.position.x - 1,
-                    y: self.position.y,
-                });
-            }
-        }
-        self.i += 1;
-        if self.position.x < self.width - 1 {
-            Some(Coord {
-                x: self.position.x + 1,
-                y: self.position.y,
-            })
-        } else {
-            None
-        }
+    fn is_open(&self, coord: &Coord) -> bool {
+        *coord == self.get_initial_position()
+            || *coord == self.get_end_position()
+            || ((1..self.height - 1).contains(&coord.y) && (0..self.width).contains(&coord.x))
     }
 }
 
 #[derive(Copy, Clone, Debug)]
-enum Direction {
+pub(crate) enum Direction {
     Up,
     Down,
     Left,
@@ -396,15 +424,15 @@ enum Direction {
 }
 
 impl TryFrom<char> for Direction {
-    type Error = ();
+    type Error = ParseError;
 
-    fn try_from(value: char) -> Result<Direction, ()> {
+    fn try_from(value: char) -> Result<Direction, ParseError> {
         Ok(match value {
             '^' => Direction::Up,
             'v' => Direction::Down,
             '<' => Direction::Left,
             '>' => Direction::Right,
-            _other => return Err(()),
+            other => return Err(invalid_char(DAY, other, "invalid tile")),
         })
     }
 }
@@ -420,89 +448,65 @@ impl From<Direction> for char {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
-struct Coord {
-    y: i32,
-    x: i32,
-}
-
-impl Coord {
-    pub fn new(x: i32, y: i32) -> Self {
-        Self { x, y }
-    }
-    pub fn manhattan_distance(&self, other: &Self) -> i32 {
-        self.x.abs_diff(other.x) as i32 + self.y.abs_diff(other.y) as i32
-    }
-    pub fn formatted_direction(&self, from: &Self) -> &'static str {
-        assert!(self.x.abs_diff(from.x) + self.y.abs_diff(from.y) < 2);
-        if self == from {
-            "wait"
-        } else if self.x < from.x {
-            "move left"
-        } else if self.x > from.x {
-            "move right"
-        } else if self.y < from.y {
-            "move up"
-        } else {
-            "move down"
-        }
+fn formatted_direction(to: &Coord, from: &Coord) -> &'static str {
+    assert!(to.manhattan_distance(from) < 2);
+    if to == from {
+        "wait"
+    } else if to.x < from.x {
+        "move left"
+    } else if to.x > from.x {
+        "move right"
+    } else if to.y < from.y {
+        "move up"
+    } else {
+        "move down"
     }
 }
 
 impl FromStr for Valley {
-    type Err = anyhow::Error;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |text: &str, message: &str| ParseError::new(DAY, s, text, message);
         let lines = s.lines().collect::<Vec<_>>();
         let height = lines.len();
-        let width = match lines.iter().next().context("empty")?.chars().count() {
-            0..=2 => bail!("valley too narrow"),
+        let [first, _, .., last] = lines[..] else {
+            return Err(error(&s[s.len()..], "valley too short"));
+        };
+        let width = match first.chars().count() {
+            0..=2 => return Err(error(first, "valley too narrow")),
             l => l - 2,
         };
-        let start = lines
-            .iter()
-            .next()
-            .context("empty")?
-            .chars()
-            .position(|c| c == '.')
-            .context("start not found")? as u32
-            - 1;
-        let end = lines
-            .iter()
-            .last()
-            .context("empty")?
-            .chars()
-            .position(|c| c == '.')
-            .context("end not found")? as u32
-            - 1;
-        let blizzards: HashMap<Coord, Vec<Direction>> = lines
-            .into_iter()
-            .enumerate()
-            .skip(1)
-            .take(height - 2)
-            .flat_map(move |(y, line)| {
-                line.chars().skip(1).enumerate().filter_map(move |(x, c)| {
-                    Direction::try_from(c)
-                        .map(|d| {
-                            trace!(
-                                "blizzard {} at {:?}",
-                                char::from(d),
-                                Coord::new(x as i32, y as i32)
-                            );
-                            (Coord::new(x as i32, y as i32), d)
-                        })
-                        .ok()
-                })
-            })
-            .fold(Default::default(), |mut result, (coord, direction)| {
-                result.entry(coord).or_default().push(direction);
-                result
-            });
+        if let Some(line) = lines.iter().find(|l| l.chars().count() != width + 2) {
+            return Err(error(line, "rows have different lengths"));
+        }
+        let opening = |line: &str, what: &str| {
+            line.chars()
+                .skip(1)
+                .take(width)
+                .position(|c| c == '.')
+                .ok_or_else(|| error(line, what))
+        };
+        let start = opening(first, "start not found")?;
+        let end = opening(last, "end not found")?;
+        let mut blizzards = Grid2D::new(width, height, vec![]);
+        for (y, line) in lines.into_iter().enumerate().skip(1).take(height - 2) {
+            for (x, (i, c)) in line.char_indices().skip(1).take(width).enumerate() {
+                if let '.' | '#' = c {
+                    continue;
+                }
+                let coord = Coord::new(x as i64, y as i64);
+                let direction =
+                    Direction::try_from(c).map_err(|e| e.within(s, &line[i..i + c.len_utf8()]))?;
+                trace!("blizzard {} at {:?}", char::from(direction), coord);
+                blizzards[coord].push(direction);
+            }
+        }
         Ok(Self::new(
-            start as i32,
-            end as i32,
-            height as i32,
-            width as i32,
+            start as i64,
+            end as i64,
+            height as i64,
+            width as i64,
             BlizzardsState(blizzards),
         ))
     }
@@ -538,15 +542,7 @@ impl Display for Valley {
                                 '#'
                             }
                         } else {
-                            if let Some(b) = blizzards.0.get(&c) {
-                                if b.len() == 1 {
-                                    char::from(b[0])
-                                } else {
-                                    b.len().to_string().chars().next().unwrap()
-                                }
-                            } else {
-                                '.'
-                            }
+                            BlizzardsState::symbol(&blizzards.0[c])
                         }
                     })
                     .collect::<String>()
@@ -582,8 +578,23 @@ mod tests {
         let path = get_shortest_path(valley.clone());
         println!("shortest path: {:?}", path);
         assert_eq!(18, path.len() - 1);
+        let last = {
+            let mut frames = BlizzardBasin::visualize(&valley, 1).unwrap();
+            std::iter::from_fn(|| frames.next_frame()).last().unwrap()
+        };
+        assert_eq!("minute 18, move down", last.caption);
+        assert!(last.picture.ends_with("#####E#\n"));
         let path = get_shortest_path_3x(valley);
         println!("shortest path v2: {:?}", path);
         assert_eq!(54, path.len() - 1);
+
+        let error = input
+            .replace("#>v.", "#>x.")
+            .parse::<Valley>()
+            .err()
+            .unwrap();
+        assert_eq!((4, 3, "x"), (error.line, error.column, error.text.as_str()));
+        let error = "#.###\n#...#\n#####".parse::<Valley>().err().unwrap();
+        assert_eq!((3, "end not found"), (error.line, error.message.as_str()));
     }
 }
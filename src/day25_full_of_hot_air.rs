@@ -3,44 +3,70 @@ use std::iter::Sum;
 use std::ops::Add;
 use std::str::FromStr;
 
-use anyhow::{bail, Context};
 use itertools::{EitherOrBoth, Itertools};
+use rand::rngs::StdRng;
+use rand::Rng;
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input25.txt").unwrap();
-    let result = input
-        .lines()
-        .map(|v| v.parse::<Snafu>().unwrap())
-        .sum::<Snafu>();
-    println!("sum: {}", result);
+use crate::parse_error::{invalid_char, parse_lines, ParseError};
+use crate::solution::{Answer, PartAnswer, Solution};
+
+const DAY: u8 = 25;
+
+pub struct FullOfHotAir;
+
+impl Solution for FullOfHotAir {
+    type Input = Vec<Snafu>;
+    type Answer1 = Snafu;
+    type Answer2 = ();
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(parse_lines(input)?)
+    }
+    fn part1(numbers: &Self::Input) -> Snafu {
+        numbers.iter().cloned().sum()
+    }
+    fn part2(_: &Self::Input) {}
+    /// `size` fuel requirements of up to a trillion.
+    fn generate(rng: &mut StdRng, size: usize) -> String {
+        (0..size)
+            .map(|_| Snafu::from(rng.gen_range(1..=1_000_000_000_000u64)).to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
-struct Snafu(Vec<SnafuDigit>);
+pub struct Snafu(Vec<SnafuDigit>);
+
+impl From<Snafu> for Answer {
+    fn from(value: Snafu) -> Self {
+        Self::Text(value.to_string())
+    }
+}
+
+impl PartAnswer for Snafu {
+    fn into_answer(self) -> anyhow::Result<Answer> {
+        Ok(self.into())
+    }
+}
 
 #[derive(Copy, Clone, Debug, PartialEq)]
-enum SnafuDigit {
+pub(crate) enum SnafuDigit {
     NonNegative(u64),
     Negative(u64),
 }
 
 impl TryFrom<char> for SnafuDigit {
-    type Error = anyhow::Error;
+    type Error = ParseError;
 
-    fn try_from(value: char) -> Result<Self, Self::Error> {
-        if value.is_numeric() {
-            let val = value.to_digit(10).context("parse int error")?;
-            if val > 2 {
-                bail!("out of bounds SnafuDigit '{}'", val);
-            }
-        }
+    fn try_from(value: char) -> Result<Self, ParseError> {
         Ok(match value {
             '0' => SnafuDigit::NonNegative(0),
             '1' => SnafuDigit::NonNegative(1),
             '2' => SnafuDigit::NonNegative(2),
             '-' => SnafuDigit::Negative(1),
             '=' => SnafuDigit::Negative(2),
-            other => bail!("invalid SnafuDigit '{}'", other),
+            other => return Err(invalid_char(DAY, other, "invalid SNAFU digit")),
         })
     }
 }
@@ -79,13 +105,18 @@ impl Display for Snafu {
 }
 
 impl FromStr for Snafu {
-    type Err = anyhow::Error;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ParseError::new(DAY, s, s, "empty SNAFU number"));
+        }
         Ok(Self(
-            s.chars()
-                .map(|c| SnafuDigit::try_from(c))
-                .collect::<anyhow::Result<_>>()?,
+            s.char_indices()
+                .map(|(i, c)| {
+                    SnafuDigit::try_from(c).map_err(|e| e.within(s, &s[i..i + c.len_utf8()]))
+                })
+                .collect::<Result<_, _>>()?,
         ))
     }
 }
@@ -181,8 +212,8 @@ impl From<&Base5> for Snafu {
                 carry = true;
             }
         }
-        if carry {
-            result.push(SnafuDigit::NonNegative(1));
+        if carry || result.is_empty() {
+            result.push(SnafuDigit::NonNegative(carry as u64));
         }
         result.reverse();
         Self(result)
@@ -247,6 +278,8 @@ impl Sum for Snafu {
 
 #[cfg(test)]
 mod tests {
+    use rand::SeedableRng;
+
     use super::*;
 
     #[test]
@@ -320,5 +353,17 @@ mod tests {
             .map(|v| v.parse::<Snafu>().unwrap())
             .sum::<Snafu>();
         assert_eq!("2=-1=0", format!("{}", result));
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for n in (0..1000).chain((0..1000).map(|_| rng.gen_range(0..1 << 60))) {
+            let snafu: Snafu = Snafu::from(n).to_string().parse().unwrap();
+            assert_eq!(n, u64::from(&snafu));
+        }
+        for c in "012-=".chars() {
+            assert_eq!(c, char::from(SnafuDigit::try_from(c).unwrap()));
+        }
+
+        let error = parse_lines::<Snafu>("1=-0-2\n12311").unwrap_err();
+        assert_eq!((2, 3, "3"), (error.line, error.column, error.text.as_str()));
     }
 }
@@ -0,0 +1,141 @@
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+use crate::generate::generate;
+use crate::registry;
+use crate::solution::Answer;
+use crate::{day12, day16, day21};
+
+/// Two implementations of the same answer, which should agree on every input.
+pub struct Alternatives {
+    pub day: u8,
+    pub name: &'static str,
+    /// The answer of each implementation, or an error if `input` isn't valid.
+    pub answers: fn(&str) -> anyhow::Result<(Answer, Answer)>,
+}
+
+static ALTERNATIVES: &[Alternatives] = &[
+    Alternatives {
+        day: 12,
+        name: "shortest path from any lowest square",
+        answers: day12::global_path_both_ways,
+    },
+    Alternatives {
+        day: 16,
+        name: "most pressure alone",
+        answers: day16::pressure_alone_both_ways,
+    },
+    Alternatives {
+        day: 16,
+        name: "most pressure with an elephant",
+        answers: day16::pressure_with_elephant_both_ways,
+    },
+    Alternatives {
+        day: 21,
+        name: "number to yell",
+        answers: day21::humn_both_ways,
+    },
+];
+
+pub fn all() -> &'static [Alternatives] {
+    ALTERNATIVES
+}
+
+/// An input the implementations disagree on, shrunk as far as it would go.
+#[derive(Debug)]
+pub struct Mismatch {
+    pub seed: u64,
+    pub size: usize,
+    pub input: String,
+    pub answers: (Answer, Answer),
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} vs {} for seed {} and size {}, shrunk to:\n{}",
+            self.answers.0, self.answers.1, self.seed, self.size, self.input
+        )
+    }
+}
+
+/// Runs both implementations on the input generated from each seed, at `size`. The first
+/// input they disagree on is generated again at the smallest size that still disagrees, then
+/// lines are removed one at a time for as long as the input stays valid and disagreeing.
+pub fn check(alternatives: &Alternatives, seeds: Range<u64>, size: usize) -> Result<(), Mismatch> {
+    let day = registry::find(&alternatives.day.to_string()).unwrap();
+    let Some(seed) = seeds
+        .clone()
+        .find(|&seed| disagreement(alternatives, &generate(day, seed, size)).is_some())
+    else {
+        return Ok(());
+    };
+    let (size, mut input, mut answers) = (1..=size)
+        .find_map(|size| {
+            let input = generate(day, seed, size);
+            disagreement(alternatives, &input).map(|answers| (size, input, answers))
+        })
+        .unwrap();
+    let mut line = 0;
+    while line < input.lines().count() {
+        let shorter = input
+            .lines()
+            .enumerate()
+            .filter(|&(i, _)| i != line)
+            .map(|(_, l)| l)
+            .collect::<Vec<_>>()
+            .join("\n");
+        match disagreement(alternatives, &shorter) {
+            Some(different) => (input, answers) = (shorter, different),
+            None => line += 1,
+        }
+    }
+    Err(Mismatch {
+        seed,
+        size,
+        input,
+        answers,
+    })
+}
+
+fn disagreement(alternatives: &Alternatives, input: &str) -> Option<(Answer, Answer)> {
+    let (a, b) = (alternatives.answers)(input).ok()?;
+    (a != b).then_some((a, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day01::CalorieCounting;
+    use crate::solution::Solution;
+
+    #[test]
+    fn test1() {
+        for alternatives in all() {
+            if let Err(mismatch) = check(alternatives, 0..10, 12) {
+                panic!(
+                    "day {} {}: {}",
+                    alternatives.day, alternatives.name, mismatch
+                );
+            }
+        }
+
+        // an elf carrying over 100000 calories is capped by one of them
+        let capped = Alternatives {
+            day: 1,
+            name: "most calories",
+            answers: |input| {
+                let most = CalorieCounting::part1(&CalorieCounting::parse(input)?);
+                Ok((most.into(), most.min(100_000).into()))
+            },
+        };
+        let mismatch = check(&capped, 0..100, 10).unwrap_err();
+        let calories: Vec<u64> = mismatch.input.lines().map(|l| l.parse().unwrap()).collect();
+        assert!(calories.iter().sum::<u64>() > 100_000);
+        assert!(calories
+            .iter()
+            .all(|c| calories.iter().sum::<u64>() - c <= 100_000));
+        assert!(check(&capped, 0..0, 10).is_ok());
+    }
+}
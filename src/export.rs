@@ -0,0 +1,310 @@
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+
+use crate::cli::ExportOptions;
+use crate::image::{encode_png, encode_ppm, GifEncoder, IndexedImage, Rgb};
+use crate::visualize::{self, Frame, Visualize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Ppm,
+    Png,
+    Gif,
+}
+
+impl ImageFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Ppm => "ppm",
+            ImageFormat::Png => "png",
+            ImageFormat::Gif => "gif",
+        }
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "ppm" => ImageFormat::Ppm,
+            "png" => ImageFormat::Png,
+            "gif" => ImageFormat::Gif,
+            other => bail!("invalid image format '{}', expected ppm, png or gif", other),
+        })
+    }
+}
+
+/// The colour of each kind of cell, keyed by the character the simulations draw it with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    /// Used for spaces and to pad short lines.
+    background: Rgb,
+    /// Used for characters without a colour of their own.
+    other: Rgb,
+    cells: Vec<(char, Rgb)>,
+}
+
+impl Default for Palette {
+    /// Covers the characters drawn by the days that can be visualised.
+    fn default() -> Self {
+        let mut palette = Self {
+            background: [16, 16, 24],
+            other: [255, 0, 255],
+            cells: vec![],
+        };
+        for (cells, colour) in [
+            (".", [40, 40, 52]),
+            ("#", [160, 160, 160]),
+            ("o", [194, 178, 128]),
+            ("~", [90, 140, 200]),
+            ("+", [230, 200, 60]),
+            ("|-", [100, 100, 100]),
+            ("E", [220, 60, 60]),
+            ("<>^v", [170, 210, 255]),
+            ("23456789", [120, 170, 230]),
+        ] {
+            for cell in cells.chars() {
+                palette.set(cell, colour);
+            }
+        }
+        // hill heights, from dark green to snow
+        for (i, cell) in ('a'..='z').enumerate() {
+            let shade = |from: u8, to: u8| (from as usize + (to - from) as usize * i / 25) as u8;
+            palette.set(cell, [shade(30, 235), shade(90, 235), shade(40, 235)]);
+        }
+        palette
+    }
+}
+
+impl Palette {
+    pub fn set(&mut self, cell: char, colour: Rgb) {
+        match cell {
+            ' ' => self.background = colour,
+            _ => match self.cells.iter_mut().find(|(c, _)| *c == cell) {
+                Some((_, existing)) => *existing = colour,
+                None => self.cells.push((cell, colour)),
+            },
+        }
+    }
+    /// Applies comma-separated `<cell>=<rrggbb>` overrides, e.g. `o=ffcc00,#=808080`.
+    pub fn with_overrides(mut self, overrides: &str) -> anyhow::Result<Self> {
+        for entry in overrides.split(',').filter(|e| !e.is_empty()) {
+            let mut chars = entry.chars();
+            let (Some(cell), Some('='), hex) = (chars.next(), chars.next(), chars.as_str()) else {
+                bail!(
+                    "invalid palette entry '{}', expected <cell>=<rrggbb>",
+                    entry
+                );
+            };
+            let colour = u32::from_str_radix(hex, 16)
+                .ok()
+                .filter(|_| hex.len() == 6)
+                .with_context(|| format!("invalid colour '{}', expected rrggbb", hex))?;
+            let [_, r, g, b] = colour.to_be_bytes();
+            self.set(cell, [r, g, b]);
+        }
+        Ok(self)
+    }
+    /// The background first, then the fallback colour, then one colour per cell.
+    fn colours(&self) -> Vec<Rgb> {
+        [self.background, self.other]
+            .into_iter()
+            .chain(self.cells.iter().map(|&(_, colour)| colour))
+            .collect()
+    }
+    fn index(&self, cell: char) -> u8 {
+        match cell {
+            ' ' => 0,
+            _ => self
+                .cells
+                .iter()
+                .position(|&(c, _)| c == cell)
+                .map_or(1, |i| (i + 2) as u8),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportSettings {
+    pub format: ImageFormat,
+    /// Width and height in pixels of each character of a frame.
+    pub cell_size: usize,
+    pub palette: Palette,
+    /// Only every nth frame is exported; the last frame always is.
+    pub every: usize,
+    /// Time each GIF frame is shown for.
+    pub delay: Duration,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            format: ImageFormat::Gif,
+            cell_size: 4,
+            palette: Palette::default(),
+            every: 1,
+            delay: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Draws every character of the frame as a square of `cell_size` pixels.
+fn render(frame: &Frame, palette: &Palette, cell_size: usize) -> IndexedImage {
+    let lines: Vec<&str> = frame.picture.lines().collect();
+    let columns = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let mut image = IndexedImage::new(columns * cell_size, lines.len() * cell_size, 0);
+    for (y, line) in lines.into_iter().enumerate() {
+        for (x, cell) in line.chars().enumerate() {
+            image.fill(
+                x * cell_size,
+                y * cell_size,
+                cell_size,
+                cell_size,
+                palette.index(cell),
+            );
+        }
+    }
+    image
+}
+
+/// Writes the frames to `dir` as `<name>.gif`, or as `<name>-00000.png`, `<name>-00001.png` and
+/// so on, returning how many frames were exported.
+pub fn export_frames(
+    frames: &mut dyn Visualize,
+    dir: &Path,
+    name: &str,
+    settings: &ExportSettings,
+) -> anyhow::Result<usize> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("cannot create directory {}", dir.display()))?;
+    let colours = settings.palette.colours();
+    if colours.len() > 256 {
+        bail!("the palette has {} colours, at most 256 fit", colours.len());
+    }
+    let mut gif = match settings.format {
+        ImageFormat::Gif => Some(GifEncoder::new(&colours)?),
+        _ => None,
+    };
+    let mut exported = 0;
+    let mut write = |frame: &Frame| -> anyhow::Result<()> {
+        let image = render(frame, &settings.palette, settings.cell_size);
+        let data = match (settings.format, gif.as_mut()) {
+            (_, Some(gif)) => {
+                gif.add_frame(&image, settings.delay)?;
+                None
+            }
+            (ImageFormat::Png, _) => Some(encode_png(&image, &colours)?),
+            _ => Some(encode_ppm(&image, &colours)),
+        };
+        if let Some(data) = data {
+            let path = dir.join(format!(
+                "{}-{:05}.{}",
+                name,
+                exported,
+                settings.format.extension()
+            ));
+            std::fs::write(&path, data)
+                .with_context(|| format!("cannot write {}", path.display()))?;
+        }
+        exported += 1;
+        Ok(())
+    };
+
+    let mut skipped = None;
+    let mut i = 0;
+    while let Some(frame) = frames.next_frame() {
+        if i % settings.every.max(1) == 0 {
+            write(&frame)?;
+            skipped = None;
+        } else {
+            skipped = Some(frame);
+        }
+        i += 1;
+    }
+    if let Some(frame) = skipped {
+        write(&frame)?;
+    }
+
+    if let Some(gif) = gif {
+        let path = dir.join(format!("{}.gif", name));
+        std::fs::write(&path, gif.finish())
+            .with_context(|| format!("cannot write {}", path.display()))?;
+    }
+    Ok(exported)
+}
+
+pub(crate) fn run(options: &ExportOptions) -> anyhow::Result<()> {
+    let day = options.day;
+    let input = options.source.read(day)?;
+    let parsed = day.solver.parse(&input)?;
+    for (part, mut frames) in visualize::visualizations(day, &*parsed, options.part)? {
+        let name = format!("day{:02}-part{}", day.number, part);
+        let count = export_frames(frames.as_mut(), &options.dir, &name, &options.settings)?;
+        println!(
+            "exported {} frame(s) of day {} part {} to {}",
+            count,
+            day.number,
+            part,
+            options.dir.display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let palette = Palette::default()
+            .with_overrides("o=ffcc00, =000000,x=0a0b0c")
+            .unwrap();
+        let colours = palette.colours();
+        assert_eq!([0, 0, 0], colours[palette.index(' ') as usize]);
+        assert_eq!([255, 204, 0], colours[palette.index('o') as usize]);
+        assert_eq!([10, 11, 12], colours[palette.index('x') as usize]);
+        assert_eq!(1, palette.index('@'));
+        assert!(Palette::default().with_overrides("o=fc0").is_err());
+        assert!(Palette::default().with_overrides("o:ffcc00").is_err());
+        assert_eq!(
+            Ok(ImageFormat::Png),
+            "png".parse::<ImageFormat>().map_err(|_| ())
+        );
+
+        let image = render(&Frame::new("", "#o\n."), &palette, 2);
+        let (rock, sand, air) = (palette.index('#'), palette.index('o'), palette.index('.'));
+        assert_eq!(
+            vec![rock, rock, sand, sand, rock, rock, sand, sand, air, air, 0, 0, air, air, 0, 0],
+            image.pixels
+        );
+
+        let dir = std::env::temp_dir().join(format!("aoc-export-{}", std::process::id()));
+        let frames = || (0..5).map(|i| Frame::new("", "#".repeat(i + 1)));
+        let settings = ExportSettings {
+            format: ImageFormat::Ppm,
+            every: 3,
+            ..ExportSettings::default()
+        };
+        // frames 0 and 3, then the last one
+        assert_eq!(
+            3,
+            export_frames(&mut frames(), &dir, "test", &settings).unwrap()
+        );
+        let last = std::fs::read(dir.join("test-00002.ppm")).unwrap();
+        assert!(last.starts_with(b"P6\n20 4\n255\n"));
+        let settings = ExportSettings::default();
+        assert_eq!(
+            5,
+            export_frames(&mut frames(), &dir, "test", &settings).unwrap()
+        );
+        assert!(std::fs::read(dir.join("test.gif"))
+            .unwrap()
+            .starts_with(b"GIF89a\x14\0\x04\0"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
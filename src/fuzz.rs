@@ -0,0 +1,151 @@
+use std::str::FromStr;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::registry;
+use crate::{
+    day03, day05, day08, day09, day10, day11, day12, day13, day14, day15, day18, day19, day21,
+    day22, day23, day24, day25,
+};
+
+/// Parses a text, ignoring the result.
+type Parser = fn(&str);
+
+/// The parser of every type that can be parsed on its own, with its day.
+const PARSERS: &[(u8, Parser)] = &[
+    (3, from_char::<day03::Letter>),
+    (3, from_str::<day03::Rucksack>),
+    (5, from_str::<day05::Instruction>),
+    (5, from_str::<day05::Stacks>),
+    (8, from_str::<day08::Forest>),
+    (9, from_str::<day09::Direction>),
+    (9, from_str::<day09::Instruction>),
+    (10, from_str::<day10::Instruction>),
+    (11, from_str::<day11::Operation>),
+    (11, from_str::<day11::Monkey>),
+    (12, from_str::<day12::Hill>),
+    (13, from_str::<day13::Packet>),
+    (14, from_str::<day14::Path>),
+    (15, from_str::<day15::Sensor>),
+    (15, from_str::<day15::Grid>),
+    (18, from_str::<day18::Coord>),
+    (19, from_str::<day19::Mineral>),
+    (19, from_str::<day19::Blueprint>),
+    (21, from_str::<day21::Monkey>),
+    (22, from_char::<day22::Space>),
+    (22, from_char::<day22::Direction>),
+    (22, from_str::<day22::Map>),
+    (23, from_str::<day23::Ground>),
+    (24, from_char::<day24::Direction>),
+    (24, from_str::<day24::Valley>),
+    (25, from_char::<day25::SnafuDigit>),
+    (25, from_str::<day25::Snafu>),
+];
+
+fn from_str<T: FromStr>(text: &str) {
+    let _ = text.parse::<T>();
+}
+
+/// Every character of `text` on its own.
+fn from_char<T: TryFrom<char>>(text: &str) {
+    for c in text.chars() {
+        let _ = T::try_from(c);
+    }
+}
+
+/// Parses `data` as the input of the day picked by its first byte, ignoring the result: every
+/// parser must either succeed or return an error, whatever the bytes. This is what the fuzz
+/// targets in `fuzz/` call.
+pub fn parse_day(data: &[u8]) {
+    let Some((&day, input)) = data.split_first() else {
+        return;
+    };
+    let days = registry::all();
+    let day = &days[day as usize % days.len()];
+    let _ = day.solver.parse(&String::from_utf8_lossy(input));
+}
+
+/// Parses `data` with the parser picked by its first byte, such as the one of day 13's packets or
+/// day 22's map tiles, ignoring the result. Like [`parse_day`], this must never panic.
+pub fn parse_value(data: &[u8]) {
+    let Some((&parser, text)) = data.split_first() else {
+        return;
+    };
+    let (_, parse) = PARSERS[parser as usize % PARSERS.len()];
+    parse(&String::from_utf8_lossy(text));
+}
+
+/// `input` with one to four random edits, such as deleting, repeating or replacing a few
+/// characters, which keeps most of it valid so that the parser gets past the first line.
+pub fn mutate(rng: &mut StdRng, input: &str) -> String {
+    let mut chars: Vec<char> = input.chars().collect();
+    let mut alphabet = chars.clone();
+    alphabet.extend(" \n-+0123456789xyz,:=[]".chars());
+    for _ in 0..rng.gen_range(1..=4) {
+        let at = rng.gen_range(0..=chars.len());
+        let end = (at + rng.gen_range(1..=8)).min(chars.len());
+        match rng.gen_range(0..5) {
+            0 => {
+                chars.drain(at..end);
+            }
+            1 => {
+                let repeated: Vec<char> = chars[at..end].to_vec();
+                chars.splice(at..at, repeated);
+            }
+            2 => chars.insert(at, *alphabet.choose(rng).unwrap()),
+            3 if at < chars.len() => chars[at] = *alphabet.choose(rng).unwrap(),
+            _ => chars.truncate(at),
+        }
+    }
+    chars.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::generate::generate;
+
+    #[test]
+    fn test1() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for day in registry::all() {
+            for seed in 0..20 {
+                let input = generate(day, seed, 5);
+                for _ in 0..20 {
+                    let mutated = mutate(&mut rng, &input);
+                    let mut data = vec![day.number - 1];
+                    data.extend(mutated.as_bytes());
+                    parse_day(&data);
+                }
+            }
+        }
+        parse_day(&[]);
+        parse_day(&[255, 0xff, 0xfe, b'\n']);
+    }
+
+    #[test]
+    fn test2() {
+        // each parser gets the mutated lines of its day's inputs, as well as whole inputs
+        let mut rng = StdRng::seed_from_u64(0);
+        for (i, &(day, parse)) in PARSERS.iter().enumerate() {
+            let day = registry::find(&day.to_string()).unwrap();
+            for seed in 0..10 {
+                let input = generate(day, seed, 5);
+                for text in input.lines().chain([input.as_str()]) {
+                    parse(text);
+                    for _ in 0..5 {
+                        let mut data = vec![i as u8];
+                        data.extend(mutate(&mut rng, text).as_bytes());
+                        parse_value(&data);
+                    }
+                }
+            }
+        }
+        parse_value(&[]);
+        parse_value(&[255, 0xff, 0xfe, b'\n']);
+    }
+}
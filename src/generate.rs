@@ -0,0 +1,68 @@
+use anyhow::{bail, Context};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::cli::GenerateOptions;
+use crate::registry::Day;
+
+pub const DEFAULT_SIZE: usize = 10;
+
+/// A random input for `day`; the same seed and size always give the same input.
+pub fn generate(day: &Day, seed: u64, size: usize) -> String {
+    let mut rng = StdRng::seed_from_u64(seed);
+    day.solver.generate(&mut rng, size)
+}
+
+pub(crate) fn run(options: &GenerateOptions) -> anyhow::Result<()> {
+    // on stderr, so that stdout is only the input
+    eprintln!("seed {}, size {}", options.seed, options.size);
+    let Some(dir) = &options.output else {
+        let [day] = options.days[..] else {
+            bail!("generating several days needs --output <dir>");
+        };
+        println!("{}", generate(day, options.seed, options.size));
+        return Ok(());
+    };
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("cannot create directory {}", dir.display()))?;
+    for &day in options.days.iter() {
+        let path = dir.join(day.input_file_name());
+        std::fs::write(&path, generate(day, options.seed, options.size) + "\n")
+            .with_context(|| format!("cannot write {}", path.display()))?;
+        eprintln!("wrote {}", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::Params;
+    use crate::registry;
+
+    #[test]
+    fn test1() {
+        // day 15 sweeps 4 million rows and day 19 searches for minutes, whatever the size
+        const SLOW: [u8; 2] = [15, 19];
+        for day in registry::all() {
+            assert_eq!(generate(day, 7, 5), generate(day, 7, 5));
+            for seed in 0..3 {
+                let input = generate(day, seed, 5) + "\n";
+                let parsed = day.solver.parse(&input).unwrap_or_else(|e| {
+                    panic!("day {} seed {}: {:#}\n{}", day.number, seed, e, input)
+                });
+                if !SLOW.contains(&day.number) {
+                    let params = Params::defaults(day.solver.parameters());
+                    let answers = parsed.part1(&params).and(parsed.part2(&params));
+                    answers.unwrap_or_else(|e| {
+                        panic!("day {} seed {}: {:#}\n{}", day.number, seed, e, input)
+                    });
+                }
+            }
+        }
+        assert_ne!(
+            generate(registry::find("1").unwrap(), 1, 5),
+            generate(registry::find("1").unwrap(), 2, 5)
+        );
+    }
+}
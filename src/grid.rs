@@ -0,0 +1,307 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::{Add, Index, IndexMut, Sub};
+
+use crate::parse_error::ParseError;
+
+/// A cell position: `x` is the column and `y` the row, both growing from the top-left corner.
+#[derive(Clone, Copy, Default, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct Coord {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Coord {
+    pub const UP: Coord = Coord::new(0, -1);
+    pub const DOWN: Coord = Coord::new(0, 1);
+    pub const LEFT: Coord = Coord::new(-1, 0);
+    pub const RIGHT: Coord = Coord::new(1, 0);
+
+    pub const fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+    pub fn manhattan_distance(&self, other: &Self) -> u64 {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+    /// The orthogonal neighbours, clockwise from up.
+    pub fn neighbors4(self) -> impl Iterator<Item = Coord> {
+        [Self::UP, Self::RIGHT, Self::DOWN, Self::LEFT]
+            .into_iter()
+            .map(move |d| self + d)
+    }
+    /// The orthogonal and diagonal neighbours, clockwise from up.
+    pub fn neighbors8(self) -> impl Iterator<Item = Coord> {
+        [
+            Self::UP,
+            Self::UP + Self::RIGHT,
+            Self::RIGHT,
+            Self::DOWN + Self::RIGHT,
+            Self::DOWN,
+            Self::DOWN + Self::LEFT,
+            Self::LEFT,
+            Self::UP + Self::LEFT,
+        ]
+        .into_iter()
+        .map(move |d| self + d)
+    }
+}
+
+impl Debug for Coord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({},{})", self.x, self.y)
+    }
+}
+
+impl Add for Coord {
+    type Output = Coord;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Coord::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for Coord {
+    type Output = Coord;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Coord::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+/// A dense, row-major grid of `width` x `height` cells.
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct Grid2D<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> Grid2D<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            cells: vec![value; width * height],
+            width,
+            height,
+        }
+    }
+    /// Parses one cell per character, with `cell` returning `None` for invalid characters; lines
+    /// shorter than the longest one are padded with `T::default()`.
+    pub fn parse(
+        day: u8,
+        input: &str,
+        message: &str,
+        mut cell: impl FnMut(Coord, char) -> Option<T>,
+    ) -> Result<Self, ParseError>
+    where
+        T: Clone + Default,
+    {
+        let lines: Vec<&str> = input.lines().collect();
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        let mut grid = Self::new(width, lines.len(), T::default());
+        for (y, line) in lines.into_iter().enumerate() {
+            for (x, (i, c)) in line.char_indices().enumerate() {
+                let coord = Coord::new(x as i64, y as i64);
+                grid[coord] = cell(coord, c).ok_or_else(|| {
+                    ParseError::new(day, input, &line[i..i + c.len_utf8()], message)
+                })?;
+            }
+        }
+        Ok(grid)
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    fn index_of(&self, coord: Coord) -> Option<usize> {
+        let (x, y) = (
+            usize::try_from(coord.x).ok()?,
+            usize::try_from(coord.y).ok()?,
+        );
+        (x < self.width && y < self.height).then_some(y * self.width + x)
+    }
+    fn coord_of(&self, index: usize) -> Coord {
+        Coord::new((index % self.width) as i64, (index / self.width) as i64)
+    }
+    pub fn contains(&self, coord: Coord) -> bool {
+        self.index_of(coord).is_some()
+    }
+    pub fn get(&self, coord: Coord) -> Option<&T> {
+        self.index_of(coord).map(|i| &self.cells[i])
+    }
+    pub fn get_mut(&mut self, coord: Coord) -> Option<&mut T> {
+        self.index_of(coord).map(|i| &mut self.cells[i])
+    }
+    /// The orthogonal neighbours of `coord` that are inside the grid.
+    pub fn neighbors4(&self, coord: Coord) -> impl Iterator<Item = Coord> + '_ {
+        coord.neighbors4().filter(|&c| self.contains(c))
+    }
+    /// The orthogonal and diagonal neighbours of `coord` that are inside the grid.
+    pub fn neighbors8(&self, coord: Coord) -> impl Iterator<Item = Coord> + '_ {
+        coord.neighbors8().filter(|&c| self.contains(c))
+    }
+    /// Every coordinate, row by row.
+    pub fn coords(&self) -> impl Iterator<Item = Coord> + '_ {
+        (0..self.cells.len()).map(|i| self.coord_of(i))
+    }
+    /// Every cell with its coordinate, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Coord, &T)> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| (self.coord_of(i), cell))
+    }
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[T]> + '_ {
+        (0..self.height).map(|y| self.row(y))
+    }
+    pub fn column(&self, x: usize) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator + '_ {
+        assert!(x < self.width, "column {} is outside the grid", x);
+        (0..self.height).map(move |y| &self.cells[y * self.width + x])
+    }
+    pub fn columns(
+        &self,
+    ) -> impl Iterator<Item = impl DoubleEndedIterator<Item = &T> + ExactSizeIterator + '_> + '_
+    {
+        (0..self.width).map(|x| self.column(x))
+    }
+    pub fn transpose(&self) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            cells: self.columns().flatten().cloned().collect(),
+            width: self.height,
+            height: self.width,
+        }
+    }
+    /// Adds or removes rows at the bottom, filling new ones with `value`.
+    pub fn resize(&mut self, height: usize, value: T)
+    where
+        T: Clone,
+    {
+        self.cells.resize(height * self.width, value);
+        self.height = height;
+    }
+    /// A copy with `margin` cells of `value` added on every side, so coordinates move by `margin`.
+    pub fn padded(&self, margin: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        let offset = Coord::new(margin as i64, margin as i64);
+        let mut grid = Self::new(self.width + 2 * margin, self.height + 2 * margin, value);
+        for (coord, cell) in self.iter() {
+            grid[coord + offset] = cell.clone();
+        }
+        grid
+    }
+    /// Draws one character per cell, with rows separated by newlines.
+    pub fn render(&self, mut cell: impl FnMut(Coord, &T) -> char) -> String {
+        let mut result = String::with_capacity((self.width + 1) * self.height);
+        for (i, value) in self.cells.iter().enumerate() {
+            if i > 0 && i % self.width == 0 {
+                result.push('\n');
+            }
+            result.push(cell(self.coord_of(i), value));
+        }
+        result
+    }
+}
+
+impl<T> Index<Coord> for Grid2D<T> {
+    type Output = T;
+
+    fn index(&self, coord: Coord) -> &Self::Output {
+        match self.index_of(coord) {
+            Some(i) => &self.cells[i],
+            None => panic!(
+                "{:?} is outside the {}x{} grid",
+                coord, self.width, self.height
+            ),
+        }
+    }
+}
+
+impl<T> IndexMut<Coord> for Grid2D<T> {
+    fn index_mut(&mut self, coord: Coord) -> &mut Self::Output {
+        match self.index_of(coord) {
+            Some(i) => &mut self.cells[i],
+            None => panic!(
+                "{:?} is outside the {}x{} grid",
+                coord, self.width, self.height
+            ),
+        }
+    }
+}
+
+impl<T: Debug> Debug for Grid2D<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.rows()).finish()
+    }
+}
+
+impl<T: Display> Display for Grid2D<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (y, row) in self.rows().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let grid = Grid2D::parse(0, "123\n45\n789", "not a digit", |_, c| c.to_digit(10)).unwrap();
+        assert_eq!((3, 3), (grid.width(), grid.height()));
+        assert_eq!("123\n450\n789", grid.to_string());
+        assert_eq!(0, grid[Coord::new(2, 1)]);
+        assert_eq!(None, grid.get(Coord::new(3, 0)));
+        assert_eq!(None, grid.get(Coord::new(0, -1)));
+
+        assert_eq!(&[4, 5, 0], grid.row(1));
+        assert_eq!(vec![3, 0, 9], grid.column(2).copied().collect::<Vec<_>>());
+        assert_eq!(
+            vec![9, 0, 3],
+            grid.column(2).rev().copied().collect::<Vec<_>>()
+        );
+        assert_eq!("147\n258\n309", grid.transpose().to_string());
+        assert_eq!(grid, grid.transpose().transpose());
+
+        let corner: Vec<_> = grid.neighbors4(Coord::new(0, 0)).collect();
+        assert_eq!(vec![Coord::new(1, 0), Coord::new(0, 1)], corner);
+        assert_eq!(3, grid.neighbors8(Coord::new(0, 0)).count());
+        assert_eq!(8, grid.neighbors8(Coord::new(1, 1)).count());
+        assert_eq!(
+            Some((Coord::new(1, 2), &8)),
+            grid.iter().find(|&(_, &v)| v == 8)
+        );
+
+        let mut grid = grid.padded(1, 0);
+        assert_eq!(1, grid[Coord::new(1, 1)]);
+        grid.resize(6, 7);
+        assert_eq!("00000\n01230\n04500\n07890\n00000\n77777", grid.to_string());
+        assert_eq!(
+            "#..\n...",
+            Grid2D::new(3, 2, false).render(|c, _| if c == Coord::default() { '#' } else { '.' })
+        );
+
+        let error = Grid2D::parse(0, "12\n3x", "not a digit", |_, c| c.to_digit(10))
+            .err()
+            .unwrap();
+        assert_eq!((2, 2, "x"), (error.line, error.column, error.text.as_str()));
+    }
+}
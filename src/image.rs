@@ -0,0 +1,366 @@
+use std::time::Duration;
+
+use anyhow::bail;
+
+pub type Rgb = [u8; 3];
+
+/// An image whose pixels are indices into a palette of at most 256 colours.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedImage {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl IndexedImage {
+    pub fn new(width: usize, height: usize, index: u8) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![index; width * height],
+        }
+    }
+    pub fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, index: u8) {
+        for row in y..y + height {
+            let start = row * self.width + x;
+            self.pixels[start..start + width].fill(index);
+        }
+    }
+}
+
+/// Binary PPM (P6), which stores every pixel as plain RGB.
+pub fn encode_ppm(image: &IndexedImage, palette: &[Rgb]) -> Vec<u8> {
+    let mut data = format!("P6\n{} {}\n255\n", image.width, image.height).into_bytes();
+    for &index in image.pixels.iter() {
+        data.extend_from_slice(&palette[index as usize]);
+    }
+    data
+}
+
+/// Paletted PNG, compressed with fixed Huffman codes and matches against the previous pixel or
+/// the row above, which is enough for blocky pixel art.
+pub fn encode_png(image: &IndexedImage, palette: &[Rgb]) -> anyhow::Result<Vec<u8>> {
+    if palette.len() > 256 {
+        bail!(
+            "PNG palettes hold at most 256 colours, got {}",
+            palette.len()
+        );
+    }
+    let mut header = vec![];
+    header.extend_from_slice(&(image.width as u32).to_be_bytes());
+    header.extend_from_slice(&(image.height as u32).to_be_bytes());
+    // 8 bits per pixel, paletted, default compression, filtering and no interlacing
+    header.extend_from_slice(&[8, 3, 0, 0, 0]);
+
+    let mut raw = Vec::with_capacity((image.width + 1) * image.height);
+    for row in image.pixels.chunks(image.width.max(1)) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    let mut compressed = vec![0x78, 0x01];
+    compressed.extend(deflate(&raw, image.width + 1));
+    compressed.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut data = b"\x89PNG\r\n\x1a\n".to_vec();
+    png_chunk(&mut data, b"IHDR", &header);
+    png_chunk(&mut data, b"PLTE", &palette.concat());
+    png_chunk(&mut data, b"IDAT", &compressed);
+    png_chunk(&mut data, b"IEND", &[]);
+    Ok(data)
+}
+
+fn png_chunk(data: &mut Vec<u8>, kind: &[u8; 4], contents: &[u8]) {
+    data.extend_from_slice(&(contents.len() as u32).to_be_bytes());
+    let start = data.len();
+    data.extend_from_slice(kind);
+    data.extend_from_slice(contents);
+    let crc = crc32(&data[start..]);
+    data.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+/// Packs bits least significant first, as both deflate and GIF expect.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, bits: u32) {
+        self.buffer |= value << self.count;
+        self.count += bits;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+    /// Huffman codes are stored most significant bit first.
+    fn write_code(&mut self, code: u32, bits: u32) {
+        self.write(code.reverse_bits() >> (32 - bits), bits);
+    }
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+const LENGTH_BASE: [usize; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [usize; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u32; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+const MAX_MATCH: usize = 258;
+const MAX_DISTANCE: usize = 32768;
+
+fn write_literal(writer: &mut BitWriter, value: u32) {
+    match value {
+        0..=143 => writer.write_code(0x30 + value, 8),
+        144..=255 => writer.write_code(0x190 + value - 144, 9),
+        256..=279 => writer.write_code(value - 256, 7),
+        _ => writer.write_code(0xC0 + value - 280, 8),
+    }
+}
+
+fn write_match(writer: &mut BitWriter, length: usize, distance: usize) {
+    let i = LENGTH_BASE.iter().rposition(|&b| b <= length).unwrap();
+    write_literal(writer, 257 + i as u32);
+    writer.write((length - LENGTH_BASE[i]) as u32, LENGTH_EXTRA[i]);
+    let i = DISTANCE_BASE.iter().rposition(|&b| b <= distance).unwrap();
+    writer.write_code(i as u32, 5);
+    writer.write((distance - DISTANCE_BASE[i]) as u32, DISTANCE_EXTRA[i]);
+}
+
+/// A single fixed-Huffman deflate block, only looking for repeats 1 and `stride` bytes back.
+fn deflate(data: &[u8], stride: usize) -> Vec<u8> {
+    let mut writer = BitWriter::default();
+    // final block, fixed Huffman codes
+    writer.write(1, 1);
+    writer.write(1, 2);
+    let mut i = 0;
+    while i < data.len() {
+        let repeat = |distance: usize| {
+            if distance > i || distance > MAX_DISTANCE {
+                return 0;
+            }
+            (0..MAX_MATCH.min(data.len() - i))
+                .take_while(|&k| data[i + k] == data[i + k - distance])
+                .count()
+        };
+        let (length, distance) = [(repeat(stride), stride), (repeat(1), 1)]
+            .into_iter()
+            .max_by_key(|&(length, _)| length)
+            .unwrap();
+        if length >= 3 {
+            write_match(&mut writer, length, distance);
+            i += length;
+        } else {
+            write_literal(&mut writer, data[i] as u32);
+            i += 1;
+        }
+    }
+    write_literal(&mut writer, 256);
+    writer.finish()
+}
+
+/// Builds an endlessly looping GIF in memory. Frames may differ in size: each is drawn at the
+/// top-left corner and cleared to the background afterwards, and the screen grows to fit the
+/// largest one.
+pub struct GifEncoder {
+    palette: Vec<Rgb>,
+    /// Everything after the header and the global palette.
+    body: Vec<u8>,
+    width: u16,
+    height: u16,
+}
+
+impl GifEncoder {
+    pub fn new(palette: &[Rgb]) -> anyhow::Result<Self> {
+        if palette.len() > 256 {
+            bail!(
+                "GIF palettes hold at most 256 colours, got {}",
+                palette.len()
+            );
+        }
+        let mut body = vec![0x21, 0xFF, 0x0B];
+        body.extend_from_slice(b"NETSCAPE2.0");
+        // loop forever
+        body.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+        Ok(Self {
+            palette: palette.to_vec(),
+            body,
+            width: 0,
+            height: 0,
+        })
+    }
+    /// log2 of the padded palette size, at least 2 as LZW requires.
+    fn palette_bits(&self) -> u32 {
+        (usize::BITS - (self.palette.len().max(4) - 1).leading_zeros()).max(2)
+    }
+    pub fn add_frame(&mut self, image: &IndexedImage, delay: Duration) -> anyhow::Result<()> {
+        let (Ok(width), Ok(height)) = (u16::try_from(image.width), u16::try_from(image.height))
+        else {
+            bail!(
+                "{}x{} is too large for a GIF frame; try a smaller cell size",
+                image.width,
+                image.height
+            );
+        };
+        self.width = self.width.max(width);
+        self.height = self.height.max(height);
+        let centiseconds = (delay.as_millis() / 10).min(u16::MAX as u128) as u16;
+        // graphic control: restore to background after the frame is shown
+        self.body.extend_from_slice(&[0x21, 0xF9, 0x04, 2 << 2]);
+        self.body.extend_from_slice(&centiseconds.to_le_bytes());
+        self.body.extend_from_slice(&[0x00, 0x00]);
+        // image descriptor at (0, 0) without a local palette
+        self.body.extend_from_slice(&[0x2C, 0, 0, 0, 0]);
+        self.body.extend_from_slice(&width.to_le_bytes());
+        self.body.extend_from_slice(&height.to_le_bytes());
+        self.body.push(0x00);
+
+        let min_code_size = self.palette_bits();
+        self.body.push(min_code_size as u8);
+        for block in lzw(&image.pixels, min_code_size).chunks(255) {
+            self.body.push(block.len() as u8);
+            self.body.extend_from_slice(block);
+        }
+        self.body.push(0x00);
+        Ok(())
+    }
+    pub fn finish(self) -> Vec<u8> {
+        let bits = self.palette_bits();
+        let mut data = b"GIF89a".to_vec();
+        data.extend_from_slice(&self.width.to_le_bytes());
+        data.extend_from_slice(&self.height.to_le_bytes());
+        // global palette of 2^bits colours, background colour 0, square pixels
+        let size = bits as u8 - 1;
+        data.extend_from_slice(&[0x80 | (size << 4) | size, 0, 0]);
+        for i in 0..1 << bits {
+            data.extend_from_slice(&self.palette.get(i).copied().unwrap_or_default());
+        }
+        data.extend(self.body);
+        data.push(0x3B);
+        data
+    }
+}
+
+/// Variable-width LZW as used by GIF, starting over whenever the code table fills up.
+fn lzw(pixels: &[u8], min_code_size: u32) -> Vec<u8> {
+    let clear = 1u32 << min_code_size;
+    let end = clear + 1;
+    let mut writer = BitWriter::default();
+    let mut table = std::collections::HashMap::new();
+    let mut next = end + 1;
+    let mut bits = min_code_size + 1;
+    writer.write(clear, bits);
+    let mut prefix: Option<u32> = None;
+    for &pixel in pixels {
+        let Some(current) = prefix else {
+            prefix = Some(pixel as u32);
+            continue;
+        };
+        if let Some(&code) = table.get(&(current, pixel)) {
+            prefix = Some(code);
+            continue;
+        }
+        writer.write(current, bits);
+        // stop one short of 4096 codes, so that decoders never need to read a 13-bit code
+        if next < 4095 {
+            table.insert((current, pixel), next);
+            next += 1;
+            if next > 1 << bits {
+                bits += 1;
+            }
+        } else {
+            writer.write(clear, bits);
+            table.clear();
+            next = end + 1;
+            bits = min_code_size + 1;
+        }
+        prefix = Some(pixel as u32);
+    }
+    if let Some(current) = prefix {
+        writer.write(current, bits);
+        // decoders add a code for this one too, which may widen the end code
+        if next == 1 << bits && bits < 12 {
+            bits += 1;
+        }
+    }
+    writer.write(end, bits);
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let palette = [[0, 0, 0], [255, 128, 0]];
+        let mut image = IndexedImage::new(3, 2, 0);
+        image.fill(1, 0, 2, 1, 1);
+        assert_eq!(vec![0, 1, 1, 0, 0, 0], image.pixels);
+        assert_eq!(
+            b"P6\n3 2\n255\n\0\0\0\xff\x80\0\xff\x80\0\0\0\0\0\0\0\0\0\0".to_vec(),
+            encode_ppm(&image, &palette)
+        );
+
+        assert_eq!(0xAE42_6082, crc32(b"IEND"));
+        assert_eq!(0x11E6_0398, adler32(b"Wikipedia"));
+        let png = encode_png(&image, &palette).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x03\0\0\0\x02"));
+        assert!(png.ends_with(b"\0\0\0\0IEND\xae\x42\x60\x82"));
+        // literals 'a' and 'b', then 4 bytes repeated from 2 bytes back, then the end of the block
+        assert_eq!(vec![0x4B, 0x4C, 0x02, 0x41, 0x00], deflate(b"ababab", 2));
+        assert!(deflate(&[7; 10_000], 100).len() < 200);
+
+        // clear, 1, 6 (1 1), 6 (1 1) in 3 bits, then the end code in 4 bits once 8 codes exist
+        assert_eq!(vec![0x8C, 0x5D], lzw(&[1, 1, 1, 1, 1], 2));
+        let mut gif = GifEncoder::new(&palette).unwrap();
+        gif.add_frame(&image, Duration::from_millis(50)).unwrap();
+        gif.add_frame(&IndexedImage::new(5, 1, 1), Duration::ZERO)
+            .unwrap();
+        let gif = gif.finish();
+        assert!(gif.starts_with(b"GIF89a\x05\0\x02\0\x91\0\0\0\0\0\xff\x80\0"));
+        assert_eq!(Some(&0x3B), gif.last());
+        assert!(GifEncoder::new(&[[0; 3]; 257]).is_err());
+    }
+}
@@ -0,0 +1,330 @@
+use std::fmt::{Debug, Display, Formatter};
+
+/// The integers an [`Interval`] can hold.
+pub trait Integer: Copy + Ord + Debug + Display {
+    fn checked_next(self) -> Option<Self>;
+    fn checked_previous(self) -> Option<Self>;
+    /// How many integers `from..=to` holds, saturating for the full range of a 64-bit type.
+    fn count(from: Self, to: Self) -> u64;
+}
+
+macro_rules! integer {
+    ($($t:ty),*) => {
+        $(impl Integer for $t {
+            fn checked_next(self) -> Option<Self> {
+                self.checked_add(1)
+            }
+            fn checked_previous(self) -> Option<Self> {
+                self.checked_sub(1)
+            }
+            fn count(from: Self, to: Self) -> u64 {
+                (to.abs_diff(from) as u64).saturating_add(1)
+            }
+        })*
+    };
+}
+
+integer!(i32, i64, u32, u64, usize);
+
+/// Whether an interval ending at `end` and one starting at `start` after it leave no gap.
+fn touches<T: Integer>(end: T, start: T) -> bool {
+    end.checked_next().is_none_or(|next| next >= start)
+}
+
+/// The integers from `start` to `end`, both included.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Interval<T> {
+    start: T,
+    end: T,
+}
+
+impl<T: Integer> Interval<T> {
+    /// Panics if `start` is after `end`.
+    pub fn new(start: T, end: T) -> Self {
+        assert!(
+            start <= end,
+            "interval {}-{} ends before it starts",
+            start,
+            end
+        );
+        Self { start, end }
+    }
+    pub fn single(value: T) -> Self {
+        Self::new(value, value)
+    }
+    pub fn start(&self) -> T {
+        self.start
+    }
+    pub fn end(&self) -> T {
+        self.end
+    }
+    /// At least 1, as an interval is never empty.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u64 {
+        T::count(self.start, self.end)
+    }
+    pub fn contains(&self, value: T) -> bool {
+        self.start <= value && value <= self.end
+    }
+    pub fn contains_interval(&self, other: &Self) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        self.overlaps(other)
+            .then(|| Self::new(self.start.max(other.start), self.end.min(other.end)))
+    }
+}
+
+/// As in the puzzles, `2-4`.
+impl<T: Display> Display for Interval<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// A set of integers, stored as sorted intervals that neither overlap nor touch, so that every set
+/// has a single representation.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T> {
+    intervals: Vec<Interval<T>>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self { intervals: vec![] }
+    }
+}
+
+impl<T: Integer> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// In order.
+    pub fn intervals(&self) -> &[Interval<T>] {
+        &self.intervals
+    }
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+    /// How many integers the set holds.
+    pub fn len(&self) -> u64 {
+        self.intervals
+            .iter()
+            .fold(0, |len, interval| len.saturating_add(interval.len()))
+    }
+    pub fn min(&self) -> Option<T> {
+        self.intervals.first().map(|interval| interval.start)
+    }
+    pub fn max(&self) -> Option<T> {
+        self.intervals.last().map(|interval| interval.end)
+    }
+    pub fn insert(&mut self, interval: Interval<T>) {
+        // the intervals from `first` to `last` overlap or touch the new one
+        let first = self
+            .intervals
+            .partition_point(|i| !touches(i.end, interval.start));
+        let last = self
+            .intervals
+            .partition_point(|i| touches(interval.end, i.start));
+        let merged = match first < last {
+            true => Interval::new(
+                interval.start.min(self.intervals[first].start),
+                interval.end.max(self.intervals[last - 1].end),
+            ),
+            false => interval,
+        };
+        self.intervals.splice(first..last, [merged]);
+    }
+    pub fn remove(&mut self, interval: Interval<T>) {
+        let first = self.intervals.partition_point(|i| i.end < interval.start);
+        let last = self.intervals.partition_point(|i| i.start <= interval.end);
+        if first == last {
+            return;
+        }
+        let (start, end) = (self.intervals[first].start, self.intervals[last - 1].end);
+        let before = (start < interval.start)
+            .then(|| Interval::new(start, interval.start.checked_previous().unwrap()));
+        let after =
+            (interval.end < end).then(|| Interval::new(interval.end.checked_next().unwrap(), end));
+        self.intervals
+            .splice(first..last, before.into_iter().chain(after));
+    }
+    /// The interval holding `value`, if any.
+    fn find(&self, value: T) -> Option<&Interval<T>> {
+        let i = self.intervals.partition_point(|i| i.end < value);
+        self.intervals.get(i).filter(|i| i.start <= value)
+    }
+    pub fn contains(&self, value: T) -> bool {
+        self.find(value).is_some()
+    }
+    pub fn contains_interval(&self, interval: &Interval<T>) -> bool {
+        self.find(interval.start)
+            .is_some_and(|i| i.contains_interval(interval))
+    }
+    pub fn overlaps(&self, interval: &Interval<T>) -> bool {
+        let i = self.intervals.partition_point(|i| i.end < interval.start);
+        self.intervals.get(i).is_some_and(|i| i.overlaps(interval))
+    }
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.intervals
+            .iter()
+            .all(|interval| other.contains_interval(interval))
+    }
+    pub fn union(&self, other: &Self) -> Self {
+        self.intervals
+            .iter()
+            .chain(other.intervals.iter())
+            .copied()
+            .collect()
+    }
+    pub fn intersection(&self, other: &Self) -> Self {
+        let (mut a, mut b) = (
+            self.intervals.iter().peekable(),
+            other.intervals.iter().peekable(),
+        );
+        let mut intervals = vec![];
+        while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+            intervals.extend(x.intersection(y));
+            // the one ending first cannot overlap anything else of the other set
+            if x.end < y.end {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+        Self { intervals }
+    }
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut difference = self.clone();
+        for &interval in other.intervals.iter() {
+            difference.remove(interval);
+        }
+        difference
+    }
+    /// Everything in `bounds` that is not in the set.
+    pub fn complement(&self, bounds: Interval<T>) -> Self {
+        Self::from(bounds).difference(self)
+    }
+    /// The intervals missing between the first and last values of the set.
+    pub fn gaps(&self) -> impl Iterator<Item = Interval<T>> + '_ {
+        self.intervals.windows(2).map(|pair| {
+            Interval::new(
+                pair[0].end.checked_next().unwrap(),
+                pair[1].start.checked_previous().unwrap(),
+            )
+        })
+    }
+}
+
+impl<T: Integer> From<Interval<T>> for IntervalSet<T> {
+    fn from(interval: Interval<T>) -> Self {
+        Self {
+            intervals: vec![interval],
+        }
+    }
+}
+
+impl<T: Integer> FromIterator<Interval<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(intervals: I) -> Self {
+        let mut sorted: Vec<Interval<T>> = intervals.into_iter().collect();
+        sorted.sort();
+        let mut merged: Vec<Interval<T>> = Vec::with_capacity(sorted.len());
+        for interval in sorted {
+            match merged.last_mut() {
+                Some(last) if touches(last.end, interval.start) => {
+                    last.end = last.end.max(interval.end)
+                }
+                _ => merged.push(interval),
+            }
+        }
+        Self { intervals: merged }
+    }
+}
+
+/// Such as `2-4, 6-8`.
+impl<T: Display> Display for IntervalSet<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let intervals: Vec<String> = self.intervals.iter().map(|i| i.to_string()).collect();
+        write!(f, "{}", intervals.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(intervals: &[(i64, i64)]) -> IntervalSet<i64> {
+        intervals
+            .iter()
+            .map(|&(start, end)| Interval::new(start, end))
+            .collect()
+    }
+
+    #[test]
+    fn test1() {
+        let a = set(&[(5, 7), (1, 2), (3, 3), (10, 12), (11, 11)]);
+        assert_eq!("1-3, 5-7, 10-12", a.to_string());
+        assert_eq!(9, a.len());
+        assert_eq!((Some(1), Some(12)), (a.min(), a.max()));
+        assert!(a.contains(6) && !a.contains(4) && !a.contains(13));
+        assert!(a.contains_interval(&Interval::new(5, 7)));
+        assert!(!a.contains_interval(&Interval::new(3, 5)));
+        assert!(a.overlaps(&Interval::new(8, 10)) && !a.overlaps(&Interval::new(8, 9)));
+        assert_eq!(
+            "4-4, 8-9",
+            a.gaps()
+                .map(|g| g.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        let b = set(&[(2, 5), (9, 20)]);
+        assert_eq!("1-7, 9-20", a.union(&b).to_string());
+        assert_eq!("2-3, 5-5, 10-12", a.intersection(&b).to_string());
+        assert_eq!("1-1, 6-7", a.difference(&b).to_string());
+        assert_eq!(
+            "6-8",
+            b.complement(Interval::new(3, 10))
+                .difference(&set(&[(3, 4)]))
+                .to_string()
+        );
+        assert_eq!(
+            "0-0, 4-4, 8-9, 13-15",
+            a.complement(Interval::new(0, 15)).to_string()
+        );
+        assert!(a.intersection(&b).is_subset(&a) && !a.is_subset(&b));
+        assert_eq!(
+            a.len() + b.len(),
+            a.union(&b).len() + a.intersection(&b).len()
+        );
+
+        let mut c = a.clone();
+        c.insert(Interval::new(4, 4));
+        assert_eq!("1-7, 10-12", c.to_string());
+        c.insert(Interval::new(8, 9));
+        assert_eq!("1-12", c.to_string());
+        c.remove(Interval::single(1));
+        c.remove(Interval::new(5, 6));
+        c.remove(Interval::new(12, 20));
+        c.remove(Interval::new(-5, -1));
+        assert_eq!("2-4, 7-11", c.to_string());
+        c.insert(Interval::new(-3, -2));
+        assert_eq!("-3--2, 2-4, 7-11", c.to_string());
+        assert_eq!(set(&[(-3, -2), (2, 4), (7, 11)]), c);
+
+        let full: IntervalSet<u64> = IntervalSet::from(Interval::new(0, u64::MAX));
+        assert_eq!(u64::MAX, full.len());
+        let mut split = full.clone();
+        split.remove(Interval::single(u64::MAX));
+        split.insert(Interval::single(u64::MAX));
+        assert_eq!(full, split);
+        assert!(full.complement(Interval::new(3, 9)).is_empty());
+        assert_eq!(0, IntervalSet::<u64>::new().len());
+        assert!(Interval::new(2, 4)
+            .intersection(&Interval::new(5, 6))
+            .is_none());
+    }
+}
@@ -0,0 +1,86 @@
+//! Solutions for [Advent of Code 2022](https://adventofcode.com/2022).
+//!
+//! Every day is a module exposing its parsed model and a [`Solution`] with `parse`, `part1` and
+//! `part2`; the modules are also available under short aliases such as [`day16`].
+//!
+//! ```
+//! use advent_of_code_2022::day25::{FullOfHotAir, Snafu};
+//! use advent_of_code_2022::Solution;
+//!
+//! let numbers: Vec<Snafu> = FullOfHotAir::parse("1=-0-2\n12111").unwrap();
+//! assert_eq!("1-111=", FullOfHotAir::part1(&numbers).to_string());
+//! ```
+
+mod answers;
+mod bench;
+pub mod cancel;
+pub mod cli;
+pub mod day01_calorie_counting;
+pub mod day02_rock_paper_scissors;
+pub mod day03_rucksack_reorganization;
+pub mod day04_camp_cleanup;
+pub mod day05_supply_stacks;
+pub mod day06_tuning_trouble;
+pub mod day07_no_space_left_on_device;
+pub mod day08_treetop_tree_house;
+pub mod day09_rope_bridge;
+pub mod day10_cathode_ray_tube;
+pub mod day11_monkey_in_the_middle;
+pub mod day12_hill_climbing_algorithm;
+pub mod day13_distress_signal;
+pub mod day14_regolith_reservoir;
+pub mod day15_beacon_exclusion_zone;
+pub mod day16_proboscidea_volcanium;
+pub mod day17_pyroclastic_flow;
+pub mod day18_boiling_boulders;
+pub mod day19_not_enough_minerals;
+pub mod day20_grove_positioning_system;
+pub mod day21_monkey_math;
+pub mod day22_monkey_map;
+pub mod day23_unstable_diffusion;
+pub mod day24_blizzard_basin;
+pub mod day25_full_of_hot_air;
+pub mod differential;
+pub mod export;
+pub mod fuzz;
+pub mod generate;
+pub mod grid;
+pub mod image;
+pub mod interval;
+pub mod params;
+pub mod parse_error;
+pub mod progress;
+pub mod registry;
+pub mod repl;
+mod runner;
+pub mod search;
+pub mod solution;
+pub mod visualize;
+
+pub use day01_calorie_counting as day01;
+pub use day02_rock_paper_scissors as day02;
+pub use day03_rucksack_reorganization as day03;
+pub use day04_camp_cleanup as day04;
+pub use day05_supply_stacks as day05;
+pub use day06_tuning_trouble as day06;
+pub use day07_no_space_left_on_device as day07;
+pub use day08_treetop_tree_house as day08;
+pub use day09_rope_bridge as day09;
+pub use day10_cathode_ray_tube as day10;
+pub use day11_monkey_in_the_middle as day11;
+pub use day12_hill_climbing_algorithm as day12;
+pub use day13_distress_signal as day13;
+pub use day14_regolith_reservoir as day14;
+pub use day15_beacon_exclusion_zone as day15;
+pub use day16_proboscidea_volcanium as day16;
+pub use day17_pyroclastic_flow as day17;
+pub use day18_boiling_boulders as day18;
+pub use day19_not_enough_minerals as day19;
+pub use day20_grove_positioning_system as day20;
+pub use day21_monkey_math as day21;
+pub use day22_monkey_map as day22;
+pub use day23_unstable_diffusion as day23;
+pub use day24_blizzard_basin as day24;
+pub use day25_full_of_hot_air as day25;
+pub use parse_error::ParseError;
+pub use solution::{Answer, Solution};
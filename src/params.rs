@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+
+use crate::answers::day_key;
+use crate::registry::{self, Day};
+
+/// A named puzzle constant that can be changed without editing the day's source.
+#[derive(Debug)]
+pub struct Parameter {
+    pub name: &'static str,
+    pub default: DefaultValue,
+    pub description: &'static str,
+    /// Smallest value allowed, for integers and for every element of a list; text has no range.
+    pub min: i64,
+    /// Largest value allowed.
+    pub max: i64,
+}
+
+#[derive(Debug)]
+pub enum DefaultValue {
+    Integer(i64),
+    List(&'static [i64]),
+    Text(&'static str),
+}
+
+impl Parameter {
+    pub const fn integer(name: &'static str, default: i64, description: &'static str) -> Self {
+        Self {
+            name,
+            default: DefaultValue::Integer(default),
+            description,
+            min: i64::MIN,
+            max: i64::MAX,
+        }
+    }
+    pub const fn list(
+        name: &'static str,
+        default: &'static [i64],
+        description: &'static str,
+    ) -> Self {
+        Self {
+            name,
+            default: DefaultValue::List(default),
+            description,
+            min: i64::MIN,
+            max: i64::MAX,
+        }
+    }
+    pub const fn text(
+        name: &'static str,
+        default: &'static str,
+        description: &'static str,
+    ) -> Self {
+        Self {
+            name,
+            default: DefaultValue::Text(default),
+            description,
+            min: i64::MIN,
+            max: i64::MAX,
+        }
+    }
+    pub const fn range(self, min: i64, max: i64) -> Self {
+        Self { min, max, ..self }
+    }
+    pub fn default_value(&self) -> Value {
+        match self.default {
+            DefaultValue::Integer(n) => Value::Integer(n),
+            DefaultValue::List(values) => Value::List(values.to_vec()),
+            DefaultValue::Text(text) => Value::Text(text.to_string()),
+        }
+    }
+    /// Checks the kind and range of `value`; a single integer is accepted as a list of one, and
+    /// as text.
+    fn check(&self, value: Value) -> anyhow::Result<Value> {
+        let value = match (&self.default, value) {
+            (DefaultValue::List(_), Value::Integer(n)) => Value::List(vec![n]),
+            (DefaultValue::Text(_), Value::Integer(n)) => Value::Text(n.to_string()),
+            (DefaultValue::Integer(_), Value::List(_)) => {
+                bail!("parameter '{}' expects a single integer", self.name)
+            }
+            (DefaultValue::Integer(_) | DefaultValue::List(_), Value::Text(text)) => {
+                bail!("parameter '{}' expects integers, got '{}'", self.name, text)
+            }
+            (DefaultValue::Text(_), Value::List(_)) => {
+                bail!("parameter '{}' expects text", self.name)
+            }
+            (_, value) => value,
+        };
+        let values = match &value {
+            Value::Integer(n) => std::slice::from_ref(n),
+            Value::List(values) => &values[..],
+            Value::Text(_) => &[],
+        };
+        if let Some(n) = values.iter().find(|n| !(self.min..=self.max).contains(n)) {
+            bail!(
+                "parameter '{}' must be between {} and {}, got {}",
+                self.name,
+                self.min,
+                self.max,
+                n
+            );
+        }
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    Integer(i64),
+    List(Vec<i64>),
+    Text(String),
+}
+
+impl FromStr for Value {
+    type Err = anyhow::Error;
+
+    /// `10` is an integer, `20,60,100` a list, and anything else text, such as `spock.toml`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |n: &str| n.trim().replace('_', "").parse::<i64>();
+        let value = if s.contains(',') {
+            s.split(',')
+                .map(parse)
+                .collect::<Result<_, _>>()
+                .map(Value::List)
+        } else {
+            parse(s).map(Value::Integer)
+        };
+        Ok(value.unwrap_or_else(|_| Value::Text(s.to_string())))
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Integer(n) => write!(f, "{}", n),
+            Value::List(values) => {
+                let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                write!(f, "{}", values.join(","))
+            }
+            Value::Text(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+/// The value of every parameter of a day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Params(BTreeMap<&'static str, Value>);
+
+impl Params {
+    pub fn defaults(parameters: &'static [Parameter]) -> Self {
+        Self(
+            parameters
+                .iter()
+                .map(|p| (p.name, p.default_value()))
+                .collect(),
+        )
+    }
+    /// Panics if the day doesn't declare `name` as an integer parameter.
+    pub fn integer(&self, name: &str) -> i64 {
+        match self.0.get(name) {
+            Some(Value::Integer(n)) => *n,
+            _ => panic!("no integer parameter '{}'", name),
+        }
+    }
+    /// Panics if the day doesn't declare `name` as a list parameter.
+    pub fn list(&self, name: &str) -> &[i64] {
+        match self.0.get(name) {
+            Some(Value::List(values)) => values,
+            _ => panic!("no list parameter '{}'", name),
+        }
+    }
+    /// Panics if the day doesn't declare `name` as a text parameter.
+    pub fn text(&self, name: &str) -> &str {
+        match self.0.get(name) {
+            Some(Value::Text(text)) => text,
+            _ => panic!("no text parameter '{}'", name),
+        }
+    }
+}
+
+impl Display for Params {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let values: Vec<String> = self
+            .0
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        write!(f, "{}", values.join(", "))
+    }
+}
+
+/// Parameter values that differ from the defaults, stored as one `[dayNN]` table per day.
+#[derive(Debug, Default)]
+pub(crate) struct Overrides(BTreeMap<u8, BTreeMap<String, Value>>);
+
+impl Overrides {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read parameters file {}", path.display()))?;
+        Self::from_toml(&contents)
+            .with_context(|| format!("invalid parameters file {}", path.display()))
+    }
+    fn from_toml(contents: &str) -> anyhow::Result<Self> {
+        let tables: BTreeMap<String, BTreeMap<String, Value>> = toml::from_str(contents)?;
+        let mut overrides = Self::default();
+        for (key, values) in tables {
+            let Some(day) = registry::all().iter().find(|d| day_key(d.number) == key) else {
+                bail!("unknown table [{}], expected [day01] to [day25]", key);
+            };
+            for (name, value) in values {
+                overrides.set(day, name, value);
+            }
+        }
+        Ok(overrides)
+    }
+    pub fn set(&mut self, day: &Day, name: String, value: Value) {
+        self.0.entry(day.number).or_default().insert(name, value);
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    /// The defaults of `day` with the overrides applied.
+    pub fn for_day(&self, day: &Day) -> anyhow::Result<Params> {
+        let parameters = day.solver.parameters();
+        let mut params = Params::defaults(parameters);
+        for (name, value) in self.0.get(&day.number).into_iter().flatten() {
+            let Some(parameter) = parameters.iter().find(|p| p.name == name) else {
+                bail!(
+                    "day {} has no parameter '{}'{}",
+                    day.number,
+                    name,
+                    match parameters.len() {
+                        0 => " (it has none)".to_string(),
+                        _ => format!(
+                            " (expected one of {})",
+                            parameters
+                                .iter()
+                                .map(|p| p.name)
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    }
+                );
+            };
+            params
+                .0
+                .insert(parameter.name, parameter.check(value.clone())?);
+        }
+        Ok(params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        assert_eq!(Value::Integer(2_000_000), "2_000_000".parse().unwrap());
+        assert_eq!(Value::List(vec![20, 60]), "20, 60".parse().unwrap());
+        assert_eq!("20,60", Value::List(vec![20, 60]).to_string());
+        assert_eq!(Value::Text("20,x".to_string()), "20,x".parse().unwrap());
+
+        let day15 = registry::find("15").unwrap();
+        let defaults = Overrides::default().for_day(day15).unwrap();
+        assert_eq!(2_000_000, defaults.integer("row"));
+        assert_eq!("bound=4000000, row=2000000", defaults.to_string());
+
+        let mut overrides =
+            Overrides::from_toml("[day15]\nrow = 10\n\n[day10]\ncycles = [1, 2]").unwrap();
+        overrides.set(day15, "bound".to_string(), Value::Integer(20));
+        let params = overrides.for_day(day15).unwrap();
+        assert_eq!((10, 20), (params.integer("row"), params.integer("bound")));
+        let day10 = registry::find("10").unwrap();
+        assert_eq!(&[1, 2], overrides.for_day(day10).unwrap().list("cycles"));
+        overrides.set(day10, "cycles".to_string(), Value::Integer(5));
+        assert_eq!(&[5], overrides.for_day(day10).unwrap().list("cycles"));
+
+        overrides.set(day15, "bound".to_string(), Value::List(vec![1, 2]));
+        assert!(overrides.for_day(day15).is_err());
+        overrides.set(day15, "bound".to_string(), Value::Integer(-1));
+        assert!(overrides.for_day(day15).is_err());
+        overrides.set(day15, "rows".to_string(), Value::Integer(1));
+        assert!(overrides.for_day(day15).is_err());
+        assert!(Overrides::from_toml("[day26]\nrow = 1").is_err());
+        let mut typo = Overrides::from_toml("[day15]\nrow = \"1O\"").unwrap();
+        assert_eq!(
+            "parameter 'row' expects integers, got '1O'",
+            typo.for_day(day15).unwrap_err().to_string()
+        );
+
+        let day02 = registry::find("2").unwrap();
+        assert_eq!("", typo.for_day(day02).unwrap().text("rules"));
+        typo.set(day02, "rules".to_string(), "spock.toml".parse().unwrap());
+        assert_eq!("spock.toml", typo.for_day(day02).unwrap().text("rules"));
+        typo.set(day02, "rules".to_string(), Value::List(vec![1, 2]));
+        assert!(typo.for_day(day02).is_err());
+    }
+}
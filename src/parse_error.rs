@@ -0,0 +1,127 @@
+use std::str::FromStr;
+
+use thiserror::Error;
+
+/// A malformed puzzle input, pointing at the offending text.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("day {day}, line {line}, column {column}: {message}: '{text}'")]
+pub struct ParseError {
+    pub day: u8,
+    /// 1-based line of `text`, relative to the string that was parsed.
+    pub line: usize,
+    /// 1-based column of `text`, counted in characters.
+    pub column: usize,
+    pub text: String,
+    pub message: String,
+}
+
+impl ParseError {
+    /// `text` should be a slice of `source` so that its position can be worked out; otherwise the
+    /// error points at the start of `source`.
+    pub fn new(day: u8, source: &str, text: &str, message: impl Into<String>) -> Self {
+        let (line, column) = position(source, text).unwrap_or((1, 1));
+        Self {
+            day,
+            line,
+            column,
+            text: text.to_string(),
+            message: message.into(),
+        }
+    }
+    /// Moves an error returned while parsing `inner`, a slice of `outer`, so that its position is
+    /// relative to `outer`.
+    pub fn within(mut self, outer: &str, inner: &str) -> Self {
+        if let Some((line, column)) = position(outer, inner) {
+            if self.line == 1 {
+                self.column += column - 1;
+            }
+            self.line += line - 1;
+        }
+        self
+    }
+}
+
+/// 1-based line and column of `inner` in `outer`, if `inner` is a slice of `outer`.
+fn position(outer: &str, inner: &str) -> Option<(usize, usize)> {
+    let start = (inner.as_ptr() as usize).checked_sub(outer.as_ptr() as usize)?;
+    if start + inner.len() > outer.len() {
+        return None;
+    }
+    let before = &outer[..start];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Some((line, before[line_start..].chars().count() + 1))
+}
+
+/// Parses every line of `input`, with errors positioned relative to `input`.
+pub fn parse_lines<T: FromStr<Err = ParseError>>(input: &str) -> Result<Vec<T>, ParseError> {
+    input
+        .lines()
+        .map(|line| line.parse().map_err(|e: ParseError| e.within(input, line)))
+        .collect()
+}
+
+/// An error for a character parsed on its own; [`ParseError::within`] moves it to where the
+/// character was found.
+pub fn invalid_char(day: u8, value: char, message: &str) -> ParseError {
+    let text = value.to_string();
+    ParseError::new(day, &text, &text, message)
+}
+
+/// Parses `text`, a slice of `source`, as a number.
+pub fn parse_number<T: FromStr>(day: u8, source: &str, text: &str) -> Result<T, ParseError> {
+    text.parse()
+        .map_err(|_| ParseError::new(day, source, text, "invalid number"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        #[derive(Debug)]
+        struct Pair(u32, u32);
+        impl FromStr for Pair {
+            type Err = ParseError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let (a, b) = s
+                    .split_once(',')
+                    .ok_or_else(|| ParseError::new(0, s, s, "expected 'a,b'"))?;
+                Ok(Self(parse_number(0, s, a)?, parse_number(0, s, b)?))
+            }
+        }
+
+        let input = "1,2\n3,4\n5,x6\n";
+        let pairs = parse_lines::<Pair>("1,2\n3,4").unwrap();
+        assert_eq!(
+            vec![(1, 2), (3, 4)],
+            pairs.iter().map(|p| (p.0, p.1)).collect::<Vec<_>>()
+        );
+        let error = parse_lines::<Pair>(input).unwrap_err();
+        assert_eq!(
+            (3, 3, "x6"),
+            (error.line, error.column, error.text.as_str())
+        );
+        assert_eq!(
+            "day 0, line 3, column 3: invalid number: 'x6'",
+            error.to_string()
+        );
+
+        let error = parse_lines::<Pair>("1,2\n  34").unwrap_err();
+        assert_eq!(
+            (2, 1, "  34"),
+            (error.line, error.column, error.text.as_str())
+        );
+
+        let outer = "header\n\nab,cd";
+        let inner = &outer[8..];
+        let error = ParseError::new(0, inner, &inner[3..], "bad").within(outer, inner);
+        assert_eq!((3, 4), (error.line, error.column));
+
+        let source = "é,x";
+        let error = ParseError::new(0, source, &source[3..], "bad");
+        assert_eq!((1, 3), (error.line, error.column));
+    }
+}
@@ -0,0 +1,326 @@
+use std::cell::Cell;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use log::info;
+
+use crate::runner::format_duration;
+
+/// Where the long searches report their progress.
+pub trait Sink: Send + Sync {
+    fn report(&self, report: &Report);
+}
+
+static SINK: RwLock<Option<Arc<dyn Sink>>> = RwLock::new(None);
+
+/// Sends the progress of every search started from now on to `sink`, or nowhere, the default.
+pub fn set_sink(sink: Option<Arc<dyn Sink>>) {
+    *SINK.write().unwrap() = sink;
+}
+
+/// The least time between two reports of the same search.
+const INTERVAL: Duration = Duration::from_millis(200);
+
+/// How far a search has got.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report<'a> {
+    pub task: &'a str,
+    pub explored: u64,
+    pub elapsed: Duration,
+    /// Out of `total`, for the searches that know how much work there is.
+    pub done: u64,
+    pub total: Option<u64>,
+    pub finished: bool,
+}
+
+impl Report<'_> {
+    pub fn per_second(&self) -> f64 {
+        self.explored as f64 / self.elapsed.as_secs_f64().max(1e-9)
+    }
+    pub fn fraction(&self) -> Option<f64> {
+        self.total
+            .filter(|&total| total > 0)
+            .map(|total| (self.done as f64 / total as f64).min(1.0))
+    }
+    /// Time left, if the rest goes as fast as what is done.
+    pub fn eta(&self) -> Option<Duration> {
+        let fraction = self.fraction().filter(|&f| f > 0.0)?;
+        Some(self.elapsed.mul_f64((1.0 - fraction) / fraction))
+    }
+}
+
+impl Display for Report<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} states, {}/s",
+            self.task,
+            abbreviated(self.explored as f64),
+            abbreviated(self.per_second())
+        )?;
+        if self.finished {
+            return write!(f, ", done in {}", format_duration(self.elapsed));
+        }
+        if let Some(fraction) = self.fraction() {
+            write!(f, ", {:.0}%", fraction * 100.0)?;
+        }
+        if let Some(eta) = self.eta() {
+            write!(f, ", eta {}", format_duration(eta))?;
+        }
+        Ok(())
+    }
+}
+
+/// `1234567` as `1.23M`.
+fn abbreviated(n: f64) -> String {
+    match n {
+        n if n >= 1e9 => format!("{:.2}G", n / 1e9),
+        n if n >= 1e6 => format!("{:.2}M", n / 1e6),
+        n if n >= 1e3 => format!("{:.1}k", n / 1e3),
+        n => format!("{:.0}", n),
+    }
+}
+
+/// Redraws a single line on stderr, with a bar when the search knows its total.
+pub struct Bar;
+
+impl Sink for Bar {
+    fn report(&self, report: &Report) {
+        const WIDTH: usize = 20;
+        let bar = match report.fraction() {
+            Some(fraction) => {
+                let filled = (fraction * WIDTH as f64).round() as usize;
+                format!("[{}{}] ", "#".repeat(filled), "-".repeat(WIDTH - filled))
+            }
+            None => String::new(),
+        };
+        let mut stderr = std::io::stderr().lock();
+        // \x1b[K clears whatever a longer line left behind
+        let _ = write!(stderr, "\r{}{}\x1b[K", bar, report);
+        if report.finished {
+            let _ = writeln!(stderr);
+        }
+        let _ = stderr.flush();
+    }
+}
+
+/// Logs every report at info level under the `progress` target, as `key=value` pairs.
+pub struct Log;
+
+impl Sink for Log {
+    fn report(&self, report: &Report) {
+        let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        info!(
+            target: "progress",
+            "task=\"{}\" explored={} per_second={:.0} done={} total={} eta_secs={} finished={}",
+            report.task,
+            report.explored,
+            report.per_second(),
+            report.done,
+            optional(report.total.map(|t| t.to_string())),
+            optional(report.eta().map(|eta| format!("{:.1}", eta.as_secs_f64()))),
+            report.finished
+        );
+    }
+}
+
+/// Counts what a search explores and reports it to the sink every so often, and once more when
+/// dropped. Without a sink, counting returns straight away, though the searches that explore
+/// millions of states should still count them in a [`Batch`].
+pub struct Progress {
+    task: String,
+    sink: Option<Arc<dyn Sink>>,
+    started: Instant,
+    explored: AtomicU64,
+    done: AtomicU64,
+    total: Option<u64>,
+    /// Nanoseconds from `started` to the last report.
+    reported: AtomicU64,
+}
+
+impl Progress {
+    /// Reports to the sink of [`set_sink`].
+    pub fn new(task: impl Into<String>) -> Self {
+        Self::with_sink(task, SINK.read().unwrap().clone())
+    }
+    pub fn with_sink(task: impl Into<String>, sink: Option<Arc<dyn Sink>>) -> Self {
+        Self {
+            task: task.into(),
+            sink,
+            started: Instant::now(),
+            explored: AtomicU64::new(0),
+            done: AtomicU64::new(0),
+            total: None,
+            reported: AtomicU64::new(0),
+        }
+    }
+    pub fn with_total(mut self, total: u64) -> Self {
+        self.total = Some(total);
+        self
+    }
+    pub fn explored(&self, states: u64) {
+        if self.sink.is_none() {
+            return;
+        }
+        self.explored.fetch_add(states, Ordering::Relaxed);
+        let now = self.started.elapsed().as_nanos() as u64;
+        let reported = self.reported.load(Ordering::Relaxed);
+        // only one thread reports each time
+        if now - reported >= INTERVAL.as_nanos() as u64
+            && self
+                .reported
+                .compare_exchange(reported, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            self.report(false);
+        }
+    }
+    /// Progress towards the total only goes forward: a smaller `done` is ignored.
+    pub fn set_done(&self, done: u64) {
+        if self.sink.is_none() {
+            return;
+        }
+        self.done.fetch_max(done, Ordering::Relaxed);
+    }
+    pub fn done(&self) -> u64 {
+        self.done.load(Ordering::Relaxed)
+    }
+    pub fn total(&self) -> Option<u64> {
+        self.total
+    }
+    fn report(&self, finished: bool) {
+        if let Some(sink) = &self.sink {
+            sink.report(&Report {
+                task: &self.task,
+                explored: self.explored.load(Ordering::Relaxed),
+                elapsed: self.started.elapsed(),
+                done: self.done(),
+                total: self.total,
+                finished,
+            });
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.report(true);
+    }
+}
+
+/// Counts the states of a single-threaded search and hands them to a [`Progress`]
+/// [`Batch::SIZE`] at a time, the rest when dropped.
+pub struct Batch<'a> {
+    progress: &'a Progress,
+    pending: Cell<u64>,
+}
+
+impl<'a> Batch<'a> {
+    pub const SIZE: u64 = 1024;
+
+    pub fn new(progress: &'a Progress) -> Self {
+        Self {
+            progress,
+            pending: Cell::new(0),
+        }
+    }
+    pub fn progress(&self) -> &'a Progress {
+        self.progress
+    }
+    /// Counts one state, and returns whether that completed a batch, which the searches also use
+    /// to do their other periodic checks.
+    pub fn explored(&self) -> bool {
+        let pending = self.pending.get() + 1;
+        if pending < Self::SIZE {
+            self.pending.set(pending);
+            return false;
+        }
+        self.pending.set(0);
+        self.progress.explored(pending);
+        true
+    }
+}
+
+impl Drop for Batch<'_> {
+    fn drop(&mut self) {
+        self.progress.explored(self.pending.get());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorded(Mutex<Vec<(u64, u64, bool)>>);
+
+    impl Sink for Recorded {
+        fn report(&self, report: &Report) {
+            let report = (report.explored, report.done, report.finished);
+            self.0.lock().unwrap().push(report);
+        }
+    }
+
+    #[test]
+    fn test1() {
+        let report = Report {
+            task: "day 19",
+            explored: 1_500_000,
+            elapsed: Duration::from_secs(3),
+            done: 1,
+            total: Some(4),
+            finished: false,
+        };
+        assert_eq!(500_000.0, report.per_second());
+        assert_eq!(Some(Duration::from_secs(9)), report.eta());
+        assert_eq!(
+            "day 19: 1.50M states, 500.0k/s, 25%, eta 9.000 s",
+            report.to_string()
+        );
+        let finished = Report {
+            finished: true,
+            total: None,
+            ..report
+        };
+        assert_eq!(None, finished.eta());
+        assert_eq!(
+            "day 19: 1.50M states, 500.0k/s, done in 3.000 s",
+            finished.to_string()
+        );
+
+        let sink = Arc::new(Recorded::default());
+        let progress = Progress::with_sink("test", Some(sink.clone())).with_total(10);
+        progress.explored(5);
+        progress.set_done(3);
+        progress.set_done(2);
+        assert_eq!(3, progress.done());
+        assert!(sink.0.lock().unwrap().is_empty());
+        drop(progress);
+        assert_eq!(vec![(5, 3, true)], *sink.0.lock().unwrap());
+
+        let quiet = Progress::new("nowhere");
+        quiet.explored(1 << 20);
+        quiet.set_done(5);
+        assert_eq!(0, quiet.done());
+        drop(quiet);
+
+        let sink = Arc::new(Recorded::default());
+        let progress = Progress::with_sink("test", Some(sink.clone()));
+        let batch = Batch::new(&progress);
+        let completed = (0..Batch::SIZE * 2 + 5)
+            .filter(|_| batch.explored())
+            .count();
+        assert_eq!(2, completed);
+        drop(batch);
+        drop(progress);
+        assert_eq!(
+            vec![(Batch::SIZE * 2 + 5, 0, true)],
+            *sink.0.lock().unwrap()
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use crate::solution::Solver;
+
+pub struct Day {
+    pub number: u8,
+    module: &'static str,
+    pub solver: &'static dyn Solver,
+}
+
+impl Day {
+    /// The puzzle name, e.g. `calorie_counting` for day 1.
+    pub fn name(&self) -> &'static str {
+        self.module.split_once('_').unwrap().1
+    }
+    pub fn input_file_name(&self) -> String {
+        format!("input{}.txt", self.number)
+    }
+}
+
+macro_rules! days {
+    ($($number:literal => $module:ident :: $solution:ident,)*) => {
+        static DAYS: &[Day] = &[$(Day {
+            number: $number,
+            module: stringify!($module),
+            solver: &crate::$module::$solution,
+        },)*];
+    };
+}
+
+days! {
+    1 => day01_calorie_counting::CalorieCounting,
+    2 => day02_rock_paper_scissors::RockPaperScissors,
+    3 => day03_rucksack_reorganization::RucksackReorganization,
+    4 => day04_camp_cleanup::CampCleanup,
+    5 => day05_supply_stacks::SupplyStacks,
+    6 => day06_tuning_trouble::TuningTrouble,
+    7 => day07_no_space_left_on_device::NoSpaceLeftOnDevice,
+    8 => day08_treetop_tree_house::TreetopTreeHouse,
+    9 => day09_rope_bridge::RopeBridge,
+    10 => day10_cathode_ray_tube::CathodeRayTube,
+    11 => day11_monkey_in_the_middle::MonkeyInTheMiddle,
+    12 => day12_hill_climbing_algorithm::HillClimbingAlgorithm,
+    13 => day13_distress_signal::DistressSignal,
+    14 => day14_regolith_reservoir::RegolithReservoir,
+    15 => day15_beacon_exclusion_zone::BeaconExclusionZone,
+    16 => day16_proboscidea_volcanium::ProboscideaVolcanium,
+    17 => day17_pyroclastic_flow::PyroclasticFlow,
+    18 => day18_boiling_boulders::BoilingBoulders,
+    19 => day19_not_enough_minerals::NotEnoughMinerals,
+    20 => day20_grove_positioning_system::GrovePositioningSystem,
+    21 => day21_monkey_math::MonkeyMath,
+    22 => day22_monkey_map::MonkeyMap,
+    23 => day23_unstable_diffusion::UnstableDiffusion,
+    24 => day24_blizzard_basin::BlizzardBasin,
+    25 => day25_full_of_hot_air::FullOfHotAir,
+}
+
+pub fn all() -> &'static [Day] {
+    DAYS
+}
+
+pub fn latest() -> &'static Day {
+    DAYS.last().unwrap()
+}
+
+/// Finds a day by its number (`7`, `07`) or its name (`no_space_left_on_device`,
+/// `no-space-left-on-device`).
+pub fn find(key: &str) -> Option<&'static Day> {
+    if let Ok(number) = key.parse::<u8>() {
+        return DAYS.iter().find(|d| d.number == number);
+    }
+    let key = key.replace('-', "_");
+    DAYS.iter().find(|d| d.name() == key || d.module == key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        assert_eq!(25, all().len());
+        assert_eq!(25, latest().number);
+        assert_eq!(7, find("7").unwrap().number);
+        assert_eq!(7, find("07").unwrap().number);
+        assert_eq!(16, find("proboscidea_volcanium").unwrap().number);
+        assert_eq!(16, find("proboscidea-volcanium").unwrap().number);
+        assert_eq!(16, find("day16_proboscidea_volcanium").unwrap().number);
+        assert!(find("26").is_none());
+        assert!(find("unknown").is_none());
+    }
+}
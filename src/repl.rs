@@ -0,0 +1,168 @@
+use std::io::{BufRead, IsTerminal, Write};
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context};
+
+use crate::cli::ReplOptions;
+use crate::params::Params;
+use crate::solution::Parsed;
+
+/// Commands every day understands, besides the ones of its [`Explore`].
+const COMMANDS: &[(&str, &str)] = &[
+    ("part1", "solve part 1"),
+    ("part2", "solve part 2"),
+    ("help", "list the commands"),
+    ("quit", "leave the REPL"),
+];
+
+/// Commands for looking into a day's parsed puzzle model.
+pub trait Explore {
+    /// The usage and description of every command, e.g. `("step [rounds]", "...")`.
+    fn commands(&self) -> &'static [(&'static str, &'static str)];
+    /// What `command` prints, or `None` if the day has no such command.
+    fn run(&mut self, command: &str, args: &[&str]) -> Option<anyhow::Result<String>>;
+}
+
+/// The argument at `index` of a command, named `name` in errors.
+pub fn argument<T: FromStr>(args: &[&str], index: usize, name: &str) -> anyhow::Result<T> {
+    let arg = args
+        .get(index)
+        .with_context(|| format!("missing <{}>", name))?;
+    match arg.parse() {
+        Ok(value) => Ok(value),
+        Err(_) => bail!("invalid {} '{}'", name, arg),
+    }
+}
+
+/// Like [`argument`], with `default` when the command was given fewer arguments.
+pub fn optional_argument<T: FromStr>(
+    args: &[&str],
+    index: usize,
+    name: &str,
+    default: T,
+) -> anyhow::Result<T> {
+    match args.get(index) {
+        Some(_) => argument(args, index, name),
+        None => Ok(default),
+    }
+}
+
+fn help(explore: Option<&dyn Explore>) -> String {
+    let commands = explore.map_or(&[][..], |e| e.commands());
+    commands
+        .iter()
+        .chain(COMMANDS)
+        .map(|(usage, description)| format!("  {:<20} {}", usage, description))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Reads commands from `input` until it ends or `quit`, writing what each one prints to
+/// `output`. The prompt is only shown when there is one, so piped commands print nothing else.
+fn session(
+    parsed: &dyn Parsed,
+    params: &Params,
+    input: impl BufRead,
+    mut output: impl Write,
+    prompt: Option<&str>,
+) -> anyhow::Result<()> {
+    let mut explore = parsed.explore();
+    let mut lines = input.lines();
+    loop {
+        if let Some(prompt) = prompt {
+            write!(output, "{}", prompt)?;
+            output.flush()?;
+        }
+        let Some(line) = lines.next() else {
+            break;
+        };
+        let line = line?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = words.split_first() else {
+            continue;
+        };
+        let result = match command {
+            "quit" | "exit" => break,
+            "help" => Ok(help(explore.as_deref())),
+            "part1" => parsed.part1(params).map(|answer| answer.to_string()),
+            "part2" => parsed.part2(params).map(|answer| answer.to_string()),
+            _ => match explore.as_mut().and_then(|e| e.run(command, args)) {
+                Some(result) => result,
+                None => Err(anyhow!("unknown command '{}', try help", command)),
+            },
+        };
+        match result {
+            Ok(text) => writeln!(output, "{}", text)?,
+            Err(error) => writeln!(output, "error: {:#}", error)?,
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn run(options: &ReplOptions) -> anyhow::Result<()> {
+    let day = options.day;
+    let input = options.source.read(day)?;
+    let parsed = day.solver.parse(&input)?;
+    let stdin = std::io::stdin();
+    let prompt = stdin.is_terminal().then(|| {
+        println!(
+            "day {} ({}), type help for the commands",
+            day.number,
+            day.name()
+        );
+        format!("day {}> ", day.number)
+    });
+    session(
+        &*parsed,
+        &options.params,
+        stdin.lock(),
+        std::io::stdout().lock(),
+        prompt.as_deref(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::registry;
+
+    fn transcript(day: u8, input: &str, commands: &str) -> String {
+        let day = registry::find(&day.to_string()).unwrap();
+        let parsed = day.solver.parse(input).unwrap();
+        let params = Params::defaults(day.solver.parameters());
+        let mut output = vec![];
+        session(&*parsed, &params, Cursor::new(commands), &mut output, None).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test1() {
+        let output = transcript(1, "1\n2\n\n4", "part1\n\n  part2 \nfly\nquit\npart1");
+        assert_eq!("4\n7\nerror: unknown command 'fly', try help\n", output);
+        assert!(transcript(1, "1", "help").contains("part1"));
+
+        let input = ".....\n..##.\n..#..\n.....\n..##.\n.....";
+        let output = transcript(23, input, "step 3\nempty\nstep 100\nstep x");
+        assert_eq!(
+            "round 3\n10v / 10>\n..#..\n....#\n#....\n....#\n.....\n..#..\n\
+             25\n\
+             round 4: no elf moved\n10v / 10>\n..#..\n....#\n#....\n....#\n.....\n..#..\n\
+             error: invalid rounds 'x'\n",
+            output
+        );
+        assert!(transcript(23, input, "help").contains("step [rounds]"));
+
+        let input = "[1,1,3,1,1]\n[1,1,5,1,1]\n\n[9]\n[[8,7,6]]";
+        assert_eq!(
+            "   [9]\n-->[[8,7,6]]\npair 2 is not in the right order\nerror: there are 2 pairs\n",
+            transcript(13, input, "compare 2\ncompare 3")
+        );
+        let input = "root: a + humn\na: 4\nhumn: 5";
+        assert_eq!(
+            "humn: 5 = 5\nroot: a + humn = 9\nerror: no monkey named b\n",
+            transcript(21, input, "path humn root\nmonkey b")
+        );
+    }
+}
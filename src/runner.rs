@@ -0,0 +1,619 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use anyhow::bail;
+use rayon::prelude::*;
+use rayon::ThreadPool;
+use serde::Serialize;
+
+use crate::answers::{self, Answers, Status};
+use crate::cancel::{Cancellation, Outcome};
+use crate::cli::{AnswersMode, RunOptions};
+use crate::params::Params;
+use crate::registry::Day;
+use crate::solution::Answer;
+
+pub(crate) struct DayRun {
+    pub day: &'static Day,
+    pub parse_time: Duration,
+    pub parts: Vec<PartRun>,
+}
+
+pub(crate) struct PartRun {
+    pub part: u8,
+    /// The error of a part whose input has no answer does not stop the other part.
+    pub answer: anyhow::Result<Answer>,
+    pub time: Duration,
+    /// Stopped at the timeout, so `answer` is only the best found by then.
+    pub timed_out: bool,
+}
+
+/// A day whose input could not be read or parsed.
+pub(crate) type DayError = (&'static Day, anyhow::Error);
+
+impl DayRun {
+    pub fn part(&self, part: u8) -> Option<&PartRun> {
+        self.parts.iter().find(|p| p.part == part)
+    }
+    pub fn total_time(&self) -> Duration {
+        self.parse_time + self.parts.iter().map(|p| p.time).sum::<Duration>()
+    }
+    pub fn failed(&self) -> bool {
+        self.parts.iter().any(|p| p.answer.is_err())
+    }
+}
+
+/// Parses `input` and solves the requested parts in parallel, timing each phase separately. Each
+/// part gets `timeout` to finish, if any. Only a parse error fails the whole day.
+pub(crate) fn run_day(
+    day: &'static Day,
+    input: &str,
+    parts: &[u8],
+    params: &Params,
+    timeout: Option<Duration>,
+) -> anyhow::Result<DayRun> {
+    let start = Instant::now();
+    let parsed = day.solver.parse(input)?;
+    let parse_time = start.elapsed();
+    let parts = parts
+        .par_iter()
+        .map(|&part| {
+            let start = Instant::now();
+            let cancellation = timeout.map_or_else(Cancellation::new, Cancellation::with_timeout);
+            let outcome = match part {
+                1 => parsed.part1_until(params, &cancellation),
+                _ => parsed.part2_until(params, &cancellation),
+            };
+            PartRun {
+                part,
+                timed_out: outcome.as_ref().is_ok_and(|o| !o.is_finished()),
+                answer: outcome.map(Outcome::value),
+                time: start.elapsed(),
+            }
+        })
+        .collect();
+    Ok(DayRun {
+        day,
+        parse_time,
+        parts,
+    })
+}
+
+/// Runs every day of `options` on `pool`, handing the results to `on_result` in day order as soon
+/// as a day and all the days before it have finished.
+fn run_days_in_order(
+    options: &RunOptions,
+    pool: &ThreadPool,
+    mut on_result: impl FnMut(&'static Day, anyhow::Result<DayRun>),
+) {
+    let (sender, receiver) = mpsc::channel();
+    pool.in_place_scope(|scope| {
+        for (i, &day) in options.days.iter().enumerate() {
+            let sender = sender.clone();
+            scope.spawn(move |_| {
+                let result = options.source.read(day).and_then(|input| {
+                    let params = options.params.for_day(day)?;
+                    run_day(day, &input, &options.parts, &params, options.timeout)
+                });
+                // the receiver outlives the scope, so this cannot fail
+                let _ = sender.send((i, result));
+            });
+        }
+        drop(sender);
+
+        let mut finished = BTreeMap::new();
+        let mut next = 0;
+        for (i, result) in receiver {
+            finished.insert(i, result);
+            while let Some(result) = finished.remove(&next) {
+                on_result(options.days[next], result);
+                next += 1;
+            }
+        }
+    });
+}
+
+fn thread_pool(options: &RunOptions) -> anyhow::Result<ThreadPool> {
+    Ok(rayon::ThreadPoolBuilder::new()
+        .num_threads(options.jobs.unwrap_or(0))
+        .build()?)
+}
+
+pub(crate) fn format_duration(duration: Duration) -> String {
+    if duration < Duration::from_secs(1) {
+        format!("{:.3} ms", duration.as_secs_f64() * 1000.0)
+    } else {
+        format!("{:.3} s", duration.as_secs_f64())
+    }
+}
+
+/// Rows are printed as each day finishes, so the columns have fixed widths.
+pub(crate) struct TimingTable;
+
+impl TimingTable {
+    pub fn header() -> String {
+        format!(
+            "{:>3}  {:<24}  {:>10}  {:<20}  {:>10}  {:<20}  {:>10}  {:>10}",
+            "day", "name", "parse", "part 1", "time", "part 2", "time", "total"
+        )
+    }
+    pub fn row(run: &DayRun) -> String {
+        let part = |part: u8| match run.part(part) {
+            Some(p) => (table_answer(p), format_duration(p.time)),
+            None => (String::new(), String::new()),
+        };
+        let (answer1, time1) = part(1);
+        let (answer2, time2) = part(2);
+        format!(
+            "{:>3}  {:<24}  {:>10}  {:<20}  {:>10}  {:<20}  {:>10}  {:>10}",
+            run.day.number,
+            run.day.name(),
+            format_duration(run.parse_time),
+            answer1,
+            time1,
+            answer2,
+            time2,
+            format_duration(run.total_time())
+        )
+    }
+    pub fn error_row(day: &Day, error: &anyhow::Error) -> String {
+        format!("{:>3}  {:<24}  error: {:#}", day.number, day.name(), error)
+    }
+    pub fn footer(runs: &[DayRun]) -> String {
+        let total = runs.iter().map(|r| r.total_time()).sum::<Duration>();
+        let total = format!("total  {:>10}", format_duration(total));
+        format!("{:>width$}", total, width = Self::header().len())
+    }
+    /// Answers that don't fit on a table row, such as the day 10 CRT screen, and the errors of
+    /// parts without one.
+    pub fn multiline_answers(runs: &[DayRun]) -> String {
+        runs.iter()
+            .flat_map(|r| r.parts.iter().map(move |p| (r.day.number, p)))
+            .filter_map(|(day, p)| match &p.answer {
+                Ok(answer) if answer.to_string().contains('\n') => {
+                    Some(format!("day {} part {}:\n{}\n", day, p.part, answer))
+                }
+                Ok(_) => None,
+                Err(e) => Some(format!("day {} part {}: error: {:#}\n", day, p.part, e)),
+            })
+            .collect()
+    }
+}
+
+/// One `--format json` record; `part` and `answer` are null when the day could not be run.
+#[derive(Debug, Serialize)]
+pub(crate) struct JsonRecord {
+    pub day: u8,
+    pub part: Option<u8>,
+    answer: Option<Answer>,
+    duration_ms: Option<f64>,
+    /// `ok` or `error`, or `pass`, `fail` and `missing` when verifying.
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected: Option<Answer>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    timed_out: bool,
+}
+
+impl JsonRecord {
+    pub fn new(day: &Day, part: &PartRun, status: Option<Status>) -> Self {
+        let status_name = match (&part.answer, &status) {
+            (Err(_), _) => "error".to_string(),
+            (Ok(_), Some(status)) => status.to_string(),
+            (Ok(_), None) => "ok".to_string(),
+        };
+        Self {
+            day: day.number,
+            part: Some(part.part),
+            answer: part.answer.as_ref().ok().cloned(),
+            duration_ms: Some(part.time.as_secs_f64() * 1000.0),
+            status: status_name,
+            expected: match status {
+                Some(Status::Fail { expected }) => Some(expected),
+                _ => None,
+            },
+            error: part.answer.as_ref().err().map(|e| format!("{:#}", e)),
+            timed_out: part.timed_out,
+        }
+    }
+    pub fn error(day: &Day, error: &anyhow::Error) -> Self {
+        Self {
+            day: day.number,
+            part: None,
+            answer: None,
+            duration_ms: None,
+            status: "error".to_string(),
+            expected: None,
+            error: Some(format!("{:#}", error)),
+            timed_out: false,
+        }
+    }
+}
+
+fn table_answer(part: &PartRun) -> String {
+    let Ok(answer) = &part.answer else {
+        return "error (see below)".to_string();
+    };
+    let answer = answer.to_string();
+    if answer.contains('\n') {
+        "(see below)".to_string()
+    } else if part.timed_out {
+        format!("{} (timed out)", answer)
+    } else {
+        answer
+    }
+}
+
+pub(crate) fn run(options: &RunOptions) -> anyhow::Result<()> {
+    let pool = thread_pool(options)?;
+    let (runs, errors) = if let [day] = options.days[..] {
+        (vec![pool.install(|| run_single(day, options))?], vec![])
+    } else {
+        run_all(options, &pool)
+    };
+    match options.answers_mode {
+        Some(AnswersMode::Verify) => verify(&runs, &errors, &options.answers_path)?,
+        Some(AnswersMode::Record) => {
+            record(&runs, &options.answers_path)?;
+            println!("\nrecorded answers to {}", options.answers_path.display());
+        }
+        None => {}
+    }
+    let failed = errors.len() + runs.iter().filter(|run| run.failed()).count();
+    if failed > 0 {
+        bail!("{} of {} days failed", failed, options.days.len());
+    }
+    Ok(())
+}
+
+/// Prints a single JSON array with one record per day and part, and nothing else.
+pub(crate) fn run_json(options: &RunOptions) -> anyhow::Result<()> {
+    let answers = match options.answers_mode {
+        Some(AnswersMode::Verify) => Some(Answers::load(&options.answers_path)?),
+        _ => None,
+    };
+    let mut runs = vec![];
+    let mut records = vec![];
+    run_days_in_order(
+        options,
+        &thread_pool(options)?,
+        |day, result| match result {
+            Ok(run) => runs.push(run),
+            Err(e) => records.push(JsonRecord::error(day, &e)),
+        },
+    );
+    for run in runs.iter() {
+        for part in run.parts.iter() {
+            let status = match (&answers, &part.answer) {
+                (Some(answers), Ok(answer)) => {
+                    Some(answers.check(run.day.number, part.part, answer))
+                }
+                _ => None,
+            };
+            records.push(JsonRecord::new(run.day, part, status));
+        }
+    }
+    records.sort_by_key(|r| (r.day, r.part));
+    println!("{}", serde_json::to_string_pretty(&records)?);
+
+    if options.answers_mode == Some(AnswersMode::Record) {
+        record(&runs, &options.answers_path)?;
+    }
+    let failed = records
+        .iter()
+        .filter(|r| r.status == "fail" || r.status == "error")
+        .count();
+    if failed > 0 {
+        bail!("{} of {} records failed or errored", failed, records.len());
+    }
+    Ok(())
+}
+
+fn run_single(day: &'static Day, options: &RunOptions) -> anyhow::Result<DayRun> {
+    let params = options.params.for_day(day)?;
+    if options.params.is_empty() {
+        println!("running day {} ({})\n", day.number, day.name());
+    } else {
+        println!(
+            "running day {} ({}) with {}\n",
+            day.number,
+            day.name(),
+            params
+        );
+    }
+    let input = options.source.read(day)?;
+    let run = run_day(day, &input, &options.parts, &params, options.timeout)?;
+    for part in run.parts.iter() {
+        print_answer(part);
+    }
+    println!(
+        "\nparse took {}, total {}",
+        format_duration(run.parse_time),
+        format_duration(run.total_time())
+    );
+    Ok(run)
+}
+
+/// The days that ran, and the ones that could not with their errors.
+fn run_all(options: &RunOptions, pool: &ThreadPool) -> (Vec<DayRun>, Vec<DayError>) {
+    println!("{}", TimingTable::header());
+    let mut runs = vec![];
+    let mut errors = vec![];
+    run_days_in_order(options, pool, |day, result| match result {
+        Ok(run) => {
+            println!("{}", TimingTable::row(&run));
+            runs.push(run);
+        }
+        Err(e) => {
+            println!("{}", TimingTable::error_row(day, &e));
+            errors.push((day, e));
+        }
+    });
+    println!("{}", TimingTable::footer(&runs));
+    let multiline = TimingTable::multiline_answers(&runs);
+    if !multiline.is_empty() {
+        print!("\n{}", multiline);
+    }
+    (runs, errors)
+}
+
+/// Days that could not run and parts without an answer count as failed.
+fn verify(runs: &[DayRun], errors: &[DayError], path: &Path) -> anyhow::Result<()> {
+    let answers = Answers::load(path)?;
+    println!("\nverifying against {}", path.display());
+    let (mut passed, mut failed, mut missing) = (0, 0, 0);
+    for (day, error) in errors {
+        println!("day {:>2}: error: {:#}", day.number, error);
+        failed += 1;
+    }
+    for run in runs {
+        for part in run.parts.iter() {
+            let answer = match &part.answer {
+                Ok(answer) => answer,
+                Err(e) => {
+                    println!(
+                        "day {:>2} part {}: error: {:#}",
+                        run.day.number, part.part, e
+                    );
+                    failed += 1;
+                    continue;
+                }
+            };
+            let status = answers.check(run.day.number, part.part, answer);
+            let timed_out = if part.timed_out { " (timed out)" } else { "" };
+            println!(
+                "day {:>2} part {}: {}{}",
+                run.day.number, part.part, status, timed_out
+            );
+            match status {
+                Status::Pass => passed += 1,
+                Status::Fail { expected } => {
+                    failed += 1;
+                    print!("{}", answers::diff(&expected, answer));
+                }
+                Status::Missing => missing += 1,
+            }
+        }
+    }
+    println!("{} passed, {} failed, {} missing", passed, failed, missing);
+    if failed > 0 {
+        bail!("{} failed verification", failed);
+    }
+    Ok(())
+}
+
+/// Answers that timed out are left out, as they may be wrong, and so are errors.
+fn record(runs: &[DayRun], path: &Path) -> anyhow::Result<()> {
+    let mut answers = Answers::load_or_default(path)?;
+    for run in runs {
+        for part in run.parts.iter().filter(|p| !p.timed_out) {
+            if let Ok(answer) = &part.answer {
+                answers.record(run.day.number, part.part, answer);
+            }
+        }
+    }
+    answers.save(path)
+}
+
+fn print_answer(part: &PartRun) {
+    let answer = match &part.answer {
+        Ok(answer) => answer.to_string(),
+        Err(e) => {
+            println!("part {}: error: {:#}", part.part, e);
+            return;
+        }
+    };
+    let timed_out = match part.timed_out {
+        true => " (timed out, best found so far)",
+        false => "",
+    };
+    if answer.contains('\n') {
+        println!("part {}{}:\n{}", part.part, timed_out, answer);
+    } else {
+        println!("part {}: {}{}", part.part, answer, timed_out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::cli::{InputSource, OutputFormat};
+    use crate::params::Overrides;
+    use crate::registry;
+
+    use super::*;
+
+    /// Days 1 and 2, reading their inputs from `dir`.
+    fn options(dir: &Path) -> RunOptions {
+        RunOptions {
+            days: vec![registry::find("1").unwrap(), registry::find("2").unwrap()],
+            parts: vec![1, 2],
+            source: InputSource::Directory(dir.to_path_buf()),
+            answers_mode: None,
+            answers_path: PathBuf::from("answers.toml"),
+            format: OutputFormat::Text,
+            jobs: None,
+            params: Overrides::default(),
+            timeout: None,
+            progress: None,
+        }
+    }
+
+    #[test]
+    fn test1() {
+        let day = registry::find("1").unwrap();
+        let input = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000";
+        let run = run_day(day, input, &[2], &Params::defaults(&[]), None).unwrap();
+        assert!(run.part(1).is_none());
+        assert_eq!(
+            Answer::Number(45000),
+            *run.part(2).unwrap().answer.as_ref().unwrap()
+        );
+        // only the days with long searches stop early
+        let run = run_day(
+            day,
+            input,
+            &[2],
+            &Params::defaults(&[]),
+            Some(Duration::ZERO),
+        )
+        .unwrap();
+        assert!(!run.part(2).unwrap().timed_out);
+        let day = registry::find("19").unwrap();
+        let input = "Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. \
+                     Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.";
+        let params = Params::defaults(day.solver.parameters());
+        let run = run_day(day, input, &[1, 2], &params, Some(Duration::ZERO)).unwrap();
+        assert!(run
+            .parts
+            .iter()
+            .all(|p| p.timed_out && matches!(p.answer, Ok(Answer::Number(0)))));
+        // day 3's inputs without an answer are errors of their part, not panics
+        let day = registry::find("3").unwrap();
+        let params = Params::defaults(day.solver.parameters());
+        let run = run_day(day, "abc", &[1], &params, None).unwrap();
+        assert_eq!(
+            "rucksack 1 has 3 items, which do not split into 2 compartments",
+            format!("{:#}", run.part(1).unwrap().answer.as_ref().unwrap_err())
+        );
+        // two rucksacks make an incomplete group of three, but part 1 still has its answer
+        let run = run_day(day, "aa\nbb", &[1, 2], &params, None).unwrap();
+        assert_eq!(
+            Answer::Number(1 + 2),
+            *run.part(1).unwrap().answer.as_ref().unwrap()
+        );
+        assert!(run.part(2).unwrap().answer.is_err());
+        assert!(run.failed());
+
+        let run = DayRun {
+            day: registry::find("10").unwrap(),
+            parse_time: Duration::from_micros(250),
+            parts: vec![
+                PartRun {
+                    part: 1,
+                    answer: Ok(Answer::Number(13140)),
+                    time: Duration::from_micros(1500),
+                    timed_out: false,
+                },
+                PartRun {
+                    part: 2,
+                    answer: Ok(Answer::Text("##..\n..##".to_string())),
+                    time: Duration::from_millis(2500),
+                    timed_out: false,
+                },
+            ],
+        };
+        assert_eq!(
+            " 10  cathode_ray_tube            0.250 ms  13140                   1.500 ms  (see below)              2.500 s     2.502 s",
+            TimingTable::row(&run)
+        );
+        assert_eq!(TimingTable::header().len(), TimingTable::row(&run).len());
+        assert_eq!(TimingTable::header().len(), TimingTable::footer(&[]).len());
+        assert_eq!(
+            " 10  cathode_ray_tube          error: bad input",
+            TimingTable::error_row(run.day, &anyhow::anyhow!("bad input"))
+        );
+        assert_eq!(
+            "day 10 part 2:\n##..\n..##\n",
+            TimingTable::multiline_answers(std::slice::from_ref(&run))
+        );
+        assert_eq!(
+            r###"{"day":10,"part":2,"answer":"##..\n..##","duration_ms":2500.0,"status":"ok"}"###,
+            serde_json::to_string(&JsonRecord::new(run.day, &run.parts[1], None)).unwrap()
+        );
+        assert_eq!(
+            r#"{"day":10,"part":1,"answer":13140,"duration_ms":1.5,"status":"fail","expected":13141}"#,
+            serde_json::to_string(&JsonRecord::new(
+                run.day,
+                &run.parts[0],
+                Some(Status::Fail {
+                    expected: Answer::Number(13141)
+                })
+            ))
+            .unwrap()
+        );
+        assert_eq!(
+            r#"{"day":10,"part":null,"answer":null,"duration_ms":null,"status":"error","error":"bad input"}"#,
+            serde_json::to_string(&JsonRecord::error(run.day, &anyhow::anyhow!("bad input")))
+                .unwrap()
+        );
+        assert!(TimingTable::footer(std::slice::from_ref(&run)).ends_with("total     2.502 s"));
+
+        let mut run = run;
+        run.parts[0].timed_out = true;
+        assert!(TimingTable::row(&run).contains("  13140 (timed out)       1.500 ms"));
+        assert_eq!(
+            r#"{"day":10,"part":1,"answer":13140,"duration_ms":1.5,"status":"ok","timed_out":true}"#,
+            serde_json::to_string(&JsonRecord::new(run.day, &run.parts[0], None)).unwrap()
+        );
+
+        // a part without an answer is reported on its own, next to the other part's answer
+        run.parts[0] = PartRun {
+            part: 1,
+            answer: Err(anyhow::anyhow!("no answer")),
+            time: Duration::from_micros(1500),
+            timed_out: false,
+        };
+        assert!(run.failed());
+        assert!(TimingTable::row(&run).contains("  error (see below)       1.500 ms  (see below)"));
+        assert_eq!(
+            "day 10 part 1: error: no answer\nday 10 part 2:\n##..\n..##\n",
+            TimingTable::multiline_answers(std::slice::from_ref(&run))
+        );
+        assert_eq!(
+            r#"{"day":10,"part":1,"answer":null,"duration_ms":1.5,"status":"error","error":"no answer"}"#,
+            serde_json::to_string(&JsonRecord::new(run.day, &run.parts[0], Some(Status::Pass)))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test2() {
+        let dir = std::env::temp_dir().join(format!("aoc-runner-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("input1.txt"), "1000\n\n2000").unwrap();
+        // day 2's input is missing
+        let error = run(&options(&dir)).unwrap_err();
+        assert_eq!("1 of 2 days failed", error.to_string());
+        let error = run_json(&options(&dir)).unwrap_err();
+        assert_eq!("1 of 3 records failed or errored", error.to_string());
+        let mut verifying = options(&dir);
+        verifying.answers_mode = Some(AnswersMode::Verify);
+        verifying.answers_path = dir.join("answers.toml");
+        std::fs::write(
+            &verifying.answers_path,
+            "[day01]\npart1 = 2000\npart2 = 3000\n",
+        )
+        .unwrap();
+        let error = run(&verifying).unwrap_err();
+        assert_eq!("1 failed verification", error.to_string());
+        std::fs::write(dir.join("input2.txt"), "A Y").unwrap();
+        assert!(run(&options(&dir)).is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,242 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+
+use thiserror::Error;
+
+/// A graph explored lazily from its start nodes.
+pub trait Graph {
+    type Node: Clone + Eq + Hash;
+
+    /// The nodes one step away from `node`, with the cost of each step. Breadth-first searches
+    /// count every step as 1.
+    fn successors(&mut self, node: &Self::Node) -> Vec<(Self::Node, u64)>;
+    /// A lower bound on the cost from `node` to the nearest goal, used by A*.
+    fn heuristic(&mut self, _node: &Self::Node) -> u64 {
+        0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult<N> {
+    /// Every node from the start to the goal, both included.
+    pub path: Vec<N>,
+    pub cost: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum SearchError {
+    #[error("no path to a goal")]
+    NoPath,
+    #[error("gave up after visiting {0} states")]
+    TooManyStates(usize),
+}
+
+/// Nodes seen so far, with the cheapest known way to reach each of them.
+struct Visited<N> {
+    nodes: Vec<(N, Option<usize>, u64)>,
+    index: HashMap<N, usize>,
+    limit: Option<usize>,
+}
+
+impl<N: Clone + Eq + Hash> Visited<N> {
+    fn new(limit: Option<usize>) -> Self {
+        Self {
+            nodes: vec![],
+            index: HashMap::new(),
+            limit,
+        }
+    }
+    fn insert(&mut self, node: N, parent: Option<usize>, cost: u64) -> Result<usize, SearchError> {
+        if self.limit.is_some_and(|limit| self.nodes.len() >= limit) {
+            return Err(SearchError::TooManyStates(self.nodes.len()));
+        }
+        self.index.insert(node.clone(), self.nodes.len());
+        self.nodes.push((node, parent, cost));
+        Ok(self.nodes.len() - 1)
+    }
+    fn result(&self, goal: usize) -> SearchResult<N> {
+        let mut path = vec![];
+        let mut current = Some(goal);
+        while let Some(i) = current {
+            path.push(self.nodes[i].0.clone());
+            current = self.nodes[i].1;
+        }
+        path.reverse();
+        SearchResult {
+            path,
+            cost: self.nodes[goal].2,
+        }
+    }
+}
+
+/// Breadth-first, Dijkstra and A* searches, optionally giving up after visiting `max_visited`
+/// states.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Search {
+    max_visited: Option<usize>,
+}
+
+impl Search {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_max_visited(mut self, max_visited: usize) -> Self {
+        self.max_visited = Some(max_visited);
+        self
+    }
+    /// Fewest steps from `start` to a node satisfying `is_goal`.
+    pub fn bfs<G: Graph>(
+        &self,
+        graph: &mut G,
+        start: G::Node,
+        is_goal: impl FnMut(&G::Node) -> bool,
+    ) -> Result<SearchResult<G::Node>, SearchError> {
+        self.multi_source_bfs(graph, [start], is_goal)
+    }
+    /// Fewest steps from any of `starts` to a node satisfying `is_goal`.
+    pub fn multi_source_bfs<G: Graph>(
+        &self,
+        graph: &mut G,
+        starts: impl IntoIterator<Item = G::Node>,
+        mut is_goal: impl FnMut(&G::Node) -> bool,
+    ) -> Result<SearchResult<G::Node>, SearchError> {
+        let mut visited = Visited::new(self.max_visited);
+        let mut queue = VecDeque::new();
+        for start in starts {
+            if !visited.index.contains_key(&start) {
+                queue.push_back(visited.insert(start, None, 0)?);
+            }
+        }
+        while let Some(i) = queue.pop_front() {
+            let (node, _, steps) = visited.nodes[i].clone();
+            if is_goal(&node) {
+                return Ok(visited.result(i));
+            }
+            for (next, _) in graph.successors(&node) {
+                if !visited.index.contains_key(&next) {
+                    queue.push_back(visited.insert(next, Some(i), steps + 1)?);
+                }
+            }
+        }
+        Err(SearchError::NoPath)
+    }
+    /// Cheapest path from `start` to a node satisfying `is_goal`.
+    pub fn dijkstra<G: Graph>(
+        &self,
+        graph: &mut G,
+        start: G::Node,
+        is_goal: impl FnMut(&G::Node) -> bool,
+    ) -> Result<SearchResult<G::Node>, SearchError> {
+        self.best_first(graph, start, is_goal, false)
+    }
+    /// Like [`Search::dijkstra`], but guided by [`Graph::heuristic`].
+    pub fn a_star<G: Graph>(
+        &self,
+        graph: &mut G,
+        start: G::Node,
+        is_goal: impl FnMut(&G::Node) -> bool,
+    ) -> Result<SearchResult<G::Node>, SearchError> {
+        self.best_first(graph, start, is_goal, true)
+    }
+    fn best_first<G: Graph>(
+        &self,
+        graph: &mut G,
+        start: G::Node,
+        mut is_goal: impl FnMut(&G::Node) -> bool,
+        use_heuristic: bool,
+    ) -> Result<SearchResult<G::Node>, SearchError> {
+        let mut visited = Visited::new(self.max_visited);
+        let mut queue = BinaryHeap::new();
+        queue.push(Reverse((0, 0, visited.insert(start, None, 0)?)));
+        while let Some(Reverse((_, cost, i))) = queue.pop() {
+            if cost > visited.nodes[i].2 {
+                // a cheaper way here was found after this entry was queued
+                continue;
+            }
+            let node = visited.nodes[i].0.clone();
+            if is_goal(&node) {
+                return Ok(visited.result(i));
+            }
+            for (next, step) in graph.successors(&node) {
+                let next_cost = cost + step;
+                let j = match visited.index.get(&next) {
+                    Some(&j) if visited.nodes[j].2 <= next_cost => continue,
+                    Some(&j) => {
+                        visited.nodes[j].1 = Some(i);
+                        visited.nodes[j].2 = next_cost;
+                        j
+                    }
+                    None => visited.insert(next, Some(i), next_cost)?,
+                };
+                let estimate = if use_heuristic {
+                    graph.heuristic(&visited.nodes[j].0)
+                } else {
+                    0
+                };
+                queue.push(Reverse((next_cost + estimate, next_cost, j)));
+            }
+        }
+        Err(SearchError::NoPath)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        /// Numbers up to `target`, where n leads to n + 1 at cost 1 and to 2n at cost `double`.
+        struct Numbers {
+            double: u64,
+            target: u64,
+        }
+        impl Graph for Numbers {
+            type Node = u64;
+
+            fn successors(&mut self, node: &u64) -> Vec<(u64, u64)> {
+                [(node + 1, 1), (node * 2, self.double)]
+                    .into_iter()
+                    .filter(|&(n, _)| n <= self.target)
+                    .collect()
+            }
+            fn heuristic(&mut self, node: &u64) -> u64 {
+                u64::from(*node < self.target)
+            }
+        }
+
+        let mut graph = Numbers {
+            double: 1,
+            target: 10,
+        };
+        let result = Search::new().bfs(&mut graph, 1, |&n| n == 10).unwrap();
+        assert_eq!(vec![1, 2, 4, 5, 10], result.path);
+        assert_eq!(4, result.cost);
+        let result = Search::new()
+            .multi_source_bfs(&mut graph, [1, 9], |&n| n == 10)
+            .unwrap();
+        assert_eq!((vec![9, 10], 1), (result.path, result.cost));
+
+        let mut graph = Numbers {
+            double: 6,
+            target: 10,
+        };
+        let dijkstra = Search::new().dijkstra(&mut graph, 1, |&n| n == 10).unwrap();
+        assert_eq!(9, dijkstra.cost);
+        assert_eq!((1..=10).collect::<Vec<_>>(), dijkstra.path);
+        let a_star = Search::new().a_star(&mut graph, 1, |&n| n == 10).unwrap();
+        assert_eq!(dijkstra, a_star);
+
+        assert_eq!(
+            Err(SearchError::TooManyStates(5)),
+            Search::new()
+                .with_max_visited(5)
+                .bfs(&mut graph, 1, |&n| n == 1000)
+        );
+        assert_eq!(
+            Err(SearchError::NoPath),
+            Search::new().bfs(&mut graph, 1, |&n| n == 0)
+        );
+    }
+}
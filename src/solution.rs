@@ -0,0 +1,213 @@
+use std::fmt::{Display, Formatter};
+
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+
+use crate::cancel::{Cancellation, Outcome};
+use crate::params::{Parameter, Params};
+use crate::repl::Explore;
+use crate::visualize::Visualize;
+
+/// A puzzle solution split into its parse step and its two parts.
+pub trait Solution {
+    /// `Sync` so that both parts can be solved in parallel.
+    type Input: Sync + 'static;
+    type Answer1: PartAnswer;
+    type Answer2: PartAnswer;
+    /// Puzzle constants that can be overridden with `--param` or a parameters file.
+    const PARAMETERS: &'static [Parameter] = &[];
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input>;
+    fn part1(input: &Self::Input) -> Self::Answer1;
+    fn part2(input: &Self::Input) -> Self::Answer2;
+    /// Part 1 with the given values of [`Solution::PARAMETERS`]; days with parameters implement
+    /// `part1` by calling this with [`Params::defaults`].
+    fn part1_with(input: &Self::Input, _params: &Params) -> Self::Answer1 {
+        Self::part1(input)
+    }
+    fn part2_with(input: &Self::Input, _params: &Params) -> Self::Answer2 {
+        Self::part2(input)
+    }
+    /// Part 1 giving up with the best answer so far once `cancellation` is cancelled; days with
+    /// long searches implement it, the others always finish.
+    fn part1_until(
+        input: &Self::Input,
+        params: &Params,
+        _cancellation: &Cancellation,
+    ) -> Outcome<Self::Answer1> {
+        Outcome::Finished(Self::part1_with(input, params))
+    }
+    fn part2_until(
+        input: &Self::Input,
+        params: &Params,
+        _cancellation: &Cancellation,
+    ) -> Outcome<Self::Answer2> {
+        Outcome::Finished(Self::part2_with(input, params))
+    }
+    /// A random puzzle input that both parts can solve, of roughly `size` lines, valves,
+    /// blueprints and so on, or `size` tiles wide for the maps.
+    fn generate(rng: &mut StdRng, size: usize) -> String;
+    /// Frames of the simulation behind `part`, for days that have one to watch.
+    fn visualize(_input: &Self::Input, _part: u8) -> Option<Box<dyn Visualize + '_>> {
+        None
+    }
+    /// Commands for the REPL to look into the parsed model, for days that have some.
+    fn explore(_input: &Self::Input) -> Option<Box<dyn Explore + '_>> {
+        None
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Answer {
+    Number(i64),
+    Text(String),
+    None,
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+            Answer::None => write!(f, "-"),
+        }
+    }
+}
+
+impl From<i64> for Answer {
+    fn from(value: i64) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<u64> for Answer {
+    fn from(value: u64) -> Self {
+        Self::Number(i64::try_from(value).expect("answer does not fit in an i64"))
+    }
+}
+
+impl From<u32> for Answer {
+    fn from(value: u32) -> Self {
+        Self::Number(value as i64)
+    }
+}
+
+impl From<i32> for Answer {
+    fn from(value: i32) -> Self {
+        Self::Number(value as i64)
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(value: usize) -> Self {
+        Self::Number(value as i64)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl From<()> for Answer {
+    fn from(_: ()) -> Self {
+        Self::None
+    }
+}
+
+/// What a part returns: an answer, or a `Result` of one for days whose input may have none.
+pub trait PartAnswer {
+    fn into_answer(self) -> anyhow::Result<Answer>;
+}
+
+macro_rules! part_answer {
+    ($($t:ty),*) => {
+        $(impl PartAnswer for $t {
+            fn into_answer(self) -> anyhow::Result<Answer> {
+                Ok(self.into())
+            }
+        })*
+    };
+}
+
+part_answer!(i64, u64, u32, i32, usize, String, ());
+
+impl<T: Into<Answer>, E: std::error::Error + Send + Sync + 'static> PartAnswer for Result<T, E> {
+    fn into_answer(self) -> anyhow::Result<Answer> {
+        Ok(self?.into())
+    }
+}
+
+/// Object-safe view of a [`Solution`], used by the registry.
+pub trait Solver: Sync {
+    fn parse(&self, input: &str) -> anyhow::Result<Box<dyn Parsed>>;
+    fn parameters(&self) -> &'static [Parameter];
+    fn generate(&self, rng: &mut StdRng, size: usize) -> String;
+}
+
+/// A parsed puzzle input, ready to be solved.
+pub trait Parsed: Sync {
+    fn part1(&self, params: &Params) -> anyhow::Result<Answer>;
+    fn part2(&self, params: &Params) -> anyhow::Result<Answer>;
+    fn part1_until(
+        &self,
+        params: &Params,
+        cancellation: &Cancellation,
+    ) -> anyhow::Result<Outcome<Answer>>;
+    fn part2_until(
+        &self,
+        params: &Params,
+        cancellation: &Cancellation,
+    ) -> anyhow::Result<Outcome<Answer>>;
+    fn visualize(&self, part: u8) -> Option<Box<dyn Visualize + '_>>;
+    fn explore(&self) -> Option<Box<dyn Explore + '_>>;
+}
+
+struct ParsedInput<S: Solution>(S::Input);
+
+impl<S: Solution> Parsed for ParsedInput<S> {
+    fn part1(&self, params: &Params) -> anyhow::Result<Answer> {
+        S::part1_with(&self.0, params).into_answer()
+    }
+    fn part2(&self, params: &Params) -> anyhow::Result<Answer> {
+        S::part2_with(&self.0, params).into_answer()
+    }
+    fn part1_until(
+        &self,
+        params: &Params,
+        cancellation: &Cancellation,
+    ) -> anyhow::Result<Outcome<Answer>> {
+        S::part1_until(&self.0, params, cancellation)
+            .map(PartAnswer::into_answer)
+            .transpose()
+    }
+    fn part2_until(
+        &self,
+        params: &Params,
+        cancellation: &Cancellation,
+    ) -> anyhow::Result<Outcome<Answer>> {
+        S::part2_until(&self.0, params, cancellation)
+            .map(PartAnswer::into_answer)
+            .transpose()
+    }
+    fn visualize(&self, part: u8) -> Option<Box<dyn Visualize + '_>> {
+        S::visualize(&self.0, part)
+    }
+    fn explore(&self) -> Option<Box<dyn Explore + '_>> {
+        S::explore(&self.0)
+    }
+}
+
+impl<S: Solution + Sync + 'static> Solver for S {
+    fn parse(&self, input: &str) -> anyhow::Result<Box<dyn Parsed>> {
+        Ok(Box::new(ParsedInput::<S>(S::parse(input)?)))
+    }
+    fn parameters(&self) -> &'static [Parameter] {
+        S::PARAMETERS
+    }
+    fn generate(&self, rng: &mut StdRng, size: usize) -> String {
+        S::generate(rng, size)
+    }
+}
@@ -0,0 +1,360 @@
+use std::collections::VecDeque;
+use std::io::{Stdout, Write};
+use std::time::Duration;
+
+use anyhow::bail;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::Print;
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+
+use crate::cli::VisualizeOptions;
+use crate::registry::Day;
+use crate::solution::Parsed;
+
+/// How many past frames the player keeps for scrubbing back.
+const HISTORY: usize = 1000;
+
+const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+const MIN_DELAY: Duration = Duration::from_millis(1);
+const MAX_DELAY: Duration = Duration::from_millis(2000);
+
+const HELP: &str = "space play/pause  ←/→ step  ↑/↓ speed  home/end jump  q quit";
+
+/// One step of a simulation: a caption such as "round 12" and the picture below it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub caption: String,
+    pub picture: String,
+}
+
+impl Frame {
+    pub fn new(caption: impl Into<String>, picture: impl ToString) -> Self {
+        Self {
+            caption: caption.into(),
+            picture: picture.to_string(),
+        }
+    }
+}
+
+/// A simulation that can be watched one frame at a time.
+pub trait Visualize {
+    /// Advances the simulation and draws it, or returns `None` once it is over.
+    fn next_frame(&mut self) -> Option<Frame>;
+}
+
+impl<I: Iterator<Item = Frame>> Visualize for I {
+    fn next_frame(&mut self) -> Option<Frame> {
+        self.next()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    TogglePlay,
+    Forward,
+    Back,
+    Faster,
+    Slower,
+    First,
+    Last,
+    Quit,
+}
+
+impl Action {
+    fn from_key(key: KeyEvent) -> Option<Self> {
+        Some(match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
+            KeyCode::Char(' ') => Action::TogglePlay,
+            KeyCode::Right | KeyCode::Char('n') => Action::Forward,
+            KeyCode::Left | KeyCode::Char('p') => Action::Back,
+            KeyCode::Up | KeyCode::Char('+') => Action::Faster,
+            KeyCode::Down | KeyCode::Char('-') => Action::Slower,
+            KeyCode::Home | KeyCode::Char('g') => Action::First,
+            KeyCode::End | KeyCode::Char('G') => Action::Last,
+            KeyCode::Esc | KeyCode::Char('q') => Action::Quit,
+            _ => return None,
+        })
+    }
+}
+
+/// Pulls frames from a [`Visualize`] on demand and keeps the last [`HISTORY`] of them, so the
+/// viewer can step back through what was already shown.
+struct Player<'a> {
+    frames: Box<dyn Visualize + 'a>,
+    history: VecDeque<Frame>,
+    /// Frames dropped from the front of `history`.
+    dropped: usize,
+    /// Index of the shown frame, counting dropped ones.
+    current: usize,
+    finished: bool,
+    playing: bool,
+    delay: Duration,
+}
+
+impl<'a> Player<'a> {
+    fn new(frames: Box<dyn Visualize + 'a>) -> Self {
+        let mut player = Self {
+            frames,
+            history: VecDeque::new(),
+            dropped: 0,
+            current: 0,
+            finished: false,
+            playing: false,
+            delay: DEFAULT_DELAY,
+        };
+        player.pull();
+        player
+    }
+    /// Fetches one more frame, returning false once the simulation is over.
+    fn pull(&mut self) -> bool {
+        if self.finished {
+            return false;
+        }
+        match self.frames.next_frame() {
+            Some(frame) => {
+                self.history.push_back(frame);
+                if self.history.len() > HISTORY {
+                    self.history.pop_front();
+                    self.dropped += 1;
+                }
+                true
+            }
+            None => {
+                self.finished = true;
+                false
+            }
+        }
+    }
+    fn last(&self) -> usize {
+        (self.dropped + self.history.len()).saturating_sub(1)
+    }
+    fn frame(&self) -> Option<&Frame> {
+        self.history.get(self.current - self.dropped)
+    }
+    fn forward(&mut self) -> bool {
+        if self.current < self.last() || self.pull() {
+            self.current += 1;
+            true
+        } else {
+            false
+        }
+    }
+    /// Applies `action`, returning false when the player should close.
+    fn apply(&mut self, action: Action) -> bool {
+        match action {
+            Action::TogglePlay => self.playing = !self.playing,
+            Action::Forward => {
+                self.playing = false;
+                self.forward();
+            }
+            Action::Back => {
+                self.playing = false;
+                self.current = self.current.saturating_sub(1).max(self.dropped);
+            }
+            Action::Faster => self.delay = (self.delay / 2).max(MIN_DELAY),
+            Action::Slower => self.delay = (self.delay * 2).min(MAX_DELAY),
+            Action::First => self.current = self.dropped,
+            Action::Last => self.current = self.last(),
+            Action::Quit => return false,
+        }
+        true
+    }
+    /// Called when the delay passes while playing; pauses at the end of the simulation.
+    fn tick(&mut self) {
+        if !self.forward() {
+            self.playing = false;
+        }
+    }
+    fn status(&self) -> String {
+        format!(
+            "frame {}{}  {}  {} ms/frame  {}",
+            self.current + 1,
+            if self.finished {
+                format!("/{}", self.last() + 1)
+            } else {
+                String::new()
+            },
+            if self.playing { "playing" } else { "paused" },
+            self.delay.as_millis(),
+            HELP
+        )
+    }
+    /// Draws the title, the caption and as much of the picture as fits, with the status at the
+    /// bottom.
+    fn draw(&self, out: &mut Stdout, title: &str) -> anyhow::Result<()> {
+        let (columns, rows) = terminal::size()?;
+        let crop = |line: &str| line.chars().take(columns as usize).collect::<String>();
+        queue!(out, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+        let caption = self.frame().map_or("no frames", |f| f.caption.as_str());
+        queue!(out, Print(crop(&format!("{} - {}", title, caption))))?;
+        if let Some(frame) = self.frame() {
+            for (y, line) in frame
+                .picture
+                .lines()
+                .take(rows.saturating_sub(3) as usize)
+                .enumerate()
+            {
+                queue!(out, cursor::MoveTo(0, y as u16 + 2), Print(crop(line)))?;
+            }
+        }
+        queue!(
+            out,
+            cursor::MoveTo(0, rows.saturating_sub(1)),
+            Print(crop(&self.status()))
+        )?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Leaves the alternate screen and raw mode even when the player fails.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter(out: &mut Stdout) -> anyhow::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(
+            std::io::stdout(),
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Shows `frames` full screen until the viewer quits.
+pub fn play(frames: Box<dyn Visualize + '_>, title: &str) -> anyhow::Result<()> {
+    let mut player = Player::new(frames);
+    let mut out = std::io::stdout();
+    let _guard = TerminalGuard::enter(&mut out)?;
+    loop {
+        player.draw(&mut out, title)?;
+        let timeout = if player.playing {
+            player.delay
+        } else {
+            Duration::from_secs(60)
+        };
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Release {
+                    continue;
+                }
+                if let Some(action) = Action::from_key(key) {
+                    if !player.apply(action) {
+                        return Ok(());
+                    }
+                }
+            }
+        } else if player.playing {
+            player.tick();
+        }
+    }
+}
+
+/// The simulation of `part`, or without one, of each part that has one; most days only simulate
+/// one of their parts.
+pub(crate) fn visualizations<'a>(
+    day: &Day,
+    parsed: &'a dyn Parsed,
+    part: Option<u8>,
+) -> anyhow::Result<Vec<(u8, Box<dyn Visualize + 'a>)>> {
+    if let Some(part) = part {
+        let Some(frames) = parsed.visualize(part) else {
+            bail!("day {} part {} has no visualisation", day.number, part);
+        };
+        return Ok(vec![(part, frames)]);
+    }
+    let visualizations: Vec<_> = [1, 2]
+        .into_iter()
+        .filter_map(|part| Some((part, parsed.visualize(part)?)))
+        .collect();
+    if visualizations.is_empty() {
+        bail!("day {} has no visualisation", day.number);
+    }
+    Ok(visualizations)
+}
+
+pub(crate) fn run(options: &VisualizeOptions) -> anyhow::Result<()> {
+    let day = options.day;
+    let input = options.source.read(day)?;
+    let parsed = day.solver.parse(&input)?;
+    for (part, frames) in visualizations(day, &*parsed, options.part)? {
+        play(
+            frames,
+            &format!("day {} ({}) part {}", day.number, day.name(), part),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry;
+
+    #[test]
+    fn test1() {
+        let frames = (0..HISTORY + 5).map(|i| Frame::new(format!("step {}", i), i));
+        let mut player = Player::new(Box::new(frames));
+        assert_eq!("step 0", player.frame().unwrap().caption);
+        assert!(!player.apply(Action::Quit));
+
+        player.apply(Action::Back);
+        assert_eq!(0, player.current);
+        player.apply(Action::Forward);
+        player.apply(Action::Forward);
+        player.apply(Action::Back);
+        assert_eq!("1", player.frame().unwrap().picture);
+        player.apply(Action::Last);
+        assert_eq!(2, player.current);
+
+        player.apply(Action::TogglePlay);
+        while player.playing {
+            player.tick();
+        }
+        assert!(player.finished);
+        assert_eq!(HISTORY + 4, player.current);
+        assert!(player
+            .status()
+            .starts_with(&format!("frame {}/{}", HISTORY + 5, HISTORY + 5)));
+        player.apply(Action::First);
+        assert_eq!("step 5", player.frame().unwrap().caption);
+        player.apply(Action::Back);
+        assert_eq!(5, player.current);
+
+        player.apply(Action::Faster);
+        assert_eq!(DEFAULT_DELAY / 2, player.delay);
+        for _ in 0..20 {
+            player.apply(Action::Slower);
+        }
+        assert_eq!(MAX_DELAY, player.delay);
+        assert_eq!(
+            Some(Action::Forward),
+            Action::from_key(KeyEvent::new(KeyCode::Right, KeyModifiers::NONE))
+        );
+
+        // the CRT only draws part 2, so that is all there is to watch without -p
+        let day = registry::find("10").unwrap();
+        let parsed = day.solver.parse("noop\naddx 3\naddx -5").unwrap();
+        let parts: Vec<u8> = visualizations(day, &*parsed, None)
+            .unwrap()
+            .into_iter()
+            .map(|(part, _)| part)
+            .collect();
+        assert_eq!(vec![2], parts);
+        let error = visualizations(day, &*parsed, Some(1)).err().unwrap();
+        assert_eq!("day 10 part 1 has no visualisation", error.to_string());
+        let day = registry::find("1").unwrap();
+        let parsed = day.solver.parse("1000").unwrap();
+        let error = visualizations(day, &*parsed, None).err().unwrap();
+        assert_eq!("day 1 has no visualisation", error.to_string());
+    }
+}